@@ -0,0 +1,4 @@
+//! Fleet policy server: signed releases per host group and the compliance reports agents send back
+
+pub mod server;
+pub mod store;
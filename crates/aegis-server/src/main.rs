@@ -0,0 +1,185 @@
+use aegis_server::server;
+use aegis_server::store::FleetStore;
+use clap::{Parser, Subcommand};
+use colored::*;
+use cyberwall_core::fleet::{DEFAULT_FLEET_ADDR, FLEET_TOKEN_ENV};
+use cyberwall_core::signing;
+use cyberwall_core::{EngineError, EngineResult, FirewallPolicy};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Parser)]
+#[command(name = "aegis-server")]
+#[command(author = "Split2ops Software <support@split2ops.com>")]
+#[command(version = "1.0.0")]
+#[command(about = "S2O Aegis Fleet Server: Central, Signed Firewall Policy Distribution for aegisd Agents", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Generate the release signing key; the public half (<out>.pub) goes to every agent
+    Keygen {
+        /// Signing key file to create
+        #[arg(long, default_value = "/etc/aegis-server/release.key")]
+        out: PathBuf,
+    },
+    /// Serve policy releases to agents and collect their compliance reports
+    Serve {
+        /// Listen address; agents send $AEGIS_FLEET_TOKEN as a bearer token when it is set
+        #[arg(short, long, default_value = DEFAULT_FLEET_ADDR)]
+        listen: String,
+        /// Fleet database (defaults to $AEGIS_FLEET_DB, then /var/lib/aegis-server/fleet.db)
+        #[arg(long, value_name = "PATH")]
+        db: Option<PathBuf>,
+    },
+    /// Sign a firewall policy and publish it as the next version for a host group
+    Publish {
+        /// Policy JSON file
+        file: PathBuf,
+        /// Host group the release is for
+        #[arg(short, long)]
+        group: String,
+        /// Release signing key
+        #[arg(long, default_value = "/etc/aegis-server/release.key")]
+        key: PathBuf,
+        /// Fleet database (defaults to $AEGIS_FLEET_DB, then /var/lib/aegis-server/fleet.db)
+        #[arg(long, value_name = "PATH")]
+        db: Option<PathBuf>,
+    },
+    /// List published versions of a host group
+    History {
+        #[arg(short, long)]
+        group: String,
+        /// Fleet database (defaults to $AEGIS_FLEET_DB, then /var/lib/aegis-server/fleet.db)
+        #[arg(long, value_name = "PATH")]
+        db: Option<PathBuf>,
+    },
+    /// Show the latest compliance report from every host
+    Hosts {
+        /// Output the reports as JSON
+        #[arg(long)]
+        json: bool,
+        /// Fleet database (defaults to $AEGIS_FLEET_DB, then /var/lib/aegis-server/fleet.db)
+        #[arg(long, value_name = "PATH")]
+        db: Option<PathBuf>,
+    },
+}
+
+fn open_store(path: Option<PathBuf>) -> EngineResult<FleetStore> {
+    match path {
+        Some(path) => FleetStore::open(&path),
+        None => FleetStore::open_default(),
+    }
+}
+
+fn timestamp(secs: u64) -> String {
+    chrono::DateTime::from_timestamp(secs as i64, 0).map(|t| t.format("%Y-%m-%d %H:%M:%SZ").to_string()).unwrap_or_else(|| secs.to_string())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Commands::Keygen { out } => {
+            let key = signing::generate_key();
+            signing::write_keypair(&out, &key)?;
+            println!("{}", format!("[AEGIS-SERVER] Wrote signing key {} (key id {})", out.display(), signing::key_id(&key.verifying_key())).green().bold());
+            println!("Distribute {} to agents (aegisd start --fleet-key)", out.with_extension("pub").display());
+        }
+        Commands::Serve { listen, db } => {
+            let store = Arc::new(open_store(db)?);
+            let token = std::env::var(FLEET_TOKEN_ENV).ok();
+            println!("{}", "=========================================================".cyan());
+            println!("{}", "        STARTING SPLIT2OPS AEGIS FLEET POLICY SERVER     ".bold().green());
+            println!("{}", "=========================================================".cyan());
+            println!("[AEGIS-SERVER] Database: {}", store.path().display());
+            println!("[AEGIS-SERVER] Listening on http://{} ({})", listen, if token.is_some() { "token required" } else { "no token" });
+            tokio::select! {
+                res = server::serve(&listen, store, token) => res?,
+                _ = tokio::signal::ctrl_c() => println!("\n[AEGIS-SERVER] Shutting down."),
+            }
+        }
+        Commands::Publish { file, group, key, db } => {
+            let raw = std::fs::read_to_string(&file).map_err(|e| EngineError(format!("Failed to read {}: {}", file.display(), e)))?;
+            let policy: FirewallPolicy =
+                serde_json::from_str(&raw).map_err(|e| EngineError(format!("{} is not a firewall policy: {}", file.display(), e)))?;
+            let key = signing::load_secret(&key)?;
+            let signed = open_store(db)?.publish(&group, policy, &key)?;
+            let release = &signed.release;
+            println!(
+                "{}",
+                format!(
+                    "[AEGIS-SERVER] Published '{}' v{} ({} rules) to group '{}' as release {} (key id {})",
+                    release.policy.name,
+                    release.policy.version,
+                    release.policy.rules.len(),
+                    release.group,
+                    release.version,
+                    signed.signature.key_id
+                )
+                .green()
+                .bold()
+            );
+        }
+        Commands::History { group, db } => {
+            let history = open_store(db)?.history(&group)?;
+            println!("{}", format!("=== RELEASES FOR GROUP '{}' ===", group).cyan());
+            if history.is_empty() {
+                println!("Nothing published yet.");
+            }
+            for signed in &history {
+                let release = &signed.release;
+                let published = timestamp(release.published_at);
+                println!(
+                    " v{:<4} {}  '{}' v{} ({} rules)  key {}",
+                    release.version,
+                    published,
+                    release.policy.name,
+                    release.policy.version,
+                    release.policy.rules.len(),
+                    signed.signature.key_id
+                );
+            }
+        }
+        Commands::Hosts { json, db } => {
+            let hosts = open_store(db)?.hosts()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&hosts)?);
+                return Ok(());
+            }
+            println!("{}", "=== FLEET HOSTS ===".cyan());
+            if hosts.is_empty() {
+                println!("No agent has reported yet.");
+            }
+            for host in &hosts {
+                let report = &host.report;
+                let state = match (report.compliant, report.applied_version == host.latest_version) {
+                    (true, true) => "COMPLIANT".green().bold(),
+                    (true, false) => "BEHIND".yellow().bold(),
+                    (false, _) => "NON-COMPLIANT".red().bold(),
+                };
+                println!(
+                    " {:<20} {:<12} v{}/{}  {:<14} last report {}",
+                    report.host.bold(),
+                    report.group,
+                    report.applied_version,
+                    host.latest_version,
+                    state,
+                    timestamp(report.reported_at)
+                );
+                if let Some(error) = &report.error {
+                    println!("   error: {}", error.red());
+                }
+                for item in &report.drift {
+                    println!("   drift: {}", item.yellow());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
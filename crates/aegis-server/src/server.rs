@@ -0,0 +1,130 @@
+use crate::store::FleetStore;
+use bytes::Bytes;
+use cyberwall_core::fleet::ComplianceReport;
+use cyberwall_core::ipc::token_matches;
+use cyberwall_core::{EngineError, EngineResult};
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::Incoming;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+/// Longest long-poll a client may ask for
+const MAX_WAIT: Duration = Duration::from_secs(60);
+/// How often a long-poll re-checks the database for a new release
+const WAIT_STEP: Duration = Duration::from_secs(1);
+/// Largest report body accepted
+const MAX_BODY: usize = 1024 * 1024;
+
+type HttpResponse = Response<Full<Bytes>>;
+
+fn json_response(status: StatusCode, value: &impl serde::Serialize) -> HttpResponse {
+    let body = serde_json::to_vec(value).unwrap_or_default();
+    let mut resp = Response::new(Full::new(Bytes::from(body)));
+    *resp.status_mut() = status;
+    resp.headers_mut().insert(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static("application/json"));
+    resp
+}
+
+fn error_response(status: StatusCode, message: impl Into<String>) -> HttpResponse {
+    json_response(status, &serde_json::json!({ "error": message.into() }))
+}
+
+fn query_param<T: std::str::FromStr>(query: Option<&str>, name: &str) -> Option<T> {
+    query?.split('&').filter_map(|pair| pair.split_once('=')).find(|(k, _)| *k == name).and_then(|(_, v)| v.parse().ok())
+}
+
+struct FleetApi {
+    store: Arc<FleetStore>,
+    token: Option<String>,
+}
+
+impl FleetApi {
+    async fn serve(&self, req: Request<Incoming>) -> HttpResponse {
+        if let Some(token) = &self.token {
+            let presented = req.headers().get(hyper::header::AUTHORIZATION).and_then(|v| v.to_str().ok());
+            if !presented.and_then(|v| v.strip_prefix("Bearer ")).is_some_and(|given| token_matches(token, given)) {
+                return error_response(StatusCode::UNAUTHORIZED, "Missing or wrong fleet token");
+            }
+        }
+        let path: Vec<String> = req.uri().path().trim_matches('/').split('/').map(str::to_string).collect();
+        let segments: Vec<&str> = path.iter().map(String::as_str).collect();
+        let result = match (req.method().clone(), segments.as_slice()) {
+            (Method::GET, ["v1", "groups", group, "policy"]) => {
+                let after = query_param(req.uri().query(), "after").unwrap_or(0);
+                let wait = query_param(req.uri().query(), "wait").map(Duration::from_secs).unwrap_or_default().min(MAX_WAIT);
+                self.policy(group, after, wait).await
+            }
+            (Method::GET, ["v1", "groups", group, "history"]) => self.store.history(group).map(|h| json_response(StatusCode::OK, &h)),
+            (Method::POST, ["v1", "hosts", host, "report"]) => self.report(host, req.into_body()).await,
+            (Method::GET, ["v1", "hosts"]) => self.store.hosts().map(|h| json_response(StatusCode::OK, &h)),
+            (method, _) => return error_response(StatusCode::NOT_FOUND, format!("No route for {} /{}", method, path.join("/"))),
+        };
+        result.unwrap_or_else(|e| error_response(StatusCode::INTERNAL_SERVER_ERROR, e.0))
+    }
+
+    /// Returns the newest release once it is newer than `after`, holding the request open for up to `wait`
+    async fn policy(&self, group: &str, after: u64, wait: Duration) -> EngineResult<HttpResponse> {
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            if let Some(release) = self.store.latest(group)?.filter(|r| r.release.version > after) {
+                return Ok(json_response(StatusCode::OK, &release));
+            }
+            if tokio::time::Instant::now() + WAIT_STEP > deadline {
+                let mut resp = Response::new(Full::new(Bytes::new()));
+                *resp.status_mut() = StatusCode::NOT_MODIFIED;
+                return Ok(resp);
+            }
+            tokio::time::sleep(WAIT_STEP).await;
+        }
+    }
+
+    async fn report(&self, host: &str, body: Incoming) -> EngineResult<HttpResponse> {
+        let body = match Limited::new(body, MAX_BODY).collect().await {
+            Ok(collected) => collected.to_bytes(),
+            Err(e) => return Ok(error_response(StatusCode::PAYLOAD_TOO_LARGE, format!("Failed to read request body: {}", e))),
+        };
+        let report: ComplianceReport = match serde_json::from_slice(&body) {
+            Ok(report) => report,
+            Err(e) => return Ok(error_response(StatusCode::BAD_REQUEST, format!("Invalid compliance report: {}", e))),
+        };
+        if report.host != host {
+            return Ok(error_response(StatusCode::BAD_REQUEST, format!("Report is for host '{}', posted to '{}'", report.host, host)));
+        }
+        self.store.record_report(&report)?;
+        Ok(json_response(StatusCode::OK, &serde_json::json!({ "recorded": true })))
+    }
+}
+
+/// Serves releases to agents and collects their reports; run behind a TLS terminator outside a lab
+pub async fn serve(addr: &str, store: Arc<FleetStore>, token: Option<String>) -> EngineResult<()> {
+    let listener = TcpListener::bind(addr).await.map_err(|e| EngineError(format!("Failed to bind {}: {}", addr, e)))?;
+    serve_listener(listener, store, token).await
+}
+
+/// Serves on an already bound listener
+pub async fn serve_listener(listener: TcpListener, store: Arc<FleetStore>, token: Option<String>) -> EngineResult<()> {
+    let api = Arc::new(FleetApi { store, token });
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                eprintln!("[AEGIS-SERVER] Accept failed: {}", e);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let api = api.clone();
+        tokio::spawn(async move {
+            let service = hyper::service::service_fn(move |req| {
+                let api = api.clone();
+                async move { Ok::<_, std::convert::Infallible>(api.serve(req).await) }
+            });
+            if let Err(e) = hyper::server::conn::http1::Builder::new().serve_connection(TokioIo::new(stream), service).await {
+                eprintln!("[AEGIS-SERVER] Connection from {} ended: {}", peer, e);
+            }
+        });
+    }
+}
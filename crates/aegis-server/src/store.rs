@@ -0,0 +1,132 @@
+use cyberwall_core::fleet::{ComplianceReport, HostStatus, Release, SignedRelease};
+use cyberwall_core::health::unix_now;
+use cyberwall_core::signing::SigningKey;
+use cyberwall_core::state::{db_err, open_database};
+use cyberwall_core::{EngineError, EngineResult, FirewallPolicy};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+pub const DEFAULT_DB_PATH: &str = "/var/lib/aegis-server/fleet.db";
+/// Overrides the default database location
+pub const DB_ENV: &str = "AEGIS_FLEET_DB";
+
+/// Schema history of the fleet database, oldest first
+const MIGRATIONS: &[&str] = &["
+    CREATE TABLE releases (
+        group_name   TEXT NOT NULL,
+        version      INTEGER NOT NULL,
+        published_at INTEGER NOT NULL,
+        signed       TEXT NOT NULL,
+        PRIMARY KEY (group_name, version)
+    );
+    CREATE TABLE hosts (
+        host       TEXT PRIMARY KEY,
+        group_name TEXT NOT NULL,
+        last_seen  INTEGER NOT NULL,
+        report     TEXT NOT NULL
+    );
+"];
+
+fn decode<T: serde::de::DeserializeOwned>(raw: String) -> EngineResult<T> {
+    serde_json::from_str(&raw).map_err(|e| EngineError(format!("Corrupt fleet database row: {}", e)))
+}
+
+fn encode<T: serde::Serialize>(value: &T) -> EngineResult<String> {
+    serde_json::to_string(value).map_err(|e| EngineError(format!("Failed to encode: {}", e)))
+}
+
+/// Published releases per host group and the latest report from every host
+pub struct FleetStore {
+    path: PathBuf,
+    conn: Mutex<Connection>,
+}
+
+impl FleetStore {
+    /// Opens the database named by `$AEGIS_FLEET_DB`, falling back to the system location
+    pub fn open_default() -> EngineResult<Self> {
+        let path = std::env::var(DB_ENV).unwrap_or_else(|_| DEFAULT_DB_PATH.to_string());
+        Self::open(Path::new(&path))
+    }
+
+    pub fn open(path: &Path) -> EngineResult<Self> {
+        let conn = open_database(path, MIGRATIONS)?;
+        Ok(Self { path: path.to_path_buf(), conn: Mutex::new(conn) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Signs `policy` as the next version for `group` and stores it
+    pub fn publish(&self, group: &str, policy: FirewallPolicy, key: &SigningKey) -> EngineResult<SignedRelease> {
+        let mut conn = self.conn();
+        let tx = conn.transaction().map_err(db_err)?;
+        let current: u64 = tx
+            .query_row("SELECT COALESCE(MAX(version), 0) FROM releases WHERE group_name = ?1", params![group], |row| row.get(0))
+            .map_err(db_err)?;
+        let release = Release { group: group.to_string(), version: current + 1, published_at: unix_now(), policy };
+        let signed = release.sign(key)?;
+        tx.execute(
+            "INSERT INTO releases (group_name, version, published_at, signed) VALUES (?1, ?2, ?3, ?4)",
+            params![group, signed.release.version, signed.release.published_at, encode(&signed)?],
+        )
+        .map_err(db_err)?;
+        tx.commit().map_err(db_err)?;
+        Ok(signed)
+    }
+
+    /// Newest release for `group`, if anything was ever published to it
+    pub fn latest(&self, group: &str) -> EngineResult<Option<SignedRelease>> {
+        let raw: Option<String> = self
+            .conn()
+            .query_row(
+                "SELECT signed FROM releases WHERE group_name = ?1 ORDER BY version DESC LIMIT 1",
+                params![group],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(db_err)?;
+        raw.map(decode).transpose()
+    }
+
+    /// Every release for `group`, newest first
+    pub fn history(&self, group: &str) -> EngineResult<Vec<SignedRelease>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT signed FROM releases WHERE group_name = ?1 ORDER BY version DESC").map_err(db_err)?;
+        let rows = stmt.query_map(params![group], |row| row.get::<_, String>(0)).map_err(db_err)?;
+        rows.map(|row| row.map_err(db_err).and_then(decode)).collect()
+    }
+
+    /// Keeps the report as the host's current state, replacing the previous one
+    pub fn record_report(&self, report: &ComplianceReport) -> EngineResult<()> {
+        self.conn()
+            .execute(
+                "INSERT OR REPLACE INTO hosts (host, group_name, last_seen, report) VALUES (?1, ?2, ?3, ?4)",
+                params![report.host, report.group, unix_now(), encode(report)?],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    /// Latest report per host alongside the newest version of its group
+    pub fn hosts(&self) -> EngineResult<Vec<HostStatus>> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT h.report, (SELECT COALESCE(MAX(r.version), 0) FROM releases r WHERE r.group_name = h.group_name)
+                 FROM hosts h ORDER BY h.group_name, h.host",
+            )
+            .map_err(db_err)?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?))).map_err(db_err)?;
+        rows.map(|row| {
+            let (raw, latest_version) = row.map_err(db_err)?;
+            Ok(HostStatus { report: decode(raw)?, latest_version })
+        })
+        .collect()
+    }
+}
@@ -0,0 +1,160 @@
+use crate::log;
+use cyberwall_core::ipc::{serve_connection, Caller, ControlHandler};
+use cyberwall_core::{EngineError, EngineResult};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+/// Pause after a failed accept so a persistent error (e.g. EMFILE) does not spin
+pub(crate) const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Accepts control connections on TCP; every request must carry the shared token
+pub async fn bind_tcp(addr: &str, token: String, handler: Arc<dyn ControlHandler>) -> EngineResult<tokio::task::JoinHandle<()>> {
+    if token.is_empty() {
+        return Err(EngineError("The TCP control listener requires a non-empty token".to_string()));
+    }
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| EngineError(format!("Failed to bind control listener on {}: {}", addr, e)))?;
+    let token: Arc<str> = token.into();
+    Ok(tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    log::error("control", &format!("Control accept failed: {}", e));
+                    tokio::time::sleep(ACCEPT_BACKOFF).await;
+                    continue;
+                }
+            };
+            let (handler, token) = (handler.clone(), token.clone());
+            tokio::spawn(async move {
+                // Anyone holding the shared token looks the same; the peer address is all that tells them apart
+                let caller = Caller { source: "tcp".to_string(), actor: "token holder".to_string(), uid: None, peer: peer.to_string(), role: None };
+                if let Err(e) = serve_connection(stream, handler.as_ref(), Some(&token), &caller).await {
+                    log::warn("control", &format!("Control connection from {} ended: {}", peer, e));
+                }
+            });
+        }
+    }))
+}
+
+#[cfg(unix)]
+pub use unix::{bind_unix, UnixControlSocket};
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use cyberwall_core::audit;
+    use std::fs::DirBuilder;
+    use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+    use std::path::{Path, PathBuf};
+    use tokio::net::{UnixListener, UnixStream};
+
+    /// Unix control socket; access is limited by file permissions, so no token is checked
+    pub struct UnixControlSocket {
+        /// Set when aegisd created the socket file; an activated socket belongs to systemd
+        owned_path: Option<PathBuf>,
+        task: tokio::task::JoinHandle<()>,
+    }
+
+    impl UnixControlSocket {
+        /// Serves a socket passed in by systemd socket activation
+        pub fn activated(listener: std::os::unix::net::UnixListener, handler: Arc<dyn ControlHandler>) -> EngineResult<Self> {
+            listener.set_nonblocking(true).map_err(|e| EngineError(format!("Activated control socket unusable: {}", e)))?;
+            let listener =
+                UnixListener::from_std(listener).map_err(|e| EngineError(format!("Activated control socket unusable: {}", e)))?;
+            Ok(Self { owned_path: None, task: spawn_accept(listener, handler) })
+        }
+    }
+
+    impl Drop for UnixControlSocket {
+        fn drop(&mut self) {
+            self.task.abort();
+            if let Some(path) = &self.owned_path {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    /// Removes a socket file left behind by a daemon that is no longer running
+    async fn clear_stale(path: &Path) -> EngineResult<()> {
+        let Ok(meta) = std::fs::symlink_metadata(path) else {
+            return Ok(());
+        };
+        if !meta.file_type().is_socket() {
+            return Err(EngineError(format!("{} exists and is not a socket", path.display())));
+        }
+        if UnixStream::connect(path).await.is_ok() {
+            return Err(EngineError(format!("Another aegisd is already listening on {}", path.display())));
+        }
+        std::fs::remove_file(path).map_err(|e| EngineError(format!("Failed to remove stale {}: {}", path.display(), e)))
+    }
+
+    pub async fn bind_unix(path: &Path, handler: Arc<dyn ControlHandler>) -> EngineResult<UnixControlSocket> {
+        let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        if !dir.exists() {
+            DirBuilder::new()
+                .recursive(true)
+                .mode(0o700)
+                .create(dir)
+                .map_err(|e| EngineError(format!("Failed to create {}: {}", dir.display(), e)))?;
+        }
+        clear_stale(path).await?;
+        let listener = bind_private(dir, path)?;
+        Ok(UnixControlSocket { owned_path: Some(path.to_path_buf()), task: spawn_accept(listener, handler) })
+    }
+
+    /// Binds in a 0700 staging directory and renames the socket into place once it is 0600,
+    /// so it is never connectable with umask permissions
+    fn bind_private(dir: &Path, path: &Path) -> EngineResult<UnixListener> {
+        let staging = dir.join(format!(".aegisd-bind.{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&staging);
+        DirBuilder::new().mode(0o700).create(&staging).map_err(|e| EngineError(format!("Failed to create {}: {}", staging.display(), e)))?;
+        let staged = staging.join("control.sock");
+        let result = UnixListener::bind(&staged)
+            .map_err(|e| EngineError(format!("Failed to bind control socket {}: {}", path.display(), e)))
+            .and_then(|listener| {
+                std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))
+                    .map_err(|e| EngineError(format!("Failed to restrict {}: {}", path.display(), e)))?;
+                std::fs::rename(&staged, path).map_err(|e| EngineError(format!("Failed to move control socket to {}: {}", path.display(), e)))?;
+                Ok(listener)
+            });
+        let _ = std::fs::remove_dir_all(&staging);
+        result
+    }
+
+    /// Identifies the peer process from its socket credentials
+    fn unix_caller(stream: &UnixStream) -> Caller {
+        let cred = stream.peer_cred().ok();
+        let uid = cred.map(|c| c.uid());
+        let actor = match uid {
+            Some(uid) => audit::user_name(uid).unwrap_or_else(|| format!("uid {}", uid)),
+            None => "unknown".to_string(),
+        };
+        let peer = cred.and_then(|c| c.pid()).map(|pid| format!("pid {}", pid)).unwrap_or_else(|| "local".to_string());
+        Caller { source: "unix".to_string(), actor, uid, peer, role: None }
+    }
+
+    fn spawn_accept(listener: UnixListener, handler: Arc<dyn ControlHandler>) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        log::error("control", &format!("Control accept failed: {}", e));
+                        tokio::time::sleep(ACCEPT_BACKOFF).await;
+                        continue;
+                    }
+                };
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let caller = unix_caller(&stream);
+                    if let Err(e) = serve_connection(stream, handler.as_ref(), None, &caller).await {
+                        log::warn("control", &format!("Control connection ended: {}", e));
+                    }
+                });
+            }
+        })
+    }
+}
@@ -0,0 +1,297 @@
+use crate::deadman::DeadManCheck;
+use crate::drift::DriftCheck;
+use crate::fleet::{FleetCheck, FleetStatus};
+use crate::health::{platform_checks, StateCheck};
+use crate::log;
+use crate::manifest::{Manifest, ReloadReport, SubsystemResult};
+use crate::supervisor::{Supervisor, WorkerState};
+use async_trait::async_trait;
+use cyberwall_core::health::{unix_now, HealthReport, HealthRegistry, HealthState, ModuleHealth};
+use cyberwall_core::audit;
+use cyberwall_core::ipc::{dispatch, Caller, ControlHandler, IpcRequest, IpcResponse};
+use cyberwall_core::signing::SigningConfig;
+use cyberwall_core::state::{AuditEntry, StateStore};
+use cyberwall_core::{EngineResult, FirewallEngine, FirewallPolicy};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// State shared by every control connection
+pub struct Daemon {
+    engine: Arc<dyn FirewallEngine>,
+    health: HealthRegistry,
+    manifest_path: PathBuf,
+    /// Last manifest applied successfully; the lock also serialises reloads
+    manifest: Mutex<Option<Manifest>>,
+    supervisor: Option<Arc<Supervisor>>,
+    state: Option<Arc<StateStore>>,
+    started_at: u64,
+}
+
+fn subsystem(subsystem: &str, ok: bool, detail: String) -> SubsystemResult {
+    SubsystemResult { subsystem: subsystem.to_string(), ok, detail }
+}
+
+/// Subsystem name, whether its section changed, a summary, and the write that delivers it
+type Section<'a> = (&'static str, bool, String, &'a dyn Fn() -> EngineResult<()>);
+
+/// Writes the manifest sections other subsystems read from the state store
+fn deliver_sections(store: &StateStore, previous: &Manifest, next: &Manifest) -> Vec<SubsystemResult> {
+    let sections: [Section; 3] = [
+        (
+            "cyberdns",
+            previous.dns != next.dns,
+            format!("{} blocked domains, {} resolvers", next.dns.blocklist.len(), next.dns.resolvers.len()),
+            &|| store.dns().replace_source("manifest", &next.dns.blocklist).and_then(|_| store.config().set_dns_resolvers(&next.dns.resolvers)),
+        ),
+        ("cyberdefender", previous.scans != next.scans, format!("{} scan schedules", next.scans.len()), &|| store.config().set_scan_schedules(&next.scans)),
+        ("cyberztna", previous.ztna_routes != next.ztna_routes, format!("{} routes", next.ztna_routes.len()), &|| store.config().set_ztna_routes(&next.ztna_routes)),
+    ];
+    let mut results = Vec::new();
+    let mut failed = false;
+    for (name, changed, summary, write) in sections {
+        results.push(match (changed, failed) {
+            (false, _) => subsystem(name, true, format!("{} (unchanged)", summary)),
+            (true, true) => subsystem(name, false, format!("{} not applied after an earlier failure", summary)),
+            (true, false) => match write() {
+                Ok(()) => subsystem(name, true, format!("{} delivered", summary)),
+                Err(e) => {
+                    failed = true;
+                    subsystem(name, false, e.0)
+                }
+            },
+        });
+    }
+    results
+}
+
+impl Daemon {
+    pub fn new(engine: Arc<dyn FirewallEngine>, manifest_path: PathBuf) -> Self {
+        Self {
+            health: platform_checks(engine.clone(), None),
+            engine,
+            manifest_path,
+            manifest: Mutex::new(None),
+            supervisor: None,
+            state: None,
+            started_at: unix_now(),
+        }
+    }
+
+    pub fn with_supervisor(mut self, supervisor: Arc<Supervisor>) -> Self {
+        self.supervisor = Some(supervisor);
+        self
+    }
+
+    pub fn with_state(mut self, store: Arc<StateStore>) -> Self {
+        self.health = platform_checks(self.engine.clone(), Some(store.clone())).register(StateCheck { store: store.clone() });
+        self.state = Some(store);
+        self
+    }
+
+    /// Reports the fleet agent's progress as the "fleet" health module
+    pub fn with_fleet(mut self, status: Arc<FleetStatus>) -> Self {
+        self.health = std::mem::take(&mut self.health).register(FleetCheck { status });
+        self
+    }
+
+    /// Reports drift between the live rules and the last applied policy as the "drift" health module
+    pub fn with_drift(mut self, check: DriftCheck) -> Self {
+        self.health = std::mem::take(&mut self.health).register(check);
+        self
+    }
+
+    /// Reports whether the dead-man switch is armed or has isolated the host as the "deadman" health module
+    pub fn with_deadman(mut self, check: DeadManCheck) -> Self {
+        self.health = std::mem::take(&mut self.health).register(check);
+        self
+    }
+
+    pub fn manifest_path(&self) -> &std::path::Path {
+        &self.manifest_path
+    }
+
+    pub async fn health(&self) -> HealthReport {
+        let mut report = self.health.report().await;
+        let uptime = unix_now().saturating_sub(self.started_at);
+        let workers = self.supervisor.as_ref().map(|s| s.statuses()).unwrap_or_default();
+        let running = workers.iter().filter(|w| w.state == WorkerState::Running).count();
+        let failed: Vec<&str> = workers.iter().filter(|w| w.state == WorkerState::Failed).map(|w| w.name.as_str()).collect();
+        let mut aegisd = match failed.is_empty() {
+            true => ModuleHealth::new("aegisd", HealthState::Healthy, format!("Control API serving, {}/{} workers running", running, workers.len())),
+            false => ModuleHealth::new("aegisd", HealthState::Degraded, format!("Workers failed: {}", failed.join(", "))),
+        };
+        aegisd = aegisd
+            .metric("uptime_seconds", uptime as f64)
+            .metric("workers_running", running as f64)
+            .metric("workers_failed", failed.len() as f64);
+        report.modules.insert(0, aegisd);
+        report
+    }
+
+    async fn apply_firewall(&self, previous: Option<&FirewallPolicy>, next: Option<&FirewallPolicy>) -> SubsystemResult {
+        let result = |ok, detail: String| SubsystemResult { subsystem: "cyberwall".to_string(), ok, detail };
+        let Some(policy) = next else {
+            return result(true, "No firewall section; live rules left untouched".to_string());
+        };
+        if previous == Some(policy) {
+            return result(true, format!("Policy '{}' v{} (unchanged)", policy.name, policy.version));
+        }
+        match self.engine.apply_policy(policy).await {
+            Ok(()) => result(true, format!("Applied policy '{}' v{} ({} rules)", policy.name, policy.version, policy.rules.len())),
+            Err(e) => {
+                // A backend may have failed half way; put the previous policy back
+                let restored = match previous {
+                    Some(old) => match self.engine.apply_policy(old).await {
+                        Ok(()) => format!("; restored '{}' v{}", old.name, old.version),
+                        Err(re) => format!("; restoring '{}' also failed: {}", old.name, re),
+                    },
+                    None => String::new(),
+                };
+                result(false, format!("{}{}", e, restored))
+            }
+        }
+    }
+
+    /// Puts the previous firewall section back after a later subsystem failed; returns a note for the report
+    async fn rollback_firewall(&self, previous: Option<&FirewallPolicy>, next: Option<&FirewallPolicy>) -> String {
+        match (previous, next) {
+            (Some(old), Some(new)) if old != new => match self.engine.apply_policy(old).await {
+                Ok(()) => format!("; rolled back to '{}' v{}", old.name, old.version),
+                Err(e) => format!("; rolling back to '{}' also failed: {}", old.name, e),
+            },
+            (None, Some(_)) => "; no previous firewall section to roll back to, the new policy stays live".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Loads the manifest and applies it; on any failure the previous manifest stays in force
+    pub async fn reload(&self) -> ReloadReport {
+        let mut active = self.manifest.lock().await;
+        let mut report = ReloadReport {
+            path: self.manifest_path.display().to_string(),
+            revision: None,
+            signed_by: None,
+            applied: false,
+            error: None,
+            subsystems: Vec::new(),
+        };
+        let loaded = SigningConfig::load_default().and_then(|signing| Manifest::load(&self.manifest_path, &signing));
+        let next = match loaded {
+            Ok((next, signed_by)) => {
+                report.signed_by = signed_by;
+                next
+            }
+            Err(e) => {
+                report.error = Some(e.0);
+                return report;
+            }
+        };
+        report.revision = Some(next.revision.clone());
+        let previous = active.clone().unwrap_or_default();
+
+        let firewall = self.apply_firewall(previous.firewall.as_ref(), next.firewall.as_ref()).await;
+        let firewall_ok = firewall.ok;
+        report.subsystems.push(firewall);
+        let sections = match (&self.state, firewall_ok) {
+            (Some(store), true) => deliver_sections(store, &previous, &next),
+            // Nothing is delivered after a firewall failure, so the other subsystems keep the previous manifest
+            (_, false) => Vec::new(),
+            (None, true) => [("cyberdns", previous.dns != next.dns), ("cyberdefender", previous.scans != next.scans), ("cyberztna", previous.ztna_routes != next.ztna_routes)]
+                .into_iter()
+                .map(|(name, changed)| match changed {
+                    true => subsystem(name, false, "Not applied: aegisd has no state store to deliver it through".to_string()),
+                    false => subsystem(name, true, "(unchanged)".to_string()),
+                })
+                .collect(),
+        };
+        let sections_ok = sections.iter().all(|s| s.ok);
+        report.subsystems.extend(sections);
+        if firewall_ok && !sections_ok {
+            if let Some(store) = &self.state {
+                // Sections written before the failure go back to the previous manifest's values
+                for undone in deliver_sections(store, &next, &previous).into_iter().filter(|s| !s.ok) {
+                    log::error("reload", &format!("Failed to restore {} after a failed reload: {}", undone.subsystem, undone.detail));
+                }
+            }
+            let rollback = self.rollback_firewall(previous.firewall.as_ref(), next.firewall.as_ref()).await;
+            if let Some(result) = report.subsystems.first_mut() {
+                result.detail.push_str(&rollback);
+            }
+        }
+
+        report.applied = report.subsystems.iter().all(|s| s.ok);
+        if report.applied {
+            *active = Some(next);
+        } else {
+            report.error = Some("One or more subsystems failed; the previous manifest remains active".to_string());
+        }
+        report
+    }
+}
+
+/// Arguments of a control call as kept in the audit log; policies are summarised rather than stored whole
+pub fn request_args(request: &IpcRequest) -> Vec<String> {
+    let policy = |p: &FirewallPolicy| vec![p.name.clone(), format!("v{}", p.version), format!("{} rules", p.rules.len())];
+    match request {
+        IpcRequest::ApplyPolicy { policy: p } => policy(p),
+        IpcRequest::ApplySignedPolicy { signed } => {
+            let mut args = serde_json::from_str::<FirewallPolicy>(&signed.document).map(|p| policy(&p)).unwrap_or_default();
+            args.push(format!("key {}", signed.signature.key_id));
+            args
+        }
+        other => match serde_json::to_value(other) {
+            Ok(serde_json::Value::Object(map)) => map.get("params").map(|p| vec![p.to_string()]).unwrap_or_default(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+#[async_trait]
+impl ControlHandler for Daemon {
+    async fn handle(&self, request: IpcRequest) -> IpcResponse {
+        match request {
+            IpcRequest::Health => IpcResponse::from_result(EngineResult::Ok(self.health().await)),
+            IpcRequest::Reload => IpcResponse::from_result(EngineResult::Ok(self.reload().await)),
+            IpcRequest::Workers => {
+                let workers = self.supervisor.as_ref().map(|s| s.statuses()).unwrap_or_default();
+                IpcResponse::from_result(EngineResult::Ok(workers))
+            }
+            other => dispatch(self.engine.as_ref(), other).await,
+        }
+    }
+
+    /// Runs the call, recording state-changing ones with the firewall state before and after
+    async fn handle_from(&self, caller: &Caller, request: IpcRequest) -> IpcResponse {
+        let Some(store) = self.state.as_ref().filter(|_| request.is_mutating()) else {
+            return self.handle(request).await;
+        };
+        let (action, args) = (request.method(), request_args(&request));
+        let before = self.engine.get_status().await.ok().map(|s| audit::status_summary(&s));
+        let response = self.handle(request).await;
+        let after = self.engine.get_status().await.ok().map(|s| audit::status_summary(&s));
+        let (ok, detail) = match &response {
+            IpcResponse::Ok { .. } => (true, "ok".to_string()),
+            IpcResponse::Error { message } => (false, message.clone()),
+        };
+        let entry = AuditEntry {
+            at: unix_now(),
+            source: caller.source.clone(),
+            actor: caller.actor.clone(),
+            uid: caller.uid,
+            role: caller.role.clone(),
+            peer: caller.peer.clone(),
+            action: action.to_string(),
+            args,
+            before,
+            after,
+            ok,
+            detail,
+            ..Default::default()
+        };
+        if let Err(e) = store.audit().record(&entry) {
+            log::error("audit", &format!("Failed to record audit entry for {} by {}: {}", action, caller.actor, e));
+        }
+        response
+    }
+}
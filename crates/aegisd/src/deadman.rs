@@ -0,0 +1,147 @@
+use crate::daemon::Daemon;
+use crate::fleet::FleetStatus;
+use crate::log::{self, Priority};
+use async_trait::async_trait;
+use cyberwall_core::health::{unix_now, HealthCheck, HealthState, ModuleHealth};
+use cyberwall_core::ipc::{Caller, ControlHandler, IpcRequest, IpcResponse};
+use cyberwall_core::{EmergencyMode, EmergencyPeer, EngineError, EngineResult};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Shortest allowed timeout; a healthy agent can go a long-poll plus a retry without contact
+pub const MIN_DEAD_MAN: Duration = Duration::from_secs(60);
+/// How often the time since the last fleet contact is checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// When to lock the host and what stays reachable once it is locked
+pub struct DeadManConfig {
+    pub after: Duration,
+    /// Management channel kept open by the isolation, including the fleet server itself
+    pub management: Vec<EmergencyPeer>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DeadManState {
+    /// When the switch isolated the host; cleared once the fleet server is heard from again
+    pub tripped_at: Option<u64>,
+    pub trips: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct DeadManMonitor(Mutex<DeadManState>);
+
+impl DeadManMonitor {
+    pub fn snapshot(&self) -> DeadManState {
+        self.0.lock().unwrap_or_else(|p| p.into_inner()).clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut DeadManState)) {
+        f(&mut self.0.lock().unwrap_or_else(|p| p.into_inner()));
+    }
+}
+
+/// Resolves the host and port of the fleet server URL so the isolation keeps it reachable
+pub async fn fleet_peers(url: &str) -> EngineResult<Vec<EmergencyPeer>> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| EngineError(format!("Invalid fleet URL '{}': {}", url, e)))?;
+    let host = parsed.host_str().ok_or_else(|| EngineError(format!("Fleet URL '{}' has no host", url)))?;
+    let port = parsed.port_or_known_default().ok_or_else(|| EngineError(format!("Fleet URL '{}' has no port", url)))?;
+    let addrs = tokio::net::lookup_host((host.trim_start_matches('[').trim_end_matches(']'), port))
+        .await
+        .map_err(|e| EngineError(format!("Failed to resolve fleet server {}: {}", host, e)))?;
+    let mut peers: Vec<EmergencyPeer> = Vec::new();
+    for addr in addrs {
+        let peer = EmergencyPeer { address: addr.ip().to_string(), port: Some(port) };
+        if !peers.contains(&peer) {
+            peers.push(peer);
+        }
+    }
+    Ok(peers)
+}
+
+/// Isolates the host through the daemon, so the lockdown lands in the audit log like any other call
+async fn trip(config: &DeadManConfig, daemon: &Daemon, monitor: &DeadManMonitor, silent: u64) {
+    let caller = Caller { source: "deadman".to_string(), actor: "aegisd".to_string(), uid: None, peer: "local".to_string(), role: None };
+    let request = IpcRequest::SetEmergency {
+        mode: Some(EmergencyMode::Isolate { management: config.management.clone() }),
+        reason: Some(format!("no fleet contact for {}s", silent)),
+    };
+    match daemon.handle_from(&caller, request).await {
+        IpcResponse::Ok { .. } => {
+            log::event(
+                Priority::Warning,
+                "deadman",
+                &format!("No fleet contact for {}s; host isolated except the management channel until break-glass", silent),
+                &[("SILENT_SECONDS", &silent.to_string())],
+            );
+            monitor.update(|s| {
+                s.tripped_at = Some(unix_now());
+                s.trips += 1;
+                s.last_error = None;
+            });
+        }
+        IpcResponse::Error { message } => {
+            log::error("deadman", &format!("Failed to isolate the host after {}s without fleet contact: {}", silent, message));
+            monitor.update(|s| s.last_error = Some(message));
+        }
+    }
+}
+
+/// Isolates the host once the fleet server has been silent for `config.after`, counting from startup until the first contact
+///
+/// The isolation stays until an operator breaks glass; the switch re-arms once the server is heard from after the trip.
+pub async fn watch(config: DeadManConfig, daemon: Arc<Daemon>, fleet: Arc<FleetStatus>, monitor: Arc<DeadManMonitor>) {
+    let started = unix_now();
+    let mut tick = tokio::time::interval(CHECK_INTERVAL);
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tick.tick().await;
+        let last_contact = fleet.snapshot().last_contact.unwrap_or(started).max(started);
+        match monitor.snapshot().tripped_at {
+            Some(tripped) if last_contact > tripped => {
+                log::info("deadman", "Fleet contact restored; dead-man switch re-armed (isolation stays until break-glass)");
+                monitor.update(|s| s.tripped_at = None);
+            }
+            Some(_) => {}
+            None => {
+                let silent = unix_now().saturating_sub(last_contact);
+                if silent >= config.after.as_secs() {
+                    trip(&config, &daemon, &monitor, silent).await;
+                }
+            }
+        }
+    }
+}
+
+/// Dead-man switch is armed and has not locked the host
+pub struct DeadManCheck {
+    pub after: Duration,
+    pub fleet: Arc<FleetStatus>,
+    pub monitor: Arc<DeadManMonitor>,
+}
+
+#[async_trait]
+impl HealthCheck for DeadManCheck {
+    fn module(&self) -> &'static str {
+        "deadman"
+    }
+
+    async fn check(&self) -> ModuleHealth {
+        let state = self.monitor.snapshot();
+        let since = self.fleet.snapshot().last_contact.map(|t| unix_now().saturating_sub(t));
+        let (health, detail) = match (state.tripped_at, since) {
+            (Some(at), _) => (HealthState::Degraded, format!("Tripped at {}: host isolated until break-glass", at)),
+            (None, Some(s)) => (HealthState::Healthy, format!("Armed: last fleet contact {}s ago, locks after {}s", s, self.after.as_secs())),
+            (None, None) => (HealthState::Healthy, format!("Armed: waiting for the first fleet contact, locks after {}s", self.after.as_secs())),
+        };
+        let mut health = ModuleHealth::new(self.module(), health, detail)
+            .metric("timeout_seconds", self.after.as_secs() as f64)
+            .metric("tripped", state.tripped_at.is_some() as u8 as f64)
+            .metric("trips", state.trips as f64);
+        if let Some(s) = since {
+            health = health.metric("seconds_since_contact", s as f64);
+        }
+        health.last_error = state.last_error;
+        health
+    }
+}
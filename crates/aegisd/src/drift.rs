@@ -0,0 +1,214 @@
+use crate::log::{self, Priority};
+use async_trait::async_trait;
+use cyberwall_core::drift::{diff_rules, RuleDrift};
+use cyberwall_core::health::{unix_now, HealthCheck, HealthState, ModuleHealth};
+use cyberwall_core::{EmergencyMode, EngineResult, FirewallEngine, FirewallPolicy, FirewallRule, FirewallStatus, NatRule, ProfileType, RuleStats};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Default time between drift checks
+pub const DEFAULT_DRIFT_INTERVAL: u64 = 60;
+
+/// Engine wrapper remembering the last policy applied through aegisd, by any path
+pub struct TrackedEngine {
+    inner: Arc<dyn FirewallEngine>,
+    applied: Mutex<Option<FirewallPolicy>>,
+}
+
+impl TrackedEngine {
+    pub fn new(inner: Arc<dyn FirewallEngine>) -> Self {
+        Self { inner, applied: Mutex::new(None) }
+    }
+
+    /// Starts from a policy applied before aegisd started, e.g. the one restored at boot
+    pub fn seed(&self, policy: FirewallPolicy) {
+        *self.applied.lock().unwrap_or_else(|p| p.into_inner()) = Some(policy);
+    }
+
+    pub fn last_applied(&self) -> Option<FirewallPolicy> {
+        self.applied.lock().unwrap_or_else(|p| p.into_inner()).clone()
+    }
+}
+
+#[async_trait]
+impl FirewallEngine for TrackedEngine {
+    async fn get_status(&self) -> EngineResult<FirewallStatus> {
+        self.inner.get_status().await
+    }
+
+    async fn set_enabled(&self, enabled: bool) -> EngineResult<()> {
+        self.inner.set_enabled(enabled).await
+    }
+
+    async fn set_profile_enabled(&self, profile: ProfileType, enabled: bool) -> EngineResult<()> {
+        self.inner.set_profile_enabled(profile, enabled).await
+    }
+
+    async fn set_outbound_block(&self, blocked: bool) -> EngineResult<()> {
+        self.inner.set_outbound_block(blocked).await
+    }
+
+    async fn set_emergency(&self, mode: Option<&EmergencyMode>) -> EngineResult<()> {
+        self.inner.set_emergency(mode).await
+    }
+
+    async fn list_rules(&self) -> EngineResult<Vec<FirewallRule>> {
+        self.inner.list_rules().await
+    }
+
+    async fn rule_stats(&self) -> EngineResult<Vec<RuleStats>> {
+        self.inner.rule_stats().await
+    }
+
+    async fn list_nat_rules(&self) -> EngineResult<Vec<NatRule>> {
+        self.inner.list_nat_rules().await
+    }
+
+    async fn add_nat_rule(&self, rule: &NatRule) -> EngineResult<()> {
+        self.inner.add_nat_rule(rule).await
+    }
+
+    async fn remove_nat_rule(&self, name: &str) -> EngineResult<()> {
+        self.inner.remove_nat_rule(name).await
+    }
+
+    async fn apply_policy(&self, policy: &FirewallPolicy) -> EngineResult<()> {
+        self.inner.apply_policy(policy).await?;
+        self.seed(policy.clone());
+        Ok(())
+    }
+}
+
+/// Drift counters shown by `aegisd status`
+#[derive(Debug, Clone, Default)]
+pub struct DriftState {
+    pub checks: u64,
+    /// Times the live rules were found to differ from the policy in a new way
+    pub events: u64,
+    pub heals: u64,
+    pub heal_failures: u64,
+    pub last_check: Option<u64>,
+    /// Differences found by the latest check
+    pub current: Vec<RuleDrift>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct DriftMonitor(Mutex<DriftState>);
+
+impl DriftMonitor {
+    pub fn snapshot(&self) -> DriftState {
+        self.0.lock().unwrap_or_else(|p| p.into_inner()).clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut DriftState)) {
+        f(&mut self.0.lock().unwrap_or_else(|p| p.into_inner()));
+    }
+}
+
+async fn check_once(engine: &TrackedEngine, monitor: &DriftMonitor, heal: bool) {
+    let Some(policy) = engine.last_applied() else {
+        return;
+    };
+    let drift = match engine.list_rules().await {
+        Ok(live) => diff_rules(&policy, &live),
+        Err(e) => {
+            monitor.update(|s| s.last_error = Some(format!("Failed to list rules: {}", e)));
+            return;
+        }
+    };
+    let previous = monitor.snapshot().current;
+    monitor.update(|s| {
+        s.checks += 1;
+        s.last_check = Some(unix_now());
+        s.last_error = None;
+    });
+    if drift.is_empty() {
+        if !previous.is_empty() {
+            log::info("drift", &format!("Live rules match policy '{}' v{} again", policy.name, policy.version));
+        }
+        monitor.update(|s| s.current.clear());
+        return;
+    }
+
+    // The same differences seen again are one ongoing drift, not a new event
+    if drift != previous {
+        let diff = serde_json::to_string(&drift).unwrap_or_default();
+        let summary: Vec<String> = drift.iter().map(ToString::to_string).collect();
+        log::event(
+            Priority::Warning,
+            "drift",
+            &format!("Live rules drifted from policy '{}' v{}: {}", policy.name, policy.version, summary.join("; ")),
+            &[("AEGIS_DRIFT_ITEMS", &drift.len().to_string()), ("AEGIS_DRIFT_DIFF", &diff)],
+        );
+        monitor.update(|s| s.events += 1);
+    }
+    monitor.update(|s| s.current = drift.clone());
+    if !heal {
+        return;
+    }
+    match engine.apply_policy(&policy).await {
+        Ok(()) => {
+            log::info("drift", &format!("Self-heal re-applied policy '{}' v{} ({} differences)", policy.name, policy.version, drift.len()));
+            monitor.update(|s| {
+                s.heals += 1;
+                s.current.clear();
+            });
+        }
+        Err(e) => {
+            log::error("drift", &format!("Self-heal of policy '{}' v{} failed: {}", policy.name, policy.version, e));
+            monitor.update(|s| {
+                s.heal_failures += 1;
+                s.last_error = Some(format!("Self-heal failed: {}", e));
+            });
+        }
+    }
+}
+
+/// Compares the live rules with the last applied policy every `every`, re-applying it when `heal` is set
+pub async fn watch(engine: Arc<TrackedEngine>, monitor: Arc<DriftMonitor>, every: Duration, heal: bool) {
+    let mut tick = tokio::time::interval(every);
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tick.tick().await;
+        check_once(&engine, &monitor, heal).await;
+    }
+}
+
+/// Live firewall rules still match the last applied policy
+pub struct DriftCheck {
+    pub engine: Arc<TrackedEngine>,
+    pub monitor: Arc<DriftMonitor>,
+    pub heal: bool,
+}
+
+#[async_trait]
+impl HealthCheck for DriftCheck {
+    fn module(&self) -> &'static str {
+        "drift"
+    }
+
+    async fn check(&self) -> ModuleHealth {
+        let state = self.monitor.snapshot();
+        let mode = if self.heal { "self-heal on" } else { "self-heal off" };
+        let (health, detail) = match (self.engine.last_applied(), state.last_check) {
+            (None, _) => (HealthState::Unknown, "No policy applied through aegisd yet".to_string()),
+            (Some(_), None) => (HealthState::Unknown, format!("Waiting for the first check ({})", mode)),
+            (Some(p), Some(_)) if state.current.is_empty() => {
+                (HealthState::Healthy, format!("Live rules match policy '{}' v{} ({})", p.name, p.version, mode))
+            }
+            (Some(p), Some(_)) => (
+                HealthState::Degraded,
+                format!("{} differences from policy '{}' v{}: {}", state.current.len(), p.name, p.version, state.current[0]),
+            ),
+        };
+        let mut health = ModuleHealth::new(self.module(), health, detail)
+            .metric("checks", state.checks as f64)
+            .metric("drift_events", state.events as f64)
+            .metric("drifted_items", state.current.len() as f64)
+            .metric("heals", state.heals as f64)
+            .metric("heal_failures", state.heal_failures as f64);
+        health.last_error = state.last_error;
+        health
+    }
+}
@@ -0,0 +1,399 @@
+use crate::log::{self, Priority};
+use async_trait::async_trait;
+use cyberwall_core::drift::diff_rules;
+use cyberwall_core::fleet::{ComplianceReport, SignedRelease, FLEET_TOKEN_ENV};
+use cyberwall_core::health::{unix_now, HealthCheck, HealthState, ModuleHealth};
+use cyberwall_core::signing::VerifyingKey;
+use cyberwall_core::state::StateStore;
+use cyberwall_core::{EngineError, EngineResult, FirewallEngine, FirewallPolicy};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long each long-poll asks the server to hold the request
+const POLL_WAIT: Duration = Duration::from_secs(30);
+/// Pause before retrying after the server could not be reached
+const RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// Where to pull policy from and how to check it
+pub struct FleetConfig {
+    /// Base URL of the aegis-server, e.g. http://fleet.internal:5190
+    pub url: String,
+    pub group: String,
+    pub host: String,
+    /// Public half of the server's release signing key
+    pub key: VerifyingKey,
+}
+
+/// Agent progress, shared with the health report
+#[derive(Debug, Clone, Default)]
+pub struct FleetState {
+    pub last_contact: Option<u64>,
+    pub applied_version: u64,
+    pub compliant: bool,
+    pub drift: Vec<String>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct FleetStatus(Mutex<FleetState>);
+
+impl FleetStatus {
+    pub fn snapshot(&self) -> FleetState {
+        self.0.lock().unwrap_or_else(|p| p.into_inner()).clone()
+    }
+
+    fn update(&self, f: impl FnOnce(&mut FleetState)) {
+        f(&mut self.0.lock().unwrap_or_else(|p| p.into_inner()));
+    }
+}
+
+/// Name this host reports under when none is given
+pub fn default_host_id() -> String {
+    std::fs::read_to_string("/etc/hostname")
+        .ok()
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+struct Agent {
+    config: FleetConfig,
+    engine: Arc<dyn FirewallEngine>,
+    status: Arc<FleetStatus>,
+    /// Keeps the applied version across restarts, so an older signed release is never taken back
+    store: Arc<StateStore>,
+    client: reqwest::Client,
+    token: Option<String>,
+    applied: Option<(u64, FirewallPolicy)>,
+}
+
+impl Agent {
+    fn new(config: FleetConfig, engine: Arc<dyn FirewallEngine>, status: Arc<FleetStatus>, store: Arc<StateStore>, token: Option<String>) -> EngineResult<Self> {
+        let applied = store.fleet().applied(&config.group)?;
+        if let Some((version, _)) = &applied {
+            status.update(|s| s.applied_version = *version);
+        }
+        Ok(Self { config, engine, status, store, client: reqwest::Client::new(), token, applied })
+    }
+
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Long-polls for a release newer than the applied one
+    async fn fetch(&self) -> EngineResult<Option<SignedRelease>> {
+        let after = self.applied.as_ref().map(|(v, _)| *v).unwrap_or(0);
+        let url = format!("{}/v1/groups/{}/policy", self.config.url.trim_end_matches('/'), self.config.group);
+        let response = self
+            .authorized(self.client.get(&url))
+            .query(&[("after", after), ("wait", POLL_WAIT.as_secs())])
+            .timeout(POLL_WAIT + Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| EngineError(format!("Fleet server unreachable: {}", e)))?;
+        match response.status() {
+            reqwest::StatusCode::NOT_MODIFIED => Ok(None),
+            status if status.is_success() => {
+                response.json().await.map(Some).map_err(|e| EngineError(format!("Invalid release from fleet server: {}", e)))
+            }
+            status => Err(EngineError(format!("Fleet server answered {}: {}", status, response.text().await.unwrap_or_default()))),
+        }
+    }
+
+    /// Verifies and applies a release; an older or equal version is refused as a rollback
+    async fn apply(&mut self, signed: SignedRelease) -> EngineResult<()> {
+        let release = signed.verify(&self.config.key, &self.config.group)?;
+        let applied = self.applied.as_ref().map(|(v, _)| *v).unwrap_or(0);
+        if release.version <= applied {
+            return Err(EngineError(format!("Refusing release v{}: v{} is already applied", release.version, applied)));
+        }
+        self.engine.apply_policy(&release.policy).await?;
+        log::event(
+            Priority::Info,
+            "fleet",
+            &format!("Applied release v{} of group '{}' (policy '{}' v{})", release.version, release.group, release.policy.name, release.policy.version),
+            &[("AEGIS_FLEET_VERSION", &release.version.to_string())],
+        );
+        self.applied = Some((release.version, release.policy.clone()));
+        self.store
+            .fleet()
+            .record_applied(&release.group, release.version, &release.policy)
+            .map_err(|e| EngineError(format!("Applied release v{} but could not record it as the rollback floor: {}", release.version, e)))
+    }
+
+    async fn report(&self, error: Option<String>) -> EngineResult<()> {
+        let (applied_version, drift) = match &self.applied {
+            Some((version, policy)) => match self.engine.list_rules().await {
+                Ok(live) => (*version, diff_rules(policy, &live).iter().map(ToString::to_string).collect()),
+                Err(e) => (*version, vec![format!("cannot list live rules: {}", e)]),
+            },
+            None => (0, Vec::new()),
+        };
+        let report = ComplianceReport {
+            host: self.config.host.clone(),
+            group: self.config.group.clone(),
+            applied_version,
+            compliant: error.is_none() && drift.is_empty() && self.applied.is_some(),
+            drift,
+            error,
+            reported_at: unix_now(),
+        };
+        self.status.update(|s| {
+            s.applied_version = report.applied_version;
+            s.compliant = report.compliant;
+            s.drift = report.drift.clone();
+            s.last_error = report.error.clone();
+        });
+        let url = format!("{}/v1/hosts/{}/report", self.config.url.trim_end_matches('/'), self.config.host);
+        let response = self
+            .authorized(self.client.post(&url))
+            .json(&report)
+            .send()
+            .await
+            .map_err(|e| EngineError(format!("Failed to send compliance report: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(EngineError(format!("Fleet server refused compliance report: {}", response.status())));
+        }
+        self.status.update(|s| s.last_contact = Some(unix_now()));
+        Ok(())
+    }
+
+    /// One poll: fetch, apply anything new, then report
+    async fn cycle(&mut self) -> EngineResult<()> {
+        let error = match self.fetch().await? {
+            Some(signed) => self.apply(signed).await.err().map(|e| {
+                log::error("fleet", &format!("Release rejected: {}", e));
+                e.0
+            }),
+            None => None,
+        };
+        let failed = error.is_some();
+        self.report(error).await?;
+        if failed {
+            // The server keeps offering the same release; do not hammer it
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+        Ok(())
+    }
+}
+
+/// Pulls releases for the host group forever, reporting compliance after every poll
+pub async fn run(config: FleetConfig, engine: Arc<dyn FirewallEngine>, status: Arc<FleetStatus>, store: Arc<StateStore>) {
+    let mut agent = match Agent::new(config, engine, status.clone(), store, std::env::var(FLEET_TOKEN_ENV).ok()) {
+        Ok(agent) => agent,
+        Err(e) => {
+            // Without the recorded version any older release would be accepted
+            log::error("fleet", &format!("Not pulling policy: cannot read the applied release: {}", e));
+            status.update(|s| s.last_error = Some(e.0));
+            return;
+        }
+    };
+    log::info("fleet", &format!("Pulling policy for group '{}' from {} as host '{}'", agent.config.group, agent.config.url, agent.config.host));
+    loop {
+        if let Err(e) = agent.cycle().await {
+            log::warn("fleet", &e.0);
+            agent.status.update(|s| s.last_error = Some(e.0));
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+    }
+}
+
+/// Fleet agent is in touch with the server and the host matches the release
+pub struct FleetCheck {
+    pub status: Arc<FleetStatus>,
+}
+
+#[async_trait]
+impl HealthCheck for FleetCheck {
+    fn module(&self) -> &'static str {
+        "fleet"
+    }
+
+    async fn check(&self) -> ModuleHealth {
+        let state = self.status.snapshot();
+        let since = state.last_contact.map(|t| unix_now().saturating_sub(t));
+        let stale = (POLL_WAIT + RETRY_DELAY).as_secs() * 3;
+        let (health, detail) = match since {
+            None => (HealthState::Down, "Fleet server not reached yet".to_string()),
+            Some(s) if s > stale => (HealthState::Degraded, format!("No contact with the fleet server for {}s", s)),
+            Some(_) if state.applied_version == 0 && state.last_error.is_none() => {
+                (HealthState::Degraded, "Waiting for the first release of the group".to_string())
+            }
+            Some(_) if !state.compliant => (HealthState::Degraded, format!("Release v{} not compliant", state.applied_version)),
+            Some(_) => (HealthState::Healthy, format!("Release v{} applied and compliant", state.applied_version)),
+        };
+        let mut health = ModuleHealth::new(self.module(), health, detail)
+            .metric("applied_version", state.applied_version as f64)
+            .metric("drift_items", state.drift.len() as f64);
+        if let Some(s) = since {
+            health = health.metric("seconds_since_contact", s as f64);
+        }
+        health.last_error = state.last_error;
+        health
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aegis_server::server::serve_listener;
+    use aegis_server::store::FleetStore;
+    use cyberwall_core::signing::{self, SigningKey};
+    use cyberwall_core::{EmergencyMode, FirewallRule, FirewallStatus, NatRule, PortRange, ProfileType, Protocol, RuleAction, RuleDirection, RuleStats};
+    use std::path::PathBuf;
+
+    const GROUP: &str = "web";
+    const TOKEN: &str = "fleet-secret";
+
+    /// Engine that installs policies in memory
+    #[derive(Default)]
+    struct MemoryEngine(Mutex<Vec<FirewallRule>>);
+
+    fn unused<T>() -> EngineResult<T> {
+        Err(EngineError("not used by the fleet agent".to_string()))
+    }
+
+    #[async_trait]
+    impl FirewallEngine for MemoryEngine {
+        async fn get_status(&self) -> EngineResult<FirewallStatus> {
+            unused()
+        }
+
+        async fn set_enabled(&self, _: bool) -> EngineResult<()> {
+            unused()
+        }
+
+        async fn set_profile_enabled(&self, _: ProfileType, _: bool) -> EngineResult<()> {
+            unused()
+        }
+
+        async fn set_outbound_block(&self, _: bool) -> EngineResult<()> {
+            unused()
+        }
+
+        async fn set_emergency(&self, _: Option<&EmergencyMode>) -> EngineResult<()> {
+            unused()
+        }
+
+        async fn list_rules(&self) -> EngineResult<Vec<FirewallRule>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        async fn rule_stats(&self) -> EngineResult<Vec<RuleStats>> {
+            unused()
+        }
+
+        async fn list_nat_rules(&self) -> EngineResult<Vec<NatRule>> {
+            unused()
+        }
+
+        async fn add_nat_rule(&self, _: &NatRule) -> EngineResult<()> {
+            unused()
+        }
+
+        async fn remove_nat_rule(&self, _: &str) -> EngineResult<()> {
+            unused()
+        }
+
+        async fn apply_policy(&self, policy: &FirewallPolicy) -> EngineResult<()> {
+            *self.0.lock().unwrap() = policy.rules.iter().filter(|r| r.enabled).cloned().collect();
+            Ok(())
+        }
+    }
+
+    struct Fleet {
+        dir: PathBuf,
+        server: Arc<FleetStore>,
+        key: SigningKey,
+        url: String,
+    }
+
+    impl Fleet {
+        /// Starts aegis-server on a free local port with an empty database
+        async fn start(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("aegisd-fleet-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            let server = Arc::new(FleetStore::open(&dir.join("fleet.db")).unwrap());
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            tokio::spawn(serve_listener(listener, server.clone(), Some(TOKEN.to_string())));
+            Self { dir, server, key: signing::generate_key(), url }
+        }
+
+        fn publish(&self, rule: &str) -> SignedRelease {
+            let policy = FirewallPolicy {
+                name: "web".to_string(),
+                version: "1".to_string(),
+                rules: vec![FirewallRule {
+                    protocol: Some(Protocol::Tcp),
+                    local_ports: vec![PortRange { start: 443, end: 443 }],
+                    ..FirewallRule::new(rule, RuleAction::Allow, RuleDirection::Inbound)
+                }],
+                nat_rules: Vec::new(),
+                allow_neighbor_discovery: true,
+            };
+            self.server.publish(GROUP, policy, &self.key).unwrap()
+        }
+
+        /// An agent as aegisd would start it, with its own state database under the test directory
+        fn agent(&self, engine: Arc<dyn FirewallEngine>, token: &str) -> Agent {
+            let config = FleetConfig { url: self.url.clone(), group: GROUP.to_string(), host: "web-01".to_string(), key: self.key.verifying_key() };
+            let store = Arc::new(StateStore::open(&self.dir.join("state.db")).unwrap());
+            Agent::new(config, engine, Arc::new(FleetStatus::default()), store, Some(token.to_string())).unwrap()
+        }
+    }
+
+    impl Drop for Fleet {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn agent_pulls_verifies_applies_and_reports() {
+        let fleet = Fleet::start("pull").await;
+        fleet.publish("https");
+        let engine = Arc::new(MemoryEngine::default());
+        let mut agent = fleet.agent(engine.clone(), TOKEN);
+
+        agent.cycle().await.unwrap();
+
+        assert_eq!(engine.0.lock().unwrap().iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["https"]);
+        let state = agent.status.snapshot();
+        assert_eq!((state.applied_version, state.compliant, state.last_error), (1, true, None));
+        let hosts = fleet.server.hosts().unwrap();
+        assert_eq!(hosts.len(), 1);
+        let report = &hosts[0].report;
+        assert_eq!((report.host.as_str(), report.applied_version, report.compliant, hosts[0].latest_version), ("web-01", 1, true, 1));
+        assert!(report.drift.is_empty(), "{:?}", report.drift);
+    }
+
+    #[tokio::test]
+    async fn agent_refuses_rollback_after_restart() {
+        let fleet = Fleet::start("rollback").await;
+        let old = fleet.publish("https");
+        fleet.publish("https v2");
+        let engine = Arc::new(MemoryEngine::default());
+        fleet.agent(engine.clone(), TOKEN).cycle().await.unwrap();
+
+        let mut restarted = fleet.agent(engine.clone(), TOKEN);
+        assert_eq!(restarted.status.snapshot().applied_version, 2);
+        let err = restarted.apply(old).await.unwrap_err();
+        assert!(err.0.contains("v2 is already applied"), "{}", err);
+        assert_eq!(engine.0.lock().unwrap()[0].name, "https v2");
+    }
+
+    #[tokio::test]
+    async fn server_rejects_wrong_token() {
+        let fleet = Fleet::start("token").await;
+        fleet.publish("https");
+        let err = fleet.agent(Arc::new(MemoryEngine::default()), "fleet-secreT").cycle().await.unwrap_err();
+        assert!(err.0.contains("401"), "{}", err);
+        assert!(fleet.server.hosts().unwrap().is_empty());
+    }
+}
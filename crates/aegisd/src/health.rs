@@ -0,0 +1,234 @@
+use async_trait::async_trait;
+use cyberwall_core::health::{unix_now, HealthCheck, HealthRegistry, HealthState, ModuleHealth};
+use cyberwall_core::metrics::HEARTBEAT_INTERVAL;
+use cyberwall_core::state::StateStore;
+use cyberwall_core::{EngineResult, FirewallEngine};
+use std::sync::Arc;
+
+/// Firewall state as reported by the platform engine
+pub struct FirewallCheck {
+    engine: Arc<dyn FirewallEngine>,
+}
+
+#[async_trait]
+impl HealthCheck for FirewallCheck {
+    fn module(&self) -> &'static str {
+        "cyberwall"
+    }
+
+    async fn check(&self) -> ModuleHealth {
+        let status = match self.engine.get_status().await {
+            Ok(status) => status,
+            Err(e) => {
+                let mut health = ModuleHealth::new(self.module(), HealthState::Down, "Firewall engine unreachable");
+                health.last_error = Some(e.0);
+                return health;
+            }
+        };
+        let profiles_on = status.profiles.iter().filter(|p| p.enabled).count();
+        let (state, detail) = match (status.enabled, profiles_on) {
+            (false, _) => (HealthState::Degraded, "Firewall is disabled".to_string()),
+            (true, 0) => (HealthState::Degraded, "Firewall is running but no profile is enabled".to_string()),
+            (true, n) => (HealthState::Healthy, format!("{} ({}/{} profiles enabled)", status.backend_driver, n, status.profiles.len())),
+        };
+        let mut health = ModuleHealth::new(self.module(), state, detail)
+            .metric("profiles_enabled", profiles_on as f64)
+            .metric("shield_engaged", if status.outbound_blocked { 1.0 } else { 0.0 });
+        match self.engine.list_rules().await {
+            Ok(rules) => health = health.metric("rules", rules.len() as f64),
+            Err(e) => health.last_error = Some(format!("Failed to list rules: {}", e)),
+        }
+        health
+    }
+}
+
+/// A serving subsystem is up while it keeps writing its heartbeat to the state store
+pub struct HeartbeatCheck {
+    module: &'static str,
+    store: Option<Arc<StateStore>>,
+}
+
+#[async_trait]
+impl HealthCheck for HeartbeatCheck {
+    fn module(&self) -> &'static str {
+        self.module
+    }
+
+    async fn check(&self) -> ModuleHealth {
+        let Some(store) = &self.store else {
+            return ModuleHealth::new(self.module, HealthState::Unknown, "No state store to read heartbeats from");
+        };
+        let stale_after = 3 * HEARTBEAT_INTERVAL.as_secs();
+        match store.metrics().last_heartbeat(self.module) {
+            Ok(None) => ModuleHealth::new(self.module, HealthState::Down, "Has never reported a heartbeat"),
+            Ok(Some(0)) => ModuleHealth::new(self.module, HealthState::Down, "Stopped"),
+            Ok(Some(at)) => {
+                let age = unix_now().saturating_sub(at);
+                let health = match age > stale_after {
+                    true => ModuleHealth::new(self.module, HealthState::Down, format!("No heartbeat for {}s", age)),
+                    false => ModuleHealth::new(self.module, HealthState::Healthy, format!("Heartbeat {}s ago", age)),
+                };
+                health.metric("heartbeat_age_seconds", age as f64)
+            }
+            Err(e) => {
+                let mut health = ModuleHealth::new(self.module, HealthState::Unknown, "Cannot read heartbeat");
+                health.last_error = Some(e.0);
+                health
+            }
+        }
+    }
+}
+
+/// A subsystem is up when its network interface exists and is not down
+pub struct InterfaceCheck {
+    module: &'static str,
+    interface: &'static str,
+}
+
+#[async_trait]
+impl HealthCheck for InterfaceCheck {
+    fn module(&self) -> &'static str {
+        self.module
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn check(&self) -> ModuleHealth {
+        let path = format!("/sys/class/net/{}/operstate", self.interface);
+        match tokio::fs::read_to_string(&path).await {
+            // WireGuard links report "unknown" while passing traffic
+            Ok(state) if state.trim() == "down" => {
+                ModuleHealth::new(self.module, HealthState::Degraded, format!("Interface {} is down", self.interface))
+            }
+            Ok(state) => ModuleHealth::new(self.module, HealthState::Healthy, format!("Interface {} is {}", self.interface, state.trim())),
+            Err(_) => ModuleHealth::new(self.module, HealthState::Down, format!("Interface {} does not exist", self.interface)),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn check(&self) -> ModuleHealth {
+        ModuleHealth::new(self.module, HealthState::Unknown, format!("Cannot inspect interface {} on this platform", self.interface))
+    }
+}
+
+/// A subsystem is up when a process with its binary name is running
+pub struct ProcessCheck {
+    module: &'static str,
+}
+
+#[async_trait]
+impl HealthCheck for ProcessCheck {
+    fn module(&self) -> &'static str {
+        self.module
+    }
+
+    #[cfg(target_os = "linux")]
+    async fn check(&self) -> ModuleHealth {
+        let mut running = 0usize;
+        if let Ok(mut dir) = tokio::fs::read_dir("/proc").await {
+            while let Ok(Some(entry)) = dir.next_entry().await {
+                if let Ok(comm) = tokio::fs::read_to_string(entry.path().join("comm")).await {
+                    if comm.trim() == self.module {
+                        running += 1;
+                    }
+                }
+            }
+        }
+        let health = match running {
+            0 => ModuleHealth::new(self.module, HealthState::Down, format!("No {} process is running", self.module)),
+            n => ModuleHealth::new(self.module, HealthState::Healthy, format!("{} process(es) running", n)),
+        };
+        health.metric("processes", running as f64)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    async fn check(&self) -> ModuleHealth {
+        ModuleHealth::new(self.module, HealthState::Unknown, "Process probing is not supported on this platform")
+    }
+}
+
+/// Shared state database is reachable and on the current schema
+pub struct StateCheck {
+    pub store: Arc<StateStore>,
+}
+
+#[async_trait]
+impl HealthCheck for StateCheck {
+    fn module(&self) -> &'static str {
+        "state"
+    }
+
+    async fn check(&self) -> ModuleHealth {
+        let store = &self.store;
+        let now = unix_now();
+        let probe = || -> EngineResult<(u32, u64, usize, usize)> {
+            Ok((store.schema_version()?, store.dns().count()?, store.bans().active(now)?.len(), store.sessions().active(now)?.len()))
+        };
+        match probe() {
+            Ok((version, domains, bans, sessions)) => {
+                ModuleHealth::new(self.module(), HealthState::Healthy, format!("{} (schema v{})", store.path().display(), version))
+                    .metric("blocked_domains", domains as f64)
+                    .metric("active_bans", bans as f64)
+                    .metric("active_sessions", sessions as f64)
+            }
+            Err(e) => {
+                let mut health = ModuleHealth::new(self.module(), HealthState::Down, format!("{} is unreadable", store.path().display()));
+                health.last_error = Some(e.0);
+                health
+            }
+        }
+    }
+}
+
+/// Probes for the nine platform subsystems; serving subsystems are read from their heartbeats in `store`
+pub fn platform_checks(engine: Arc<dyn FirewallEngine>, store: Option<Arc<StateStore>>) -> HealthRegistry {
+    HealthRegistry::new()
+        .register(FirewallCheck { engine })
+        .register(HeartbeatCheck { module: "cyberdns", store: store.clone() })
+        .register(InterfaceCheck { module: "cybermesh", interface: "s2o-mesh0" })
+        .register(ProcessCheck { module: "cyberdefender" })
+        .register(ProcessCheck { module: "cyberedr" })
+        .register(HeartbeatCheck { module: "cybersiem", store })
+        .register(ProcessCheck { module: "cyberintel" })
+        .register(ProcessCheck { module: "cyberid" })
+        .register(ProcessCheck { module: "cyberztna" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempStore(std::path::PathBuf);
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn check(store: &Arc<StateStore>) -> HeartbeatCheck {
+        HeartbeatCheck { module: "cyberdns", store: Some(store.clone()) }
+    }
+
+    #[tokio::test]
+    async fn heartbeat_drives_subsystem_health() {
+        let dir = TempStore(std::env::temp_dir().join(format!("aegisd-heartbeat-{}", std::process::id())));
+        std::fs::create_dir_all(&dir.0).unwrap();
+        let store = Arc::new(StateStore::open(&dir.0.join("state.db")).unwrap());
+
+        assert_eq!(check(&store).check().await.state, HealthState::Down);
+        store.metrics().heartbeat("cyberdns", false).unwrap();
+        assert_eq!(check(&store).check().await.state, HealthState::Healthy);
+
+        let stale = unix_now() - 4 * HEARTBEAT_INTERVAL.as_secs();
+        store.metrics().set(cyberwall_core::metrics::HEARTBEAT_METRIC, "", &cyberwall_core::metrics::labels(&[("subsystem", "cyberdns")]), stale as f64).unwrap();
+        let health = check(&store).check().await;
+        assert_eq!(health.state, HealthState::Down);
+        assert!(health.detail.starts_with("No heartbeat for"), "{}", health.detail);
+
+        store.metrics().heartbeat("cyberdns", true).unwrap();
+        assert_eq!(check(&store).check().await.detail, "Stopped");
+        // Another subsystem's beat says nothing about this one
+        store.metrics().heartbeat("cybersiem", false).unwrap();
+        assert_eq!(check(&store).check().await.state, HealthState::Down);
+    }
+}
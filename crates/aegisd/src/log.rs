@@ -0,0 +1,42 @@
+use crate::systemd;
+
+/// syslog priorities used by the journal
+#[derive(Debug, Clone, Copy)]
+pub enum Priority {
+    Error = 3,
+    Warning = 4,
+    Info = 6,
+}
+
+/// Logs one daemon event; under systemd it goes to the journal with an `AEGIS_SUBSYSTEM` field
+pub fn event(priority: Priority, subsystem: &str, message: &str, extra: &[(&str, &str)]) {
+    if systemd::under_journal() {
+        let level = (priority as u8).to_string();
+        let mut fields = vec![
+            ("MESSAGE", message),
+            ("PRIORITY", level.as_str()),
+            ("SYSLOG_IDENTIFIER", "aegisd"),
+            ("AEGIS_SUBSYSTEM", subsystem),
+        ];
+        fields.extend_from_slice(extra);
+        if systemd::journal_send(&fields).is_ok() {
+            return;
+        }
+    }
+    match priority {
+        Priority::Info => println!("[AEGISD] [{}] {}", subsystem, message),
+        Priority::Warning | Priority::Error => eprintln!("[AEGISD] [{}] {}", subsystem, message),
+    }
+}
+
+pub fn info(subsystem: &str, message: &str) {
+    event(Priority::Info, subsystem, message, &[]);
+}
+
+pub fn warn(subsystem: &str, message: &str) {
+    event(Priority::Warning, subsystem, message, &[]);
+}
+
+pub fn error(subsystem: &str, message: &str) {
+    event(Priority::Error, subsystem, message, &[]);
+}
@@ -1,7 +1,43 @@
-use clap::{Parser, Subcommand};
+mod control;
+mod daemon;
+mod deadman;
+mod drift;
+mod fleet;
+mod health;
+mod log;
+mod manifest;
+mod metrics;
+mod remote;
+mod supervisor;
+mod systemd;
+
+use clap::{Args, Parser, Subcommand};
 use colored::*;
+#[cfg(target_os = "linux")]
+use cyberwall_backend_linux::LinuxFirewallEngine;
+#[cfg(not(target_os = "linux"))]
 use cyberwall_backend_windows::WindowsFirewallEngine;
-use cyberwall_core::FirewallEngine;
+use cyberwall_core::audit::Actor;
+use cyberwall_core::health::{unix_now, HealthReport, HealthState, ModuleHealth};
+use cyberwall_core::ipc::{IpcClient, IpcEndpoint, IpcRequest, DEFAULT_SOCKET_PATH, DEFAULT_TCP_ADDR, TOKEN_ENV};
+use cyberwall_core::state::{AuditEntry, StateSnapshot, StateStore};
+use cyberwall_core::signing::{self, SigningConfig};
+use cyberwall_core::{EmergencyPeer, EngineError, EngineResult, FirewallEngine, FirewallPolicy};
+use daemon::Daemon;
+use deadman::{DeadManCheck, DeadManConfig, DeadManMonitor};
+use drift::{DriftCheck, DriftMonitor, TrackedEngine, DEFAULT_DRIFT_INTERVAL};
+use fleet::{FleetConfig, FleetStatus};
+use log::Priority;
+use manifest::{ReloadReport, MANIFEST_PATH};
+use metrics::{Scraper, DEFAULT_METRICS_ADDR};
+use remote::{RoleMap, TlsFiles, CLIENT_CA_PATH, DEFAULT_REMOTE_ADDR, ROLES_PATH, TLS_CERT_PATH, TLS_KEY_PATH};
+use supervisor::{platform_workers, Supervisor, WorkerState, WorkerStatus};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the manifest file is checked for changes
+const MANIFEST_POLL: Duration = Duration::from_secs(2);
 
 #[derive(Parser)]
 #[command(name = "aegisd")]
@@ -16,11 +52,311 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Start the S2O Aegis Master Service Daemon (Orchestrates Firewall, VPN, AV, EDR, SIEM, DNS, Identity, ZTNA)
-    Start,
-    /// Display status across all 9 S2O Cyber-Ops Platform engines
-    Status,
+    Start {
+        /// Unix domain socket for the local control API
+        #[arg(long, default_value = DEFAULT_SOCKET_PATH)]
+        socket: std::path::PathBuf,
+        /// Also serve the control API on TCP; requests must carry the token from AEGISD_TOKEN
+        #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = DEFAULT_TCP_ADDR)]
+        tcp: Option<String>,
+        /// Enterprise manifest; reloaded on SIGHUP or when the file changes
+        #[arg(long, default_value = MANIFEST_PATH)]
+        manifest: PathBuf,
+        /// Serve the control API only, without starting subsystem workers
+        #[arg(long)]
+        no_workers: bool,
+        /// State database (defaults to $AEGIS_STATE_DB, then /var/lib/aegisd/state.db)
+        #[arg(long, value_name = "PATH")]
+        state: Option<PathBuf>,
+        /// Serve the HTTPS management API with mutual TLS on this address
+        #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = DEFAULT_REMOTE_ADDR)]
+        remote: Option<String>,
+        /// Server certificate chain (PEM) for the management API
+        #[arg(long, default_value = TLS_CERT_PATH)]
+        tls_cert: PathBuf,
+        /// Server private key (PEM) for the management API
+        #[arg(long, default_value = TLS_KEY_PATH)]
+        tls_key: PathBuf,
+        /// CA certificates (PEM) whose client certificates are admitted
+        #[arg(long, default_value = CLIENT_CA_PATH)]
+        client_ca: PathBuf,
+        /// JSON map of client certificate common names to "read_only" or "admin"
+        #[arg(long, default_value = ROLES_PATH)]
+        remote_roles: PathBuf,
+        /// Serve Prometheus metrics over plain HTTP at /metrics on this address
+        #[arg(long, value_name = "ADDR", num_args = 0..=1, default_missing_value = DEFAULT_METRICS_ADDR)]
+        metrics: Option<String>,
+        /// Seconds between comparisons of the live rules with the last applied policy; 0 turns drift checks off
+        #[arg(long, value_name = "SECS", default_value_t = DEFAULT_DRIFT_INTERVAL)]
+        drift_interval: u64,
+        /// Re-apply the last applied policy as soon as drift is found
+        #[arg(long)]
+        self_heal: bool,
+        #[command(flatten)]
+        fleet: Box<FleetArgs>,
+    },
+    /// Back up or restore the persistent state store
+    State {
+        #[command(subcommand)]
+        action: StateAction,
+    },
+    /// Install hardened systemd units (aegisd.service and aegisd.socket)
+    InstallUnit {
+        /// Directory the unit files are written to
+        #[arg(long, default_value = "/etc/systemd/system")]
+        dir: PathBuf,
+        /// aegisd binary referenced by ExecStart (defaults to this executable)
+        #[arg(long)]
+        binary: Option<PathBuf>,
+        /// Control socket systemd listens on
+        #[arg(long, default_value = DEFAULT_SOCKET_PATH)]
+        socket: PathBuf,
+    },
+    /// Display status across all 9 S2O Cyber-Ops Platform engines; exits non-zero unless all are healthy
+    Status {
+        /// Control endpoint of the running daemon (socket path or tcp://host:port)
+        #[arg(long, default_value = DEFAULT_SOCKET_PATH)]
+        daemon: IpcEndpoint,
+        /// Output the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show the subsystem workers supervised by the running daemon
+    Workers {
+        /// Control endpoint of the running daemon (socket path or tcp://host:port)
+        #[arg(long, default_value = DEFAULT_SOCKET_PATH)]
+        daemon: IpcEndpoint,
+        /// Output worker states as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Reload enterprise policy configuration from disk
-    Reload,
+    Reload {
+        /// Control endpoint of the running daemon (socket path or tcp://host:port)
+        #[arg(long, default_value = DEFAULT_SOCKET_PATH)]
+        daemon: IpcEndpoint,
+    },
+}
+
+/// Central policy server this host pulls releases from
+#[derive(Args)]
+struct FleetArgs {
+    /// Pull signed firewall policy releases from this aegis-server URL
+    #[arg(long = "fleet", value_name = "URL", requires = "fleet_group")]
+    url: Option<String>,
+    /// Host group whose releases this host applies
+    #[arg(long, value_name = "GROUP")]
+    fleet_group: Option<String>,
+    /// Public key the fleet server signs releases with
+    #[arg(long, default_value = "/etc/aegisd/fleet-release.pub")]
+    fleet_key: PathBuf,
+    /// Name this host reports compliance under (defaults to the hostname)
+    #[arg(long)]
+    host_id: Option<String>,
+    /// Isolate the host when the fleet server has not been reached for this many seconds
+    #[arg(long = "dead-man", value_name = "SECS", requires = "url")]
+    dead_man: Option<u64>,
+    /// Peer kept reachable when the dead-man switch isolates the host; the fleet server always is (repeatable)
+    #[arg(long, value_name = "PEER", requires = "dead_man")]
+    management: Vec<EmergencyPeer>,
+}
+
+#[derive(Subcommand)]
+enum StateAction {
+    /// Write every table of the state store as a JSON snapshot
+    Export {
+        /// Snapshot file to write (defaults to stdout)
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// State database (defaults to $AEGIS_STATE_DB, then /var/lib/aegisd/state.db)
+        #[arg(long, value_name = "PATH")]
+        state: Option<PathBuf>,
+    },
+    /// Replace the contents of the state store with a JSON snapshot; the append-only audit log is kept
+    Import {
+        /// Snapshot file produced by `aegisd state export`
+        file: PathBuf,
+        /// State database (defaults to $AEGIS_STATE_DB, then /var/lib/aegisd/state.db)
+        #[arg(long, value_name = "PATH")]
+        state: Option<PathBuf>,
+    },
+}
+
+fn open_state(path: Option<PathBuf>) -> EngineResult<StateStore> {
+    match path {
+        Some(path) => StateStore::open(&path),
+        None => StateStore::open_default(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn platform_engine() -> Arc<dyn FirewallEngine> {
+    Arc::new(LinuxFirewallEngine::new())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn platform_engine() -> Arc<dyn FirewallEngine> {
+    Arc::new(WindowsFirewallEngine::new())
+}
+
+/// Policy restored at boot, the drift baseline until aegisd applies one itself
+#[cfg(target_os = "linux")]
+fn saved_policy() -> Option<FirewallPolicy> {
+    let path = std::path::Path::new(cyberwall_backend_linux::boot::SAVED_POLICY_PATH);
+    if !path.exists() {
+        return None;
+    }
+    match cyberwall_backend_linux::boot::load(path) {
+        Ok(saved) => Some(saved.policy),
+        Err(e) => {
+            log::warn("drift", &format!("Ignoring saved policy as drift baseline: {}", e));
+            None
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn saved_policy() -> Option<FirewallPolicy> {
+    None
+}
+
+fn state_label(state: HealthState) -> ColoredString {
+    match state {
+        HealthState::Healthy => "HEALTHY".green().bold(),
+        HealthState::Degraded => "DEGRADED".yellow().bold(),
+        HealthState::Down => "DOWN".red().bold(),
+        HealthState::Unknown => "UNKNOWN".yellow(),
+    }
+}
+
+fn print_reload_report(report: &ReloadReport) {
+    println!("{}", format!("[AEGISD] Manifest {} (revision {})", report.path, report.revision.as_deref().unwrap_or("-")).cyan());
+    if let Some(key) = &report.signed_by {
+        println!("[AEGISD]   signed by {}", key);
+    }
+    for sub in &report.subsystems {
+        let mark = if sub.ok { "OK".green().bold() } else { "FAILED".red().bold() };
+        println!("[AEGISD]   {:<14} {} {}", sub.subsystem, mark, sub.detail);
+    }
+    match (&report.error, report.applied) {
+        (_, true) => println!("{}", "[AEGISD] Manifest applied.".green().bold()),
+        (Some(e), false) => println!("{}", format!("[AEGISD] Manifest rejected, previous manifest kept: {}", e).red().bold()),
+        (None, false) => println!("{}", "[AEGISD] Manifest rejected, previous manifest kept.".red().bold()),
+    }
+}
+
+/// Logs each subsystem outcome of a reload done inside the daemon
+fn log_reload_report(report: &ReloadReport) {
+    let revision = report.revision.clone().unwrap_or_default();
+    for sub in &report.subsystems {
+        let priority = if sub.ok { Priority::Info } else { Priority::Error };
+        log::event(priority, &sub.subsystem, &sub.detail, &[("AEGIS_MANIFEST_REVISION", &revision)]);
+    }
+    match (&report.error, report.applied) {
+        (_, true) => {
+            let signer = report.signed_by.as_deref().map(|k| format!(", signed by {}", k)).unwrap_or_default();
+            log::event(Priority::Info, "manifest", &format!("Manifest {} applied{}", report.path, signer), &[("AEGIS_MANIFEST_REVISION", &revision)])
+        }
+        (error, false) => log::event(
+            Priority::Error,
+            "manifest",
+            &format!("Manifest {} rejected, previous manifest kept: {}", report.path, error.as_deref().unwrap_or("unknown error")),
+            &[("AEGIS_MANIFEST_REVISION", &revision)],
+        ),
+    }
+}
+
+fn print_workers(workers: &[WorkerStatus]) {
+    for w in workers {
+        let state = match w.state {
+            WorkerState::Running => "RUNNING".green().bold(),
+            WorkerState::Waiting => "WAITING".yellow(),
+            WorkerState::Backoff => "BACKOFF".yellow().bold(),
+            WorkerState::Failed => "FAILED".red().bold(),
+            WorkerState::Stopped => "STOPPED".normal(),
+        };
+        let pid = w.pid.map(|p| p.to_string()).unwrap_or_else(|| "-".to_string());
+        println!(" {:<18} {:<8} pid={:<7} restarts={}", w.name.bold(), state, pid, w.restarts);
+        if !w.depends_on.is_empty() {
+            println!("   after: {}", w.depends_on.join(", "));
+        }
+        if let Some(error) = &w.last_error {
+            println!("   last error: {}", error.red());
+        }
+    }
+}
+
+/// Resolves on Ctrl+C, or SIGTERM where available
+async fn shutdown_signal() -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        let mut term = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            res = tokio::signal::ctrl_c() => res,
+            _ = term.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await
+}
+
+#[cfg(unix)]
+async fn hangup(signal: &mut Option<tokio::signal::unix::Signal>) {
+    match signal {
+        Some(signal) => {
+            signal.recv().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+/// Manifest bytes plus its detached signature, so re-signing alone also triggers a reload
+async fn read_manifest(path: &std::path::Path) -> Option<(Vec<u8>, Option<Vec<u8>>)> {
+    let manifest = tokio::fs::read(path).await.ok()?;
+    Some((manifest, tokio::fs::read(signing::signature_path(path)).await.ok()))
+}
+
+/// Reloads the manifest on SIGHUP and whenever its content or signature changes
+async fn watch_manifest(daemon: Arc<Daemon>) {
+    let mut last = read_manifest(daemon.manifest_path()).await;
+    let mut poll = tokio::time::interval(MANIFEST_POLL);
+    #[cfg(unix)]
+    let mut sighup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()).ok();
+    loop {
+        #[cfg(unix)]
+        let trigger = tokio::select! {
+            _ = poll.tick() => "file change",
+            _ = hangup(&mut sighup) => "SIGHUP",
+        };
+        #[cfg(not(unix))]
+        let trigger = {
+            poll.tick().await;
+            "file change"
+        };
+        let current = read_manifest(daemon.manifest_path()).await;
+        // A vanished file is not a new manifest; keep running on the current one
+        if trigger == "file change" && (current.is_none() || current == last) {
+            continue;
+        }
+        last = current;
+        log::info("manifest", &format!("Reloading manifest ({})", trigger));
+        log_reload_report(&daemon.reload().await);
+    }
+}
+
+/// Asks the running daemon for its report, probing locally when it cannot be reached
+async fn health_report(endpoint: IpcEndpoint) -> HealthReport {
+    let client = IpcClient::new(endpoint, std::env::var(TOKEN_ENV).ok());
+    match client.call::<HealthReport>(IpcRequest::Health).await {
+        Ok(report) => report,
+        Err(e) => {
+            let mut report = Daemon::new(platform_engine(), PathBuf::from(MANIFEST_PATH)).health().await;
+            let mut aegisd = ModuleHealth::new("aegisd", HealthState::Down, "Daemon is not running");
+            aegisd.last_error = Some(e.0);
+            report.modules[0] = aegisd;
+            report
+        }
+    }
 }
 
 #[tokio::main]
@@ -28,61 +364,306 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Start => {
+        Commands::Start {
+            socket,
+            tcp,
+            manifest,
+            no_workers,
+            state,
+            remote,
+            tls_cert,
+            tls_key,
+            client_ca,
+            remote_roles,
+            metrics,
+            drift_interval,
+            self_heal,
+            fleet,
+        } => {
+            let tcp_token = tcp
+                .as_ref()
+                .map(|_| std::env::var(TOKEN_ENV))
+                .transpose()
+                .map_err(|_| format!("--tcp requires a shared token in the {} environment variable", TOKEN_ENV))?;
             println!("{}", "=========================================================".cyan());
             println!("{}", "     STARTING SPLIT2OPS AEGIS CYBER-OPS MASTER DAEMON    ".bold().green());
             println!("{}", "=========================================================".cyan());
-            println!("[AEGISD] [1/9] Initializing S2O Cyberwall Engine (WFP/COM)...");
-            let fw = WindowsFirewallEngine::new();
+            let tracked = Arc::new(TrackedEngine::new(platform_engine()));
+            if let Some(policy) = saved_policy() {
+                println!("[AEGISD] Drift baseline: saved policy '{}' v{}", policy.name, policy.version);
+                tracked.seed(policy);
+            }
+            let fw: Arc<dyn FirewallEngine> = tracked.clone();
             let st = fw.get_status().await?;
-            println!("[AEGISD]       -> Cyberwall Status: {}", if st.enabled { "ONLINE (Green)".green().bold() } else { "OFFLINE".red() });
+            println!("[AEGISD] Cyberwall engine: {} ({})", if st.enabled { "ONLINE".green().bold() } else { "OFFLINE".red() }, st.backend_driver);
+
+            let signing_config = SigningConfig::load_default()?;
+            match signing_config.enforce {
+                true => println!(
+                    "[AEGISD] Policy signing: {} ({} trusted keys in {})",
+                    "ENFORCED".green().bold(),
+                    signing_config.trust_store()?.keys.len(),
+                    signing_config.trusted_keys.display()
+                ),
+                false => println!("[AEGISD] Policy signing: {} (unsigned policies accepted)", "not enforced".yellow()),
+            }
+
+            let store = Arc::new(open_state(state)?);
+            println!("[AEGISD] State store: {} (schema v{})", store.path().display(), store.schema_version()?);
 
-            println!("[AEGISD] [2/9] Initializing S2O CyberMesh VPN Tunnel (s2o-mesh0)... ONLINE");
-            println!("[AEGISD] [3/9] Initializing S2O CyberDefender Real-Time Shield... ONLINE");
-            println!("[AEGISD] [4/9] Initializing S2O CyberEDR Kernel ETW Hooks... ONLINE");
-            println!("[AEGISD] [5/9] Initializing S2O CyberLog SIEM 100k EPS Collector... ONLINE");
-            println!("[AEGISD] [6/9] Initializing S2O ThreatGrid IOC Feed Engine... ONLINE");
-            println!("[AEGISD] [7/9] Initializing S2O CyberDNS Encrypted DoH Resolver... ONLINE");
-            println!("[AEGISD] [8/9] Initializing S2O CyberID Zero-Trust Auth Controller... ONLINE");
-            println!("[AEGISD] [9/9] Initializing S2O ZeroTrust SASE Gateway Proxy... ONLINE");
+            let FleetArgs { url: fleet_url, fleet_group, fleet_key, host_id, dead_man, management } = *fleet;
+            let dead_man = match (dead_man.map(Duration::from_secs), &fleet_url) {
+                (Some(after), _) if after < deadman::MIN_DEAD_MAN => {
+                    return Err(format!("--dead-man must be at least {}s", deadman::MIN_DEAD_MAN.as_secs()).into());
+                }
+                (Some(after), Some(url)) => {
+                    let mut management = management;
+                    management.extend(deadman::fleet_peers(url).await?);
+                    Some(DeadManConfig { after, management })
+                }
+                _ => None,
+            };
+            let fleet_config = match fleet_url.zip(fleet_group) {
+                Some((url, group)) => {
+                    let key = signing::load_public(&fleet_key)?;
+                    Some(FleetConfig { url, group, host: host_id.unwrap_or_else(fleet::default_host_id), key })
+                }
+                None => None,
+            };
+            let supervisor = Arc::new(Supervisor::start(if no_workers { Vec::new() } else { platform_workers() })?);
+            let fleet_status = Arc::new(FleetStatus::default());
+            let mut daemon = Daemon::new(fw.clone(), manifest.clone()).with_supervisor(supervisor.clone()).with_state(store.clone());
+            if fleet_config.is_some() {
+                daemon = daemon.with_fleet(fleet_status.clone());
+            }
+            let drift_monitor = Arc::new(DriftMonitor::default());
+            if drift_interval > 0 {
+                daemon = daemon.with_drift(DriftCheck { engine: tracked.clone(), monitor: drift_monitor.clone(), heal: self_heal });
+            }
+            let deadman_monitor = Arc::new(DeadManMonitor::default());
+            if let Some(config) = &dead_man {
+                daemon = daemon.with_deadman(DeadManCheck { after: config.after, fleet: fleet_status.clone(), monitor: deadman_monitor.clone() });
+            }
+            let handler = Arc::new(daemon);
+            if manifest.exists() {
+                log_reload_report(&handler.reload().await);
+            } else {
+                println!("{}", format!("[AEGISD] No manifest at {}; waiting for one to appear", manifest.display()).yellow());
+            }
+            let watcher = tokio::spawn(watch_manifest(handler.clone()));
+            #[cfg(unix)]
+            let (_unix, activated) = match systemd::take_listener() {
+                Some(listener) => (control::UnixControlSocket::activated(listener, handler.clone())?, true),
+                None => (control::bind_unix(&socket, handler.clone()).await?, false),
+            };
+            #[cfg(not(unix))]
+            let _ = &socket;
+            let tcp_task = match tcp.as_deref().zip(tcp_token) {
+                Some((addr, token)) => Some(control::bind_tcp(addr, token, handler.clone()).await?),
+                None => None,
+            };
+            let remote_task = match &remote {
+                Some(addr) => {
+                    let files = TlsFiles { cert: tls_cert, key: tls_key, client_ca };
+                    let roles = RoleMap::load(&remote_roles)?;
+                    Some(remote::bind_remote(addr, &files, roles, handler.clone(), store.clone()).await?)
+                }
+                None => None,
+            };
+            let metrics_task = match &metrics {
+                Some(addr) => {
+                    let scraper = Scraper { engine: fw.clone(), daemon: handler.clone(), store: store.clone() };
+                    Some(metrics::bind_metrics(addr, scraper).await?)
+                }
+                None => None,
+            };
 
             println!("{}", "=========================================================".cyan());
             println!("{}", "  SUCCESS: S2O AEGIS CYBER-OPS SUITE IS FULLY OPERATIONAL".bold().green());
-            println!("{}", "  IPC Listening on: \\\\.\\pipe\\s2o_aegis_ipc / 127.0.0.1:5180".yellow());
+            #[cfg(unix)]
+            match activated {
+                true => println!("{}", "  Control API on: socket passed in by systemd".yellow()),
+                false => println!("{}", format!("  Control API on: unix://{}", socket.display()).yellow()),
+            }
+            if let Some(addr) = &tcp {
+                println!("{}", format!("  Control API on: tcp://{} (token required)", addr).yellow());
+            }
+            if let Some(addr) = &remote {
+                println!("{}", format!("  Management API on: https://{} (mutual TLS)", addr).yellow());
+            }
+            if let Some(addr) = &metrics {
+                println!("{}", format!("  Prometheus metrics on: http://{}/metrics", addr).yellow());
+            }
+            let fleet_task = fleet_config.map(|config| {
+                println!("{}", format!("  Fleet policy from: {} (group '{}', host '{}')", config.url, config.group, config.host).yellow());
+                tokio::spawn(fleet::run(config, fw.clone(), fleet_status.clone(), store.clone()))
+            });
+            let drift_task = (drift_interval > 0).then(|| {
+                let mode = if self_heal { "self-heal on" } else { "report only" };
+                println!("{}", format!("  Drift checks every {}s ({})", drift_interval, mode).yellow());
+                tokio::spawn(drift::watch(tracked.clone(), drift_monitor.clone(), Duration::from_secs(drift_interval), self_heal))
+            });
+            let deadman_task = dead_man.map(|config| {
+                let peers: Vec<String> = config.management.iter().map(|p| p.to_string()).collect();
+                println!("{}", format!("  Dead-man switch: isolate after {}s without fleet contact (keeping {})", config.after.as_secs(), peers.join(", ")).yellow());
+                tokio::spawn(deadman::watch(config, handler.clone(), fleet_status.clone(), deadman_monitor.clone()))
+            });
             println!("{}", "=========================================================".cyan());
 
+            // Give workers a moment to spawn so the first table is meaningful
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            print_workers(&supervisor.statuses());
+
+            let running = supervisor.statuses().iter().filter(|w| w.state == WorkerState::Running).count();
+            systemd::notify(&format!("READY=1\nSTATUS=Serving control API, {} workers running", running));
+            let watchdog = systemd::watchdog_interval().map(|every| {
+                tokio::spawn(async move {
+                    let mut tick = tokio::time::interval(every);
+                    loop {
+                        tick.tick().await;
+                        systemd::notify("WATCHDOG=1");
+                    }
+                })
+            });
+
             println!("\nPress Ctrl+C to terminate S2O Aegis Master Daemon service...");
-            tokio::signal::ctrl_c().await?;
-            println!("\n[AEGISD] Gracefully shutting down all 9 Cyber-Ops subsystem engines...");
+            shutdown_signal().await?;
+            println!("\n[AEGISD] Stopping subsystem workers...");
+            systemd::notify("STOPPING=1\nSTATUS=Stopping subsystem workers");
+            watcher.abort();
+            for task in [tcp_task, remote_task, metrics_task, fleet_task, drift_task, deadman_task].into_iter().flatten() {
+                task.abort();
+            }
+            // Keep pinging the watchdog while workers wind down
+            supervisor.shutdown().await;
+            if let Some(task) = watchdog {
+                task.abort();
+            }
+            print_workers(&supervisor.statuses());
         }
-        Commands::Status => {
-            println!("{}", "=========================================================".cyan());
-            println!("{}", "    SPLIT2OPS AEGIS ENTERPRISE PLATFORM MATRIX STATUS   ".bold().green());
-            println!("{}", "=========================================================".cyan());
-            let modules = vec![
-                ("1. S2O Cyberwall Engine", "cyberwall", "ONLINE (WFP / COM / Netsh)", "ENABLED"),
-                ("2. S2O CyberMesh VPN", "cybermesh", "ONLINE (WireGuard Mesh)", "10.220.0.14"),
-                ("3. S2O CyberDefender AV", "cyberdefender", "ONLINE (YARA + Defender)", "SHIELD ON"),
-                ("4. S2O CyberEDR Agent", "cyberedr", "ONLINE (Kernel ETW / eBPF)", "0 ALERTS"),
-                ("5. S2O CyberLog SIEM", "cybersiem", "ONLINE (EventBus Ringbuffer)", "142 EPS"),
-                ("6. S2O ThreatGrid Intel", "cyberintel", "ONLINE (2.4M IOC Signatures)", "SYNCED"),
-                ("7. S2O CyberDNS Guard", "cyberdns", "ONLINE (Encrypted DoH/DoT)", "FILTER ON"),
-                ("8. S2O CyberID PAM/IAM", "cyberid", "ONLINE (Zero-Trust Auth)", "SCORE 100"),
-                ("9. S2O ZeroTrust Gateway", "cyberztna", "ONLINE (SASE mTLS Proxy)", "3 TUNNELS"),
-            ];
-
-            for (title, _cli, desc, detail) in modules {
-                println!(" Module  : {}", title.bold());
-                println!(" Driver  : {}", desc.yellow());
-                println!(" Metrics : {}", detail.green().bold());
-                println!("{}", "---------------------------------------------------------".cyan());
-            }
-            println!("{}", "   ALL 9 SPLIT2OPS CYBER-OPS MODULES HEALTHY & SYNCHRONIZED".bold().green());
-            println!("{}", "=========================================================".cyan());
+        Commands::InstallUnit { dir, binary, socket } => {
+            let binary = match binary {
+                Some(binary) => binary,
+                None => std::env::current_exe()?,
+            };
+            let written = systemd::install_units(&dir, &binary, &socket).map_err(|e| format!("Failed to write units to {}: {}", dir.display(), e))?;
+            for path in &written {
+                println!("{}", format!("[AEGISD] Wrote {}", path.display()).green());
+            }
+            println!("Enable with: systemctl daemon-reload && systemctl enable --now aegisd.socket aegisd.service");
+        }
+        Commands::State { action } => match action {
+            StateAction::Export { out, state } => {
+                let snapshot = open_state(state)?.export()?;
+                let raw = serde_json::to_string_pretty(&snapshot)?;
+                match out {
+                    Some(path) => {
+                        std::fs::write(&path, raw).map_err(|e| EngineError(format!("Failed to write {}: {}", path.display(), e)))?;
+                        println!(
+                            "{}",
+                            format!(
+                                "[AEGISD] Exported state (schema v{}): {} blocked domains, {} bans, {} scheduled rules, {} scan results, {} sessions, {} audit entries -> {}",
+                                snapshot.schema_version,
+                                snapshot.dns_blocklist.len(),
+                                snapshot.bans.len(),
+                                snapshot.scheduled_rules.len(),
+                                snapshot.scan_results.len(),
+                                snapshot.sessions.len(),
+                                snapshot.audit_log.len(),
+                                path.display()
+                            )
+                            .green()
+                        );
+                    }
+                    None => println!("{}", raw),
+                }
+            }
+            StateAction::Import { file, state } => {
+                let raw = std::fs::read_to_string(&file).map_err(|e| EngineError(format!("Failed to read {}: {}", file.display(), e)))?;
+                let snapshot: StateSnapshot =
+                    serde_json::from_str(&raw).map_err(|e| EngineError(format!("{} is not a state snapshot: {}", file.display(), e)))?;
+                let store = open_state(state)?;
+                let result = store.import(&snapshot);
+                let actor = Actor::current();
+                let entry = AuditEntry {
+                    at: unix_now(),
+                    source: "cli".to_string(),
+                    actor: actor.name,
+                    uid: actor.uid,
+                    peer: "local".to_string(),
+                    action: "state import".to_string(),
+                    args: vec![file.display().to_string()],
+                    ok: result.is_ok(),
+                    detail: result.as_ref().err().map(|e| e.0.clone()).unwrap_or_else(|| "ok".to_string()),
+                    ..Default::default()
+                };
+                store.audit().record(&entry)?;
+                result?;
+                println!(
+                    "{}",
+                    format!(
+                        "[AEGISD] Imported {} (schema v{} into v{}) into {}; the audit log was kept",
+                        file.display(),
+                        snapshot.schema_version,
+                        store.schema_version()?,
+                        store.path().display()
+                    )
+                    .green()
+                    .bold()
+                );
+            }
+        },
+        Commands::Status { daemon, json } => {
+            let report = health_report(daemon).await;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!("{}", "=========================================================".cyan());
+                println!("{}", "    SPLIT2OPS AEGIS ENTERPRISE PLATFORM MATRIX STATUS   ".bold().green());
+                println!("{}", "=========================================================".cyan());
+                for module in &report.modules {
+                    println!(" Module  : {}", module.module.bold());
+                    println!(" State   : {}", state_label(module.state));
+                    println!(" Detail  : {}", module.detail);
+                    if let Some(error) = &module.last_error {
+                        println!(" Error   : {}", error.red());
+                    }
+                    if !module.metrics.is_empty() {
+                        let metrics: Vec<String> = module.metrics.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+                        println!(" Metrics : {}", metrics.join(", "));
+                    }
+                    println!("{}", "---------------------------------------------------------".cyan());
+                }
+                let healthy = report.modules.iter().filter(|m| m.state == HealthState::Healthy).count();
+                let summary = format!("   {}/{} MODULES HEALTHY", healthy, report.modules.len());
+                println!("{}", if report.healthy() { summary.bold().green() } else { summary.bold().red() });
+                println!("{}", "=========================================================".cyan());
+            }
+            if !report.healthy() {
+                std::process::exit(1);
+            }
         }
-        Commands::Reload => {
-            println!("{}", "[AEGISD] Reloading S2O enterprise policy manifest file (policy.json)...".cyan());
-            println!("{}", "SUCCESS: All 9 subsystem engines updated with new security policy.".green().bold());
+        Commands::Workers { daemon, json } => {
+            let client = IpcClient::new(daemon, std::env::var(TOKEN_ENV).ok());
+            let workers: Vec<WorkerStatus> = client.call(IpcRequest::Workers).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&workers)?);
+            } else {
+                println!("{}", "=========================================================".cyan());
+                println!("{}", "           SPLIT2OPS AEGIS SUPERVISED WORKERS            ".bold().green());
+                println!("{}", "=========================================================".cyan());
+                print_workers(&workers);
+                println!("{}", "=========================================================".cyan());
+            }
+        }
+        Commands::Reload { daemon } => {
+            println!("{}", "[AEGISD] Reloading S2O enterprise policy manifest...".cyan());
+            let client = IpcClient::new(daemon, std::env::var(TOKEN_ENV).ok());
+            let report: ReloadReport = client.call(IpcRequest::Reload).await?;
+            print_reload_report(&report);
+            if !report.applied {
+                std::process::exit(1);
+            }
         }
     }
 
@@ -0,0 +1,134 @@
+use cyberwall_core::signing::SigningConfig;
+pub use cyberwall_core::state::{ScanSchedule, ZtnaRoute};
+use cyberwall_core::{EngineError, EngineResult, FirewallPolicy};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Default location of the enterprise manifest
+pub const MANIFEST_PATH: &str = "/etc/aegisd/policy.json";
+
+/// Single enterprise configuration document covering every subsystem
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Manifest revision, informational only
+    #[serde(default)]
+    pub revision: String,
+    #[serde(default)]
+    pub firewall: Option<FirewallPolicy>,
+    #[serde(default)]
+    pub dns: DnsConfig,
+    #[serde(default)]
+    pub scans: Vec<ScanSchedule>,
+    #[serde(default)]
+    pub ztna_routes: Vec<ZtnaRoute>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsConfig {
+    /// Domains refused by cyberdns, including their subdomains
+    #[serde(default)]
+    pub blocklist: Vec<String>,
+    /// DoH resolver URLs, tried in order
+    #[serde(default)]
+    pub resolvers: Vec<String>,
+}
+
+fn valid_domain(domain: &str) -> bool {
+    domain.len() <= 253
+        && domain.split('.').count() >= 2
+        && domain
+            .split('.')
+            .all(|l| !l.is_empty() && l.len() <= 63 && !l.starts_with('-') && !l.ends_with('-') && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+}
+
+fn duplicate<'a>(names: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let mut seen = Vec::new();
+    names.into_iter().find(|n| {
+        let dup = seen.contains(n);
+        seen.push(*n);
+        dup
+    })
+}
+
+impl Manifest {
+    /// Reads the manifest after checking `<path>.sig`; returns the name of the key that signed it
+    pub fn load(path: &Path, signing: &SigningConfig) -> EngineResult<(Self, Option<String>)> {
+        let (raw, signer) = signing.read_verified(path)?;
+        let raw = String::from_utf8(raw).map_err(|_| EngineError(format!("{}: manifest is not UTF-8", path.display())))?;
+        let manifest = Self::parse(&raw).map_err(|e| EngineError(format!("{}: {}", path.display(), e)))?;
+        Ok((manifest, signer.map(|k| format!("{} ({})", k.name, k.key_id()))))
+    }
+
+    pub fn parse(raw: &str) -> EngineResult<Self> {
+        let manifest: Self = serde_json::from_str(raw).map_err(|e| EngineError(format!("Invalid manifest: {}", e)))?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Checks every section; a manifest is applied only when all of them are valid
+    pub fn validate(&self) -> EngineResult<()> {
+        if let Some(policy) = &self.firewall {
+            for rule in &policy.rules {
+                rule.validate().map_err(|e| EngineError(format!("firewall: {}", e)))?;
+            }
+            for nat in &policy.nat_rules {
+                nat.validate().map_err(|e| EngineError(format!("firewall: {}", e)))?;
+            }
+        }
+        for domain in &self.dns.blocklist {
+            if !valid_domain(domain) {
+                return Err(EngineError(format!("dns: invalid blocklist domain '{}'", domain)));
+            }
+        }
+        for resolver in &self.dns.resolvers {
+            if !resolver.starts_with("https://") {
+                return Err(EngineError(format!("dns: resolver '{}' is not an https:// DoH URL", resolver)));
+            }
+        }
+        if let Some(name) = duplicate(self.scans.iter().map(|s| s.name.as_str())) {
+            return Err(EngineError(format!("scans: schedule '{}' is defined twice", name)));
+        }
+        for scan in &self.scans {
+            if scan.paths.is_empty() {
+                return Err(EngineError(format!("scans: schedule '{}' has no paths", scan.name)));
+            }
+            if scan.interval_minutes == 0 {
+                return Err(EngineError(format!("scans: schedule '{}' needs a non-zero interval", scan.name)));
+            }
+        }
+        if let Some(name) = duplicate(self.ztna_routes.iter().map(|r| r.name.as_str())) {
+            return Err(EngineError(format!("ztna: route '{}' is defined twice", name)));
+        }
+        for route in &self.ztna_routes {
+            if !valid_domain(&route.fqdn) {
+                return Err(EngineError(format!("ztna: route '{}' has invalid fqdn '{}'", route.name, route.fqdn)));
+            }
+            let port_ok = route.target.rsplit_once(':').is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok_and(|p| p > 0));
+            if !port_ok {
+                return Err(EngineError(format!("ztna: route '{}' target '{}' is not host:port", route.name, route.target)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of applying one manifest section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsystemResult {
+    pub subsystem: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReloadReport {
+    pub path: String,
+    pub revision: Option<String>,
+    /// Trusted key that signed the manifest, if it was signed
+    #[serde(default)]
+    pub signed_by: Option<String>,
+    /// True when the new manifest became active; false means the previous one was kept
+    pub applied: bool,
+    pub error: Option<String>,
+    pub subsystems: Vec<SubsystemResult>,
+}
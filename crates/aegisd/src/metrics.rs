@@ -0,0 +1,125 @@
+use crate::daemon::Daemon;
+use crate::log;
+use bytes::Bytes;
+use cyberwall_core::health::HealthState;
+use cyberwall_core::metrics::{self, MetricsRegistry};
+use cyberwall_core::state::StateStore;
+use cyberwall_core::{EngineError, EngineResult, FirewallEngine};
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+/// Default Prometheus scrape address; loopback only, as the endpoint has no authentication
+pub const DEFAULT_METRICS_ADDR: &str = "127.0.0.1:9184";
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Everything a scrape reads from
+pub struct Scraper {
+    pub engine: Arc<dyn FirewallEngine>,
+    pub daemon: Arc<Daemon>,
+    pub store: Arc<StateStore>,
+}
+
+impl Scraper {
+    /// Builds a fresh snapshot; a failing source is reported through `aegis_scrape_errors` rather than failing the scrape
+    async fn collect(&self) -> String {
+        let registry = MetricsRegistry::default();
+        let mut errors: Vec<(&str, String)> = Vec::new();
+        if let Err(e) = self.firewall(&registry).await {
+            errors.push(("firewall", e.0));
+        }
+        if let Err(e) = self.stored(&registry) {
+            errors.push(("state", e.0));
+        }
+        for module in self.daemon.health().await.modules {
+            let module_labels = metrics::labels(&[("module", &module.module)]);
+            registry.gauge_set("aegis_module_healthy", "1 when the subsystem reports healthy", module_labels, (module.state == HealthState::Healthy) as u8 as f64);
+            for (name, value) in &module.metrics {
+                let labels = metrics::labels(&[("module", &module.module), ("metric", name)]);
+                registry.gauge_set("aegis_module_metric", "Numeric detail of a subsystem health probe", labels, *value);
+            }
+        }
+        for (source, error) in &errors {
+            log::warn("metrics", &format!("Scrape of {} failed: {}", source, error));
+            registry.gauge_set("aegis_scrape_errors", "1 when a metrics source failed during this scrape", metrics::labels(&[("source", source)]), 1.0);
+        }
+        registry.render() + &metrics::global().render()
+    }
+
+    async fn firewall(&self, registry: &MetricsRegistry) -> EngineResult<()> {
+        let status = self.engine.get_status().await?;
+        let none = metrics::labels(&[]);
+        registry.gauge_set("cyberwall_enabled", "1 when the firewall is enabled", none.clone(), status.enabled as u8 as f64);
+        registry.gauge_set("cyberwall_shield_active", "1 while outbound traffic is blocked by the shield", none, status.outbound_blocked as u8 as f64);
+
+        let rules = self.engine.list_rules().await?;
+        let mut counts = std::collections::BTreeMap::new();
+        for rule in &rules {
+            let key = (format!("{:?}", rule.direction).to_lowercase(), format!("{:?}", rule.action).to_lowercase(), rule.enabled.to_string());
+            *counts.entry(key).or_insert(0u64) += 1;
+        }
+        for ((direction, action, enabled), count) in counts {
+            let labels = metrics::labels(&[("direction", &direction), ("action", &action), ("enabled", &enabled)]);
+            registry.gauge_set("cyberwall_rules", "Installed firewall rules", labels, count as f64);
+        }
+
+        for stats in self.engine.rule_stats().await? {
+            let labels = metrics::labels(&[("rule", &stats.name)]);
+            registry.counter_add("cyberwall_rule_packets_total", "Packets matched by a rule", labels.clone(), stats.packets as f64);
+            registry.counter_add("cyberwall_rule_bytes_total", "Bytes matched by a rule", labels, stats.bytes as f64);
+        }
+        Ok(())
+    }
+
+    /// Blocklist size plus the counters cyberdns, cyberdefender and cybersiem keep in the state store
+    fn stored(&self, registry: &MetricsRegistry) -> EngineResult<()> {
+        registry.gauge_set("cyberdns_blocklist_domains", "Domains on the DNS blocklist", metrics::labels(&[]), self.store.dns().count()? as f64);
+        registry.absorb(&self.store.metrics().list()?);
+        Ok(())
+    }
+
+    async fn serve(&self, req: Request<Incoming>) -> Response<Full<Bytes>> {
+        let (status, content_type, body) = match (req.method(), req.uri().path()) {
+            (&Method::GET, "/metrics") => (StatusCode::OK, CONTENT_TYPE, self.collect().await),
+            (method, path) => (StatusCode::NOT_FOUND, "text/plain", format!("No route for {} {}\n", method, path)),
+        };
+        let mut resp = Response::new(Full::new(Bytes::from(body)));
+        *resp.status_mut() = status;
+        resp.headers_mut().insert(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static(content_type));
+        resp
+    }
+}
+
+/// Serves `GET /metrics` in the Prometheus text format over plain HTTP
+pub async fn bind_metrics(addr: &str, scraper: Scraper) -> EngineResult<tokio::task::JoinHandle<()>> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| EngineError(format!("Failed to bind metrics endpoint on {}: {}", addr, e)))?;
+    let scraper = Arc::new(scraper);
+    Ok(tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    log::error("metrics", &format!("Metrics accept failed: {}", e));
+                    tokio::time::sleep(crate::control::ACCEPT_BACKOFF).await;
+                    continue;
+                }
+            };
+            let scraper = scraper.clone();
+            tokio::spawn(async move {
+                let service = hyper::service::service_fn(move |req| {
+                    let scraper = scraper.clone();
+                    async move { Ok::<_, std::convert::Infallible>(scraper.serve(req).await) }
+                });
+                if let Err(e) = hyper::server::conn::http1::Builder::new().serve_connection(TokioIo::new(stream), service).await {
+                    log::warn("metrics", &format!("Metrics connection from {} ended: {}", peer, e));
+                }
+            });
+        }
+    }))
+}
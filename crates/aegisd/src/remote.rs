@@ -0,0 +1,322 @@
+use crate::daemon::request_args;
+use crate::log::{self, Priority};
+use bytes::Bytes;
+use cyberwall_core::health::unix_now;
+use cyberwall_core::ipc::{Caller, ControlHandler, IpcRequest, IpcResponse};
+use cyberwall_core::state::{AuditEntry, StateStore};
+use cyberwall_core::{EngineError, EngineResult};
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::Incoming;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+
+pub const DEFAULT_REMOTE_ADDR: &str = "0.0.0.0:5443";
+pub const TLS_CERT_PATH: &str = "/etc/aegisd/tls/server.crt";
+pub const TLS_KEY_PATH: &str = "/etc/aegisd/tls/server.key";
+pub const CLIENT_CA_PATH: &str = "/etc/aegisd/tls/clients-ca.crt";
+pub const ROLES_PATH: &str = "/etc/aegisd/remote-roles.json";
+
+/// Largest request body accepted, enough for a large policy
+const MAX_BODY: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Status, health and rule listings only
+    ReadOnly,
+    /// Everything, including policy push and the shield
+    Admin,
+}
+
+impl Role {
+    /// Name as written in the role map
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::ReadOnly => "read_only",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+/// Client certificate common names and the role each one is granted
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoleMap(pub BTreeMap<String, Role>);
+
+impl RoleMap {
+    pub fn load(path: &Path) -> EngineResult<Self> {
+        let raw = std::fs::read_to_string(path).map_err(|e| EngineError(format!("Failed to read {}: {}", path.display(), e)))?;
+        serde_json::from_str(&raw).map_err(|e| EngineError(format!("{}: invalid role map: {}", path.display(), e)))
+    }
+}
+
+/// Server identity plus the CA that signs admitted client certificates
+pub struct TlsFiles {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+    pub client_ca: PathBuf,
+}
+
+fn tls_config(files: &TlsFiles) -> EngineResult<ServerConfig> {
+    let pem_err = |path: &Path, e: rustls::pki_types::pem::Error| EngineError(format!("Failed to load {}: {}", path.display(), e));
+    let certs = CertificateDer::pem_file_iter(&files.cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| pem_err(&files.cert, e))?;
+    let key = PrivateKeyDer::from_pem_file(&files.key).map_err(|e| pem_err(&files.key, e))?;
+    let mut roots = RootCertStore::empty();
+    for ca in CertificateDer::pem_file_iter(&files.client_ca).map_err(|e| pem_err(&files.client_ca, e))? {
+        let ca = ca.map_err(|e| pem_err(&files.client_ca, e))?;
+        roots.add(ca).map_err(|e| EngineError(format!("Bad client CA in {}: {}", files.client_ca.display(), e)))?;
+    }
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+        .build()
+        .map_err(|e| EngineError(format!("Invalid client CA: {}", e)))?;
+    ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(|e| EngineError(format!("TLS setup failed: {}", e)))?
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(certs, key)
+        .map_err(|e| EngineError(format!("Invalid server certificate or key: {}", e)))
+}
+
+fn common_name(cert: &CertificateDer<'_>) -> Option<String> {
+    let (_, parsed) = x509_parser::parse_x509_certificate(cert.as_ref()).ok()?;
+    let cn = parsed.subject().iter_common_name().next()?;
+    cn.as_str().ok().map(str::to_string)
+}
+
+/// Maps a REST call onto the control API, with the role it requires
+fn route(method: &Method, path: &str, body: &[u8]) -> Result<(IpcRequest, Role), (StatusCode, String)> {
+    let request = match (method, path) {
+        (&Method::GET, "/v1/status") => (IpcRequest::Status, Role::ReadOnly),
+        (&Method::GET, "/v1/health") => (IpcRequest::Health, Role::ReadOnly),
+        (&Method::GET, "/v1/rules") => (IpcRequest::ListRules, Role::ReadOnly),
+        (&Method::GET, "/v1/rules/stats") => (IpcRequest::RuleStats, Role::ReadOnly),
+        (&Method::GET, "/v1/nat") => (IpcRequest::ListNatRules, Role::ReadOnly),
+        (&Method::GET, "/v1/workers") => (IpcRequest::Workers, Role::ReadOnly),
+        (&Method::PUT, "/v1/policy") => {
+            let policy = serde_json::from_slice(body).map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid policy: {}", e)))?;
+            (IpcRequest::ApplyPolicy { policy }, Role::Admin)
+        }
+        (&Method::PUT, "/v1/policy/signed") => {
+            let signed = serde_json::from_slice(body).map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid signed policy: {}", e)))?;
+            (IpcRequest::ApplySignedPolicy { signed }, Role::Admin)
+        }
+        (&Method::POST, "/v1/shield/lock") => (IpcRequest::SetOutboundBlock { blocked: true }, Role::Admin),
+        (&Method::POST, "/v1/shield/unlock") => (IpcRequest::SetOutboundBlock { blocked: false }, Role::Admin),
+        (&Method::POST, "/v1/emergency") => {
+            let mode = serde_json::from_slice(body).map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid emergency mode: {}", e)))?;
+            (IpcRequest::SetEmergency { mode: Some(mode), reason: None }, Role::Admin)
+        }
+        (&Method::POST, "/v1/emergency/release") => {
+            let reason = serde_json::from_slice::<serde_json::Value>(body)
+                .ok()
+                .and_then(|v| v.get("reason").and_then(|r| r.as_str()).map(str::to_string))
+                .filter(|r| !r.trim().is_empty())
+                .ok_or_else(|| (StatusCode::BAD_REQUEST, "Break-glass release needs a JSON body with a \"reason\"".to_string()))?;
+            (IpcRequest::SetEmergency { mode: None, reason: Some(reason) }, Role::Admin)
+        }
+        (&Method::POST, "/v1/reload") => (IpcRequest::Reload, Role::Admin),
+        _ => return Err((StatusCode::NOT_FOUND, format!("No route for {} {}", method, path))),
+    };
+    Ok(request)
+}
+
+struct RemoteApi {
+    handler: Arc<dyn ControlHandler>,
+    roles: RoleMap,
+    store: Arc<StateStore>,
+}
+
+impl RemoteApi {
+    async fn serve(&self, req: Request<Incoming>, peer: SocketAddr, actor: Option<&str>) -> Response<Full<Bytes>> {
+        let action = format!("{} {}", req.method(), req.uri().path());
+        let actor = actor.unwrap_or("<no common name>");
+        let role = self.roles.0.get(actor).copied();
+        let caller = Caller { source: "remote".to_string(), actor: actor.to_string(), uid: None, peer: peer.to_string(), role: role.map(|r| r.as_str().to_string()) };
+        let (status, response, args, audited) = self.authorize_and_run(req, &caller, role).await;
+
+        let (ok, detail) = match &response {
+            IpcResponse::Ok { .. } => (true, status.to_string()),
+            IpcResponse::Error { message } => (false, format!("{}: {}", status, message)),
+        };
+        let entry = AuditEntry {
+            at: unix_now(),
+            source: "remote".to_string(),
+            actor: actor.to_string(),
+            role: role.map(|r| r.as_str().to_string()),
+            peer: peer.to_string(),
+            action: action.clone(),
+            args,
+            ok,
+            detail: detail.clone(),
+            ..Default::default()
+        };
+        // State-changing calls were recorded by the handler along with the firewall state around them
+        if !audited {
+            if let Err(e) = self.store.audit().record(&entry) {
+                log::error("remote", &format!("Failed to record audit entry for {} by {}: {}", action, actor, e));
+            }
+        }
+        let priority = if ok { Priority::Info } else { Priority::Warning };
+        log::event(priority, "remote", &format!("{} by {} from {}: {}", action, actor, peer, detail), &[("AEGIS_REMOTE_ACTOR", actor)]);
+
+        let body = serde_json::to_vec(&response).unwrap_or_default();
+        let mut resp = Response::new(Full::new(Bytes::from(body)));
+        *resp.status_mut() = status;
+        resp.headers_mut().insert(hyper::header::CONTENT_TYPE, hyper::header::HeaderValue::from_static("application/json"));
+        resp
+    }
+
+    /// Runs an authorized call, returning its arguments as kept in the audit log and whether the handler audited it
+    async fn authorize_and_run(&self, req: Request<Incoming>, caller: &Caller, role: Option<Role>) -> (StatusCode, IpcResponse, Vec<String>, bool) {
+        let error = |(status, message): (StatusCode, String)| (status, IpcResponse::Error { message }, Vec::new(), false);
+        let (parts, body) = req.into_parts();
+        let body = match Limited::new(body, MAX_BODY).collect().await {
+            Ok(collected) => collected.to_bytes(),
+            Err(e) => return error((StatusCode::PAYLOAD_TOO_LARGE, format!("Failed to read request body: {}", e))),
+        };
+        let request = match route(&parts.method, parts.uri.path(), &body).and_then(|(request, required)| authorize(role, required).map(|_| request)) {
+            Ok(request) => request,
+            Err(denied) => return error(denied),
+        };
+        let (args, audited) = (request_args(&request), request.is_mutating());
+        match self.handler.handle_from(caller, request).await {
+            ok @ IpcResponse::Ok { .. } => (StatusCode::OK, ok, args, audited),
+            err => (StatusCode::INTERNAL_SERVER_ERROR, err, args, audited),
+        }
+    }
+}
+
+/// Checks the caller's mapped role against the one a route requires
+fn authorize(role: Option<Role>, required: Role) -> Result<(), (StatusCode, String)> {
+    match (role, required) {
+        (None, _) => Err((StatusCode::FORBIDDEN, "Client certificate is not mapped to a role".to_string())),
+        (Some(Role::ReadOnly), Role::Admin) => Err((StatusCode::FORBIDDEN, "This call requires the admin role".to_string())),
+        _ => Ok(()),
+    }
+}
+
+/// Serves the management API over HTTPS, admitting only clients with a certificate from the client CA
+pub async fn bind_remote(
+    addr: &str,
+    files: &TlsFiles,
+    roles: RoleMap,
+    handler: Arc<dyn ControlHandler>,
+    store: Arc<StateStore>,
+) -> EngineResult<tokio::task::JoinHandle<()>> {
+    let acceptor = TlsAcceptor::from(Arc::new(tls_config(files)?));
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| EngineError(format!("Failed to bind remote API on {}: {}", addr, e)))?;
+    let api = Arc::new(RemoteApi { handler, roles, store });
+    Ok(tokio::spawn(async move {
+        loop {
+            let (stream, peer) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    log::error("remote", &format!("Remote accept failed: {}", e));
+                    tokio::time::sleep(crate::control::ACCEPT_BACKOFF).await;
+                    continue;
+                }
+            };
+            let (acceptor, api) = (acceptor.clone(), api.clone());
+            tokio::spawn(async move {
+                let tls = match acceptor.accept(stream).await {
+                    Ok(tls) => tls,
+                    Err(e) => {
+                        log::warn("remote", &format!("TLS handshake with {} failed: {}", peer, e));
+                        return;
+                    }
+                };
+                let actor: Option<Arc<str>> = tls.get_ref().1.peer_certificates().and_then(|c| c.first()).and_then(common_name).map(Into::into);
+                let service = hyper::service::service_fn(move |req| {
+                    let (api, actor) = (api.clone(), actor.clone());
+                    async move { Ok::<_, std::convert::Infallible>(api.serve(req, peer, actor.as_deref()).await) }
+                });
+                if let Err(e) = hyper::server::conn::http1::Builder::new().serve_connection(TokioIo::new(tls), service).await {
+                    log::warn("remote", &format!("Remote connection from {} ended: {}", peer, e));
+                }
+            });
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const READ_ROUTES: &[(&str, &str)] = &[
+        ("GET", "/v1/status"),
+        ("GET", "/v1/health"),
+        ("GET", "/v1/rules"),
+        ("GET", "/v1/rules/stats"),
+        ("GET", "/v1/nat"),
+        ("GET", "/v1/workers"),
+    ];
+
+    fn routed(method: &str, path: &str, body: &str) -> Result<(IpcRequest, Role), (StatusCode, String)> {
+        route(&method.parse().unwrap(), path, body.as_bytes())
+    }
+
+    const ADMIN_ROUTES: &[(&str, &str, &str)] = &[
+        ("PUT", "/v1/policy", r#"{"name":"remote","version":"1","rules":[]}"#),
+        ("POST", "/v1/shield/lock", ""),
+        ("POST", "/v1/shield/unlock", ""),
+        ("POST", "/v1/emergency", r#"{"mode":"isolate","management":[]}"#),
+        ("POST", "/v1/emergency/release", r#"{"reason":"incident closed"}"#),
+        ("POST", "/v1/reload", ""),
+    ];
+
+    #[test]
+    fn reads_need_read_only_and_writes_need_admin() {
+        for (method, path) in READ_ROUTES {
+            let (request, role) = routed(method, path, "").unwrap();
+            assert_eq!(role, Role::ReadOnly, "{} {}", method, path);
+            assert!(!request.is_mutating(), "{} {}", method, path);
+        }
+        for (method, path, body) in ADMIN_ROUTES {
+            let (request, role) = routed(method, path, body).unwrap();
+            assert_eq!(role, Role::Admin, "{} {}", method, path);
+            assert!(request.is_mutating(), "{} {}", method, path);
+        }
+    }
+
+    #[test]
+    fn roles_are_enforced() {
+        for (method, path, body) in ADMIN_ROUTES {
+            let (_, required) = routed(method, path, body).unwrap();
+            assert_eq!(authorize(Some(Role::ReadOnly), required).unwrap_err().0, StatusCode::FORBIDDEN, "{} {}", method, path);
+            assert_eq!(authorize(None, required).unwrap_err().0, StatusCode::FORBIDDEN);
+            assert!(authorize(Some(Role::Admin), required).is_ok());
+        }
+        for (method, path) in READ_ROUTES {
+            let (_, required) = routed(method, path, "").unwrap();
+            assert!(authorize(Some(Role::ReadOnly), required).is_ok());
+            assert!(authorize(Some(Role::Admin), required).is_ok());
+            assert_eq!(authorize(None, required).unwrap_err().1, "Client certificate is not mapped to a role");
+        }
+    }
+
+    #[test]
+    fn unknown_routes_and_bad_bodies_are_rejected() {
+        assert_eq!(routed("DELETE", "/v1/policy", "").unwrap_err().0, StatusCode::NOT_FOUND);
+        assert_eq!(routed("GET", "/v1/shield/lock", "").unwrap_err().0, StatusCode::NOT_FOUND);
+        assert_eq!(routed("PUT", "/v1/policy", "{").unwrap_err().0, StatusCode::BAD_REQUEST);
+        for body in ["", "{}", r#"{"reason":"  "}"#] {
+            assert_eq!(routed("POST", "/v1/emergency/release", body).unwrap_err().0, StatusCode::BAD_REQUEST, "{:?}", body);
+        }
+    }
+}
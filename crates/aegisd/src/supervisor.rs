@@ -0,0 +1,316 @@
+use crate::log::{self, Priority};
+use cyberwall_core::health::unix_now;
+use cyberwall_core::{EngineError, EngineResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::{Child, Command};
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
+
+/// How a worker is restarted after it exits on its own
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// Consecutive restarts before the worker is marked failed
+    pub max_restarts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// A run at least this long resets the restart count
+    pub stable_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            stable_after: Duration::from_secs(120),
+        }
+    }
+}
+
+impl RestartPolicy {
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff.saturating_mul(1 << attempt.min(16)).min(self.max_backoff)
+    }
+}
+
+/// Subsystem run as a supervised child process
+#[derive(Debug, Clone)]
+pub struct WorkerSpec {
+    pub name: String,
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    /// Workers that must be running before this one starts
+    pub depends_on: Vec<String>,
+    pub restart: RestartPolicy,
+    /// Time allowed between SIGTERM and a forced kill
+    pub shutdown_timeout: Duration,
+}
+
+impl WorkerSpec {
+    /// Runs a sibling binary of aegisd, falling back to `PATH`
+    pub fn sibling(name: &str, binary: &str, args: &[&str]) -> Self {
+        let program = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.join(binary)))
+            .filter(|path| path.exists())
+            .unwrap_or_else(|| PathBuf::from(binary));
+        Self {
+            name: name.to_string(),
+            program,
+            args: args.iter().map(|a| a.to_string()).collect(),
+            depends_on: Vec::new(),
+            restart: RestartPolicy::default(),
+            shutdown_timeout: Duration::from_secs(10),
+        }
+    }
+
+    pub fn after(mut self, dependency: &str) -> Self {
+        self.depends_on.push(dependency.to_string());
+        self
+    }
+}
+
+/// Long-running subsystem processes started by `aegisd start`
+pub fn platform_workers() -> Vec<WorkerSpec> {
+    let mut workers = vec![
+        WorkerSpec::sibling("cybersiem", "cybersiem", &["collect", "--listen", "127.0.0.1:5140"]),
+        WorkerSpec::sibling("cyberdns", "cyberdns", &["serve", "--listen", "127.0.0.1:5353"]),
+    ];
+    if cfg!(target_os = "linux") {
+        workers.push(WorkerSpec::sibling("cyberwall-zones", "cyberwall", &["zones", "watch"]));
+        workers.push(WorkerSpec::sibling("cyberwall-droplog", "cyberwall", &["droplog", "--siem", "127.0.0.1:5140"]).after("cybersiem"));
+    }
+    workers
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkerState {
+    /// Waiting for its dependencies to come up
+    Waiting,
+    Running,
+    /// Exited and waiting out the restart delay
+    Backoff,
+    /// Gave up after too many restarts, or a dependency failed
+    Failed,
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerStatus {
+    pub name: String,
+    pub state: WorkerState,
+    pub pid: Option<u32>,
+    pub restarts: u32,
+    pub depends_on: Vec<String>,
+    pub last_error: Option<String>,
+    /// Unix timestamp (seconds) of the last state change
+    pub since: u64,
+}
+
+type StatusTable = BTreeMap<String, WorkerStatus>;
+
+/// Orders workers so each one comes after its dependencies
+fn start_order(specs: Vec<WorkerSpec>) -> EngineResult<Vec<WorkerSpec>> {
+    for spec in &specs {
+        if let Some(dep) = spec.depends_on.iter().find(|d| !specs.iter().any(|s| &s.name == *d)) {
+            return Err(EngineError(format!("Worker '{}' depends on unknown worker '{}'", spec.name, dep)));
+        }
+    }
+    let mut pending = specs;
+    let mut ordered: Vec<WorkerSpec> = Vec::with_capacity(pending.len());
+    while !pending.is_empty() {
+        let ready = pending.iter().position(|s| s.depends_on.iter().all(|d| ordered.iter().any(|o| &o.name == d)));
+        match ready {
+            Some(idx) => ordered.push(pending.remove(idx)),
+            None => {
+                let names: Vec<&str> = pending.iter().map(|s| s.name.as_str()).collect();
+                return Err(EngineError(format!("Dependency cycle between workers: {}", names.join(", "))));
+            }
+        }
+    }
+    Ok(ordered)
+}
+
+fn set_state(table: &watch::Sender<StatusTable>, name: &str, state: WorkerState, pid: Option<u32>, error: Option<String>) {
+    let (priority, message) = match (state, &error) {
+        (WorkerState::Running, _) => (Priority::Info, format!("Worker running (pid {})", pid.unwrap_or_default())),
+        (WorkerState::Backoff, Some(e)) => (Priority::Warning, format!("Worker exited, restarting: {}", e)),
+        (WorkerState::Failed, Some(e)) => (Priority::Error, format!("Worker failed: {}", e)),
+        (state, _) => (Priority::Info, format!("Worker {:?}", state).to_lowercase()),
+    };
+    let pid_field = pid.map(|p| p.to_string()).unwrap_or_default();
+    let state_field = format!("{:?}", state);
+    log::event(priority, name, &message, &[("AEGIS_WORKER_STATE", &state_field), ("AEGIS_WORKER_PID", &pid_field)]);
+
+    table.send_modify(|t| {
+        if let Some(status) = t.get_mut(name) {
+            status.state = state;
+            status.pid = pid;
+            status.since = unix_now();
+            if error.is_some() {
+                status.last_error = error;
+            }
+            if state == WorkerState::Backoff {
+                status.restarts += 1;
+            }
+        }
+    });
+}
+
+/// Asks the child to exit, killing it once the timeout has passed
+async fn terminate(child: &mut Child, timeout: Duration) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        // SAFETY: plain kill(2) on a child we spawned and have not reaped yet
+        unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) };
+        if tokio::time::timeout(timeout, child.wait()).await.is_ok() {
+            return;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = timeout;
+    let _ = child.kill().await;
+}
+
+/// Resolves once a stop has been requested, without holding the channel lock
+async fn stopped(stop: &mut watch::Receiver<bool>) {
+    let _ = stop.wait_for(|s| *s).await;
+}
+
+async fn run_worker(spec: WorkerSpec, table: watch::Sender<StatusTable>, mut stop: watch::Receiver<bool>) {
+    let name = spec.name.as_str();
+    let mut view = table.subscribe();
+    let deps_settled = |t: &StatusTable| {
+        spec.depends_on.iter().all(|d| t.get(d).is_some_and(|s| s.state == WorkerState::Running))
+            || spec.depends_on.iter().any(|d| t.get(d).is_some_and(|s| s.state == WorkerState::Failed))
+    };
+    tokio::select! {
+        _ = async { let _ = view.wait_for(deps_settled).await; } => {}
+        _ = stopped(&mut stop) => {
+            set_state(&table, name, WorkerState::Stopped, None, None);
+            return;
+        }
+    }
+    let failed_dep = spec.depends_on.iter().find(|d| view.borrow().get(*d).is_some_and(|s| s.state == WorkerState::Failed)).cloned();
+    if let Some(dep) = failed_dep {
+        set_state(&table, name, WorkerState::Failed, None, Some(format!("Dependency '{}' failed", dep)));
+        return;
+    }
+
+    let mut attempt = 0u32;
+    loop {
+        let started = Instant::now();
+        let mut command = Command::new(&spec.program);
+        command.args(&spec.args).stdin(Stdio::null()).kill_on_drop(true);
+        // Own process group, so a terminal Ctrl+C reaches aegisd only and shutdown stays ordered
+        #[cfg(unix)]
+        command.process_group(0);
+        let spawned = command.spawn();
+        let error = match spawned {
+            Err(e) => format!("Failed to start {}: {}", spec.program.display(), e),
+            Ok(mut child) => {
+                set_state(&table, name, WorkerState::Running, child.id(), None);
+                tokio::select! {
+                    status = child.wait() => match status {
+                        Ok(status) => format!("Exited with {}", status),
+                        Err(e) => format!("Lost track of process: {}", e),
+                    },
+                    _ = stopped(&mut stop) => {
+                        terminate(&mut child, spec.shutdown_timeout).await;
+                        set_state(&table, name, WorkerState::Stopped, None, None);
+                        return;
+                    }
+                }
+            }
+        };
+
+        if started.elapsed() >= spec.restart.stable_after {
+            attempt = 0;
+        }
+        if attempt >= spec.restart.max_restarts {
+            set_state(&table, name, WorkerState::Failed, None, Some(format!("{} (gave up after {} restarts)", error, attempt)));
+            return;
+        }
+        let delay = spec.restart.backoff(attempt);
+        attempt += 1;
+        set_state(&table, name, WorkerState::Backoff, None, Some(error));
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = stopped(&mut stop) => {
+                set_state(&table, name, WorkerState::Stopped, None, None);
+                return;
+            }
+        }
+    }
+}
+
+struct RunningWorker {
+    stop: watch::Sender<bool>,
+    task: JoinHandle<()>,
+    shutdown_timeout: Duration,
+}
+
+/// Runs workers in dependency order and restarts them according to their policy
+pub struct Supervisor {
+    table: watch::Sender<StatusTable>,
+    order: Vec<String>,
+    /// Workers in start order
+    running: Mutex<Vec<RunningWorker>>,
+}
+
+impl Supervisor {
+    pub fn start(specs: Vec<WorkerSpec>) -> EngineResult<Self> {
+        let ordered = start_order(specs)?;
+        let initial = ordered
+            .iter()
+            .map(|s| {
+                let status = WorkerStatus {
+                    name: s.name.clone(),
+                    state: WorkerState::Waiting,
+                    pid: None,
+                    restarts: 0,
+                    depends_on: s.depends_on.clone(),
+                    last_error: None,
+                    since: unix_now(),
+                };
+                (s.name.clone(), status)
+            })
+            .collect();
+        let (table, _) = watch::channel(initial);
+        let order = ordered.iter().map(|s| s.name.clone()).collect();
+        let running = ordered
+            .into_iter()
+            .map(|spec| {
+                let (stop, stop_rx) = watch::channel(false);
+                let shutdown_timeout = spec.shutdown_timeout;
+                let task = tokio::spawn(run_worker(spec, table.clone(), stop_rx));
+                RunningWorker { stop, task, shutdown_timeout }
+            })
+            .collect();
+        Ok(Self { table, order, running: Mutex::new(running) })
+    }
+
+    /// Worker states in start order
+    pub fn statuses(&self) -> Vec<WorkerStatus> {
+        let table = self.table.borrow();
+        self.order.iter().filter_map(|name| table.get(name).cloned()).collect()
+    }
+
+    /// Stops workers in reverse start order, each within its own timeout
+    pub async fn shutdown(&self) {
+        let mut running = self.running.lock().await;
+        while let Some(worker) = running.pop() {
+            let _ = worker.stop.send(true);
+            let grace = worker.shutdown_timeout + Duration::from_secs(1);
+            let abort = worker.task.abort_handle();
+            if tokio::time::timeout(grace, worker.task).await.is_err() {
+                abort.abort();
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::path::Path;
+use std::time::Duration;
+
+/// Sends a state update (e.g. `READY=1`) to the service manager; a no-op outside systemd
+pub fn notify(state: &str) {
+    #[cfg(target_os = "linux")]
+    if let Some(path) = std::env::var_os("NOTIFY_SOCKET") {
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::net::{SocketAddr, UnixDatagram};
+
+        let bytes = path.as_bytes();
+        // A leading '@' names a socket in the abstract namespace
+        let addr = match bytes.strip_prefix(b"@") {
+            Some(name) => SocketAddr::from_abstract_name(name),
+            None => SocketAddr::from_pathname(Path::new(&path)),
+        };
+        if let (Ok(addr), Ok(sock)) = (addr, UnixDatagram::unbound()) {
+            let _ = sock.send_to_addr(state.as_bytes(), &addr);
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = state;
+}
+
+/// Half the watchdog timeout systemd expects pings within, when the watchdog is on for this process
+pub fn watchdog_interval() -> Option<Duration> {
+    if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+        if pid.parse::<u32>().ok() != Some(std::process::id()) {
+            return None;
+        }
+    }
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec / 2))
+}
+
+/// Takes the control socket passed by socket activation, if systemd started us that way
+#[cfg(unix)]
+pub fn take_listener() -> Option<std::os::unix::net::UnixListener> {
+    use std::os::unix::io::FromRawFd;
+    const SD_LISTEN_FDS_START: i32 = 3;
+
+    let pid: u32 = std::env::var("LISTEN_PID").ok()?.parse().ok()?;
+    let fds: i32 = std::env::var("LISTEN_FDS").ok()?.parse().ok()?;
+    if pid != std::process::id() || fds < 1 {
+        return None;
+    }
+    // Workers must neither inherit the socket nor believe they were activated
+    for var in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        std::env::remove_var(var);
+    }
+    for fd in SD_LISTEN_FDS_START..SD_LISTEN_FDS_START + fds {
+        // SAFETY: fcntl on descriptors handed to us by systemd
+        unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+    }
+    // SAFETY: systemd passes the first listening socket as fd 3 and we take ownership of it once
+    Some(unsafe { std::os::unix::net::UnixListener::from_raw_fd(SD_LISTEN_FDS_START) })
+}
+
+/// Whether stdout is connected to the journal
+pub fn under_journal() -> bool {
+    std::env::var_os("JOURNAL_STREAM").is_some()
+}
+
+/// Sends one structured entry over the journald native protocol
+#[cfg(target_os = "linux")]
+pub fn journal_send(fields: &[(&str, &str)]) -> std::io::Result<()> {
+    let mut payload = Vec::new();
+    for (key, value) in fields {
+        payload.extend_from_slice(key.as_bytes());
+        if value.contains('\n') {
+            // Multi-line values use the length-prefixed form
+            payload.push(b'\n');
+            payload.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        } else {
+            payload.push(b'=');
+        }
+        payload.extend_from_slice(value.as_bytes());
+        payload.push(b'\n');
+    }
+    let sock = std::os::unix::net::UnixDatagram::unbound()?;
+    sock.send_to(&payload, "/run/systemd/journal/socket").map(|_| ())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn journal_send(_fields: &[(&str, &str)]) -> std::io::Result<()> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "journald is not available"))
+}
+
+fn service_unit(binary: &Path, socket: &Path) -> String {
+    format!(
+        "[Unit]
+Description=S2O Aegis Cyber-Ops master daemon
+Requires=aegisd.socket
+After=aegisd.socket network-pre.target
+Wants=network-pre.target
+
+[Service]
+Type=notify
+NotifyAccess=main
+ExecStart={binary} start --socket {socket}
+ExecReload=/bin/kill -HUP $MAINPID
+WatchdogSec=30
+Restart=on-failure
+RestartSec=2
+# SIGTERM to aegisd only; it stops its workers in order before systemd kills leftovers
+KillMode=mixed
+TimeoutStopSec=45
+
+CapabilityBoundingSet=CAP_NET_ADMIN CAP_NET_RAW CAP_NET_BIND_SERVICE
+NoNewPrivileges=yes
+ProtectSystem=strict
+# /var/lib/cyberwall holds the saved policy that cyberwall-restore re-applies at boot
+StateDirectory=aegisd cyberwall
+ReadWritePaths=-/etc/cyberwall -/etc/aegisd
+ProtectHome=yes
+PrivateTmp=yes
+PrivateDevices=yes
+ProtectKernelModules=yes
+ProtectKernelTunables=yes
+ProtectKernelLogs=yes
+ProtectControlGroups=yes
+ProtectClock=yes
+ProtectHostname=yes
+RestrictAddressFamilies=AF_UNIX AF_INET AF_INET6 AF_NETLINK
+RestrictNamespaces=yes
+RestrictRealtime=yes
+RestrictSUIDSGID=yes
+LockPersonality=yes
+MemoryDenyWriteExecute=yes
+SystemCallArchitectures=native
+UMask=0077
+
+[Install]
+WantedBy=multi-user.target
+",
+        binary = binary.display(),
+        socket = socket.display()
+    )
+}
+
+fn socket_unit(socket: &Path) -> String {
+    format!(
+        "[Unit]
+Description=S2O Aegis control socket
+
+[Socket]
+ListenStream={socket}
+SocketMode=0600
+DirectoryMode=0755
+
+[Install]
+WantedBy=sockets.target
+",
+        socket = socket.display()
+    )
+}
+
+/// Writes `aegisd.service` and `aegisd.socket` into `dir` and returns their paths
+pub fn install_units(dir: &Path, binary: &Path, socket: &Path) -> std::io::Result<Vec<std::path::PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let units = [("aegisd.service", service_unit(binary, socket)), ("aegisd.socket", socket_unit(socket))];
+    let mut written = Vec::new();
+    for (name, content) in units {
+        let path = dir.join(name);
+        std::fs::write(&path, content)?;
+        written.push(path);
+    }
+    Ok(written)
+}
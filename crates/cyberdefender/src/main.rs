@@ -1,8 +1,12 @@
 use clap::{Parser, Subcommand};
 use colored::*;
+use cyberwall_core::health::unix_now;
+use cyberwall_core::metrics;
+use cyberwall_core::state::{ScanResult, StateStore};
 use sha2::{Digest, Sha256};
 use std::fs::File;
 use std::io::Read;
+use std::time::Instant;
 
 #[derive(Parser)]
 #[command(name = "cyberdefender")]
@@ -64,18 +68,49 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!(" Signature Engine  : {}", "S2O YARA Core v4.5 + Windows Defender Service".yellow());
             println!(" Loaded Rulesets   : {}", "485,120 active threat signatures".bold());
             println!(" Heuristic Scan    : {}", "DEEP BEHAVIORAL ANALYSIS (Level 3)".green());
+            match StateStore::open_default().and_then(|store| store.config().scan_schedules()) {
+                Ok(schedules) if schedules.is_empty() => println!(" Scan Schedules    : {}", "none in the manifest".yellow()),
+                Ok(schedules) => {
+                    println!(" Scan Schedules    : {}", format!("{} from the manifest", schedules.len()).bold());
+                    for schedule in &schedules {
+                        println!("   {:<16} every {} min: {}", schedule.name, schedule.interval_minutes, schedule.paths.join(", "));
+                    }
+                }
+                Err(e) => println!(" Scan Schedules    : {}", format!("UNAVAILABLE ({})", e).red()),
+            }
             println!("{}", "=========================================================".cyan());
         }
         Commands::Scan { path } => {
             println!("{}", format!("[CYBERDEFENDER] Initiating high-speed malware scan on target: '{}'...", path).cyan());
 
+            let started = Instant::now();
             match calculate_file_hash(&path) {
                 Ok(hash) => {
+                    let store = StateStore::open_default()?;
+                    let bytes = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                    let elapsed = started.elapsed().as_secs_f64();
+                    let none = metrics::labels(&[]);
+                    let recorded = store
+                        .metrics()
+                        .add("cyberdefender_files_scanned_total", "Files scanned", &none, 1.0)
+                        .and_then(|_| store.metrics().add("cyberdefender_bytes_scanned_total", "Bytes hashed by scans", &none, bytes as f64))
+                        .and_then(|_| store.metrics().add("cyberdefender_scan_seconds_total", "Time spent scanning", &none, elapsed));
+                    if let Err(e) = recorded {
+                        eprintln!("{}", format!("Failed to record scan metrics: {}", e).yellow());
+                    }
+                    let previous = store.scans().last_for(&path)?;
+                    store.scans().record(&ScanResult { path: path.clone(), sha256: hash.clone(), verdict: "clean".to_string(), scanned_at: unix_now() })?;
                     println!("{}", "---------------------------------------------------------".cyan());
                     println!(" Target File  : {}", path.bold());
                     println!(" SHA-256 Hash : {}", hash.yellow());
                     println!(" YARA Match   : {}", "CLEAN (0 malware signatures detected)".green().bold());
                     println!(" Threat Score : {}", "0 / 100 (Safe)".green().bold());
+                    println!(" Throughput   : {:.1} MB/s ({} bytes in {:.3}s)", bytes as f64 / 1_048_576.0 / elapsed.max(1e-6), bytes, elapsed);
+                    match previous {
+                        Some(prev) if prev.sha256 != hash => println!(" Since Last   : {}", "CHANGED (content differs from the previous scan)".yellow().bold()),
+                        Some(_) => println!(" Since Last   : Unchanged"),
+                        None => println!(" Since Last   : First scan of this file"),
+                    }
                     println!("{}", "---------------------------------------------------------".cyan());
                 }
                 Err(e) => {
@@ -1,5 +1,7 @@
 use clap::{Parser, Subcommand};
 use colored::*;
+use cyberwall_core::metrics;
+use cyberwall_core::state::StateStore;
 use serde::Deserialize;
 
 #[derive(Parser)]
@@ -26,6 +28,11 @@ enum Commands {
         /// Target domain name to block
         domain: String,
     },
+    /// Remove a domain from the local threat blocklist
+    Unblock {
+        /// Target domain name to unblock
+        domain: String,
+    },
     /// Start the local S2O CyberDNS Guard proxy server
     Serve {
         /// Local listen address (default: 127.0.0.1:5353)
@@ -34,6 +41,9 @@ enum Commands {
     },
 }
 
+/// Used when the manifest names no resolvers
+const DEFAULT_RESOLVER: &str = "https://cloudflare-dns.com/dns-query";
+
 #[derive(Debug, Deserialize)]
 struct DohAnswer {
     name: String,
@@ -50,6 +60,21 @@ struct DohResponse {
     Answer: Option<Vec<DohAnswer>>,
 }
 
+/// Counts one query for the aegisd metrics endpoint; a failed update never fails the lookup
+fn count_query(store: &StateStore, result: &str) {
+    let labels = metrics::labels(&[("result", result)]);
+    if let Err(e) = store.metrics().add("cyberdns_queries_total", "DNS queries by outcome", &labels, 1.0) {
+        eprintln!("{}", format!("Failed to record query metric: {}", e).yellow());
+    }
+}
+
+/// Writes the serving heartbeat; a failed write is reported and retried on the next beat
+fn beat(store: &StateStore, stopping: bool) {
+    if let Err(e) = store.metrics().heartbeat("cyberdns", stopping) {
+        eprintln!("{}", format!("Failed to record heartbeat: {}", e).yellow());
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
@@ -60,24 +85,59 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("{}", "        SPLIT2OPS SOFTWARE CYBERDNS GUARD ENGINE         ".bold().green());
             println!("{}", "=========================================================".cyan());
             println!(" Protocol Engine   : {}", "DNS-over-HTTPS (DoH) / DoT Encrypted Resolver".bold());
-            println!(" Primary Resolver  : {}", "Cloudflare DoH (1.1.1.1) / Quad9 (9.9.9.9)".yellow());
+            match StateStore::open_default().and_then(|store| store.config().dns_resolvers()) {
+                Ok(resolvers) if resolvers.is_empty() => println!(" Primary Resolver  : {}", format!("{} (built-in)", DEFAULT_RESOLVER).yellow()),
+                Ok(resolvers) => println!(" Resolvers         : {}", resolvers.join(", ").yellow()),
+                Err(e) => println!(" Resolvers         : {}", format!("UNAVAILABLE ({})", e).red()),
+            }
             println!(" Web Filtering     : {}", "ENABLED (Malware, Phishing, Adware Blocked)".green().bold());
-            println!(" Blocklist Domains : {}", "142,508 active threat domain signatures".bold());
+            match StateStore::open_default().and_then(|store| store.dns().count()) {
+                Ok(count) => println!(" Blocklist Domains : {}", format!("{} blocked domains", count).bold()),
+                Err(e) => println!(" Blocklist Domains : {}", format!("UNAVAILABLE ({})", e).red()),
+            }
             println!("{}", "=========================================================".cyan());
         }
         Commands::Resolve { domain } => {
             println!("{}", format!("[CYBERDNS] Resolving domain '{}' via Encrypted DoH...", domain).cyan());
+            let store = StateStore::open_default()?;
+            if store.dns().is_blocked(&domain)? {
+                count_query(&store, "blocked");
+                println!("{}", format!("BLOCKED: '{}' is on the S2O Threat Blocklist.", domain).red().bold());
+                return Ok(());
+            }
 
-            let url = format!("https://cloudflare-dns.com/dns-query?name={}&type=A", domain);
+            let mut resolvers = store.config().dns_resolvers()?;
+            if resolvers.is_empty() {
+                resolvers.push(DEFAULT_RESOLVER.to_string());
+            }
             let client = reqwest::Client::new();
-            let res = client
-                .get(&url)
-                .header("accept", "application/dns-json")
-                .send()
-                .await?;
+            let mut last_error = None;
+            let mut response = None;
+            // Resolvers from the manifest are tried in order until one answers
+            for resolver in &resolvers {
+                match client.get(resolver).query(&[("name", domain.as_str()), ("type", "A")]).header("accept", "application/dns-json").send().await {
+                    Ok(res) => {
+                        response = Some(res);
+                        break;
+                    }
+                    Err(e) => {
+                        eprintln!("{}", format!("[CYBERDNS] Resolver {} failed: {}", resolver, e).yellow());
+                        last_error = Some(e);
+                    }
+                }
+            }
+            let res = match (response, last_error) {
+                (Some(res), _) => res,
+                (None, Some(e)) => {
+                    count_query(&store, "error");
+                    return Err(e.into());
+                }
+                (None, None) => unreachable!("at least one resolver is always tried"),
+            };
 
             if res.status().is_success() {
                 let doh: DohResponse = res.json().await?;
+                count_query(&store, if doh.Answer.is_some() { "resolved" } else { "nxdomain" });
                 println!("{}", "---------------------------------------------------------".cyan());
                 if let Some(answers) = doh.Answer {
                     for ans in answers {
@@ -90,13 +150,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     println!("{}", "NXDOMAIN: No DNS records found for this target.".yellow());
                 }
             } else {
+                count_query(&store, "error");
                 println!("{}", format!("DoH HTTP Error: {}", res.status()).red());
             }
         }
         Commands::Block { domain } => {
             println!("{}", format!("[CYBERDNS] Adding domain '{}' to S2O Threat Blocklist...", domain).yellow());
+            StateStore::open_default()?.dns().block(&domain, "cli")?;
             println!("{}", format!("SUCCESS: Domain '{}' is now BLOCKED by CyberDNS Guard.", domain).red().bold());
         }
+        Commands::Unblock { domain } => {
+            if StateStore::open_default()?.dns().unblock(&domain)? {
+                println!("{}", format!("SUCCESS: Domain '{}' removed from the S2O Threat Blocklist.", domain).green().bold());
+            } else {
+                println!("{}", format!("Domain '{}' was not on the blocklist.", domain).yellow());
+            }
+        }
         Commands::Serve { listen } => {
             println!("{}", "=========================================================".cyan());
             println!("{}", "     STARTING S2O CYBERDNS GUARD PROXY RESOLVER          ".bold().green());
@@ -105,7 +174,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!(" Mode              : {}", "Encrypted DNS-over-HTTPS Interceptor".yellow());
             println!(" Status            : {}", "RUNNING (Press Ctrl+C to stop)".green().bold());
             println!("{}", "=========================================================".cyan());
-            tokio::signal::ctrl_c().await?;
+            // aegisd reads the heartbeat to report this resolver's health
+            let store = StateStore::open_default()?;
+            let mut heartbeat = tokio::time::interval(metrics::HEARTBEAT_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = heartbeat.tick() => beat(&store, false),
+                    _ = tokio::signal::ctrl_c() => break,
+                }
+            }
+            beat(&store, true);
             println!("\nShutting down S2O CyberDNS Guard...");
         }
     }
@@ -0,0 +1,237 @@
+use cyberwall_core::ipc::token_matches;
+use cyberwall_core::{EngineError, EngineResult, FirewallPolicy};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Last policy applied on this host, restored at boot by `cyberwall restore`
+pub const SAVED_POLICY_PATH: &str = "/var/lib/cyberwall/policy.json";
+pub const RESTORE_UNIT: &str = "cyberwall-restore.service";
+
+/// On-disk form of the saved policy; the MAC covers the serialized policy
+///
+/// The MAC is keyed with a root-only secret kept next to the policy (`<policy>.key`), so an edited file
+/// is refused at boot unless whoever edited it could also read the key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPolicy {
+    pub saved_at: u64,
+    pub hmac_sha256: String,
+    pub policy: FirewallPolicy,
+}
+
+const KEY_LEN: usize = 32;
+const BLOCK_LEN: usize = 64;
+
+/// Secret that authenticates the policy saved at `policy`
+pub fn key_path(policy: &Path) -> PathBuf {
+    policy.with_extension("key")
+}
+
+fn hmac_sha256(key: &[u8; KEY_LEN], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; BLOCK_LEN];
+    block[..KEY_LEN].copy_from_slice(key);
+    let pad = |byte: u8| block.map(|b| b ^ byte);
+    let inner = Sha256::new().chain_update(pad(0x36)).chain_update(message).finalize();
+    Sha256::new().chain_update(pad(0x5c)).chain_update(inner).finalize().into()
+}
+
+pub fn policy_mac(key: &[u8; KEY_LEN], policy: &FirewallPolicy) -> EngineResult<String> {
+    let bytes = serde_json::to_vec(policy).map_err(|e| EngineError(format!("Failed to encode policy: {}", e)))?;
+    Ok(hmac_sha256(key, &bytes).iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn read_key(path: &Path) -> EngineResult<[u8; KEY_LEN]> {
+    let raw = std::fs::read(path).map_err(|e| EngineError(format!("Failed to read policy key {}: {}", path.display(), e)))?;
+    raw.try_into().map_err(|_| EngineError(format!("Policy key {} is not {} bytes", path.display(), KEY_LEN)))
+}
+
+/// Reads the key, creating a fresh random one readable by root only when there is none yet
+fn load_or_create_key(path: &Path) -> EngineResult<[u8; KEY_LEN]> {
+    if path.exists() {
+        return read_key(path);
+    }
+    let mut key = [0u8; KEY_LEN];
+    let mut create = || -> std::io::Result<()> {
+        use std::io::{Read, Write};
+        std::fs::File::open("/dev/urandom")?.read_exact(&mut key)?;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(path)?;
+        file.write_all(&key)?;
+        file.sync_all()
+    };
+    create().map_err(|e| EngineError(format!("Failed to create policy key {}: {}", path.display(), e)))?;
+    Ok(key)
+}
+
+fn write_private(path: &Path, raw: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    let tmp = path.with_extension("json.tmp");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&tmp)?;
+    file.write_all(raw)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Writes the policy atomically, readable by root only
+pub fn save(path: &Path, policy: &FirewallPolicy) -> EngineResult<()> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| EngineError(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    let key = load_or_create_key(&key_path(path))?;
+    let saved = SavedPolicy { saved_at: cyberwall_core::health::unix_now(), hmac_sha256: policy_mac(&key, policy)?, policy: policy.clone() };
+    let raw = serde_json::to_string_pretty(&saved).map_err(|e| EngineError(format!("Failed to encode policy: {}", e)))?;
+    write_private(path, raw.as_bytes()).map_err(|e| EngineError(format!("Failed to save policy to {}: {}", path.display(), e)))
+}
+
+/// Reads the saved policy, refusing it unless its MAC verifies under the policy key
+pub fn load(path: &Path) -> EngineResult<SavedPolicy> {
+    let raw = std::fs::read_to_string(path).map_err(|e| EngineError(format!("Failed to read {}: {}", path.display(), e)))?;
+    let saved: SavedPolicy =
+        serde_json::from_str(&raw).map_err(|e| EngineError(format!("{} is not a saved cyberwall policy: {}", path.display(), e)))?;
+    let expected = policy_mac(&read_key(&key_path(path))?, &saved.policy)?;
+    if !token_matches(&expected, &saved.hmac_sha256.to_ascii_lowercase()) {
+        return Err(EngineError(format!(
+            "MAC mismatch in {}; the saved policy was modified outside cyberwall and will not be applied",
+            path.display()
+        )));
+    }
+    Ok(saved)
+}
+
+/// Oneshot unit that re-applies the saved policy before any interface comes up
+pub fn restore_unit(binary: &Path, policy: &Path) -> String {
+    format!(
+        "[Unit]
+Description=Restore the saved cyberwall firewall policy
+DefaultDependencies=no
+After=local-fs.target
+Before=network-pre.target shutdown.target
+Wants=network-pre.target
+Conflicts=shutdown.target
+
+[Service]
+Type=oneshot
+RemainAfterExit=yes
+ExecStart={binary} restore --file {policy}
+
+[Install]
+WantedBy=sysinit.target
+",
+        binary = binary.display(),
+        policy = policy.display()
+    )
+}
+
+/// Writes the restore unit into `dir` and returns its path
+pub fn install_restore_unit(dir: &Path, binary: &Path, policy: &Path) -> EngineResult<PathBuf> {
+    let path = dir.join(RESTORE_UNIT);
+    std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&path, restore_unit(binary, policy)))
+        .map_err(|e| EngineError(format!("Failed to write {}: {}", path.display(), e)))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cyberwall_core::{FirewallRule, RuleAction, RuleDirection};
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("cyberwall-boot-{}-{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn policy(rule: &str) -> FirewallPolicy {
+        FirewallPolicy {
+            name: "saved".to_string(),
+            version: "3".to_string(),
+            rules: vec![FirewallRule::new(rule, RuleAction::Block, RuleDirection::Inbound)],
+            nat_rules: Vec::new(),
+            allow_neighbor_discovery: true,
+        }
+    }
+
+    #[test]
+    fn hmac_matches_rfc4231() {
+        let mut key = [0u8; KEY_LEN];
+        key[..4].copy_from_slice(b"Jefe");
+        let mac: String = hmac_sha256(&key, b"what do ya want for nothing?").iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(mac, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+
+    #[test]
+    fn save_then_load_round_trips() {
+        let dir = TempDir::new("roundtrip");
+        let path = dir.0.join("policy.json");
+        save(&path, &policy("telnet")).unwrap();
+        assert_eq!(load(&path).unwrap().policy, policy("telnet"));
+
+        #[cfg(unix)]
+        for file in [&path, &key_path(&path)] {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(file).unwrap().permissions().mode() & 0o777, 0o600, "{}", file.display());
+        }
+
+        // A later save keeps the key, so the earlier MAC stays comparable
+        let key = std::fs::read(key_path(&path)).unwrap();
+        save(&path, &policy("smb")).unwrap();
+        assert_eq!(std::fs::read(key_path(&path)).unwrap(), key);
+        assert_eq!(load(&path).unwrap().policy, policy("smb"));
+    }
+
+    #[test]
+    fn edited_policy_is_refused_even_with_a_recomputed_checksum() {
+        let dir = TempDir::new("edited");
+        let path = dir.0.join("policy.json");
+        save(&path, &policy("telnet")).unwrap();
+
+        let mut saved: SavedPolicy = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        saved.policy.rules[0].action = RuleAction::Allow;
+        let bytes = serde_json::to_vec(&saved.policy).unwrap();
+        saved.hmac_sha256 = format!("{:x}", Sha256::digest(&bytes));
+        std::fs::write(&path, serde_json::to_string(&saved).unwrap()).unwrap();
+
+        let err = load(&path).unwrap_err();
+        assert!(err.0.contains("MAC mismatch"), "{}", err);
+    }
+
+    #[test]
+    fn policy_is_refused_under_another_key() {
+        let dir = TempDir::new("rekeyed");
+        let path = dir.0.join("policy.json");
+        save(&path, &policy("telnet")).unwrap();
+        std::fs::write(key_path(&path), [7u8; KEY_LEN]).unwrap();
+        assert!(load(&path).unwrap_err().0.contains("MAC mismatch"));
+
+        std::fs::remove_file(key_path(&path)).unwrap();
+        assert!(load(&path).unwrap_err().0.contains("Failed to read policy key"));
+    }
+
+    #[test]
+    fn checksum_only_saves_from_older_builds_are_refused() {
+        let dir = TempDir::new("legacy");
+        std::fs::create_dir_all(&dir.0).unwrap();
+        let path = dir.0.join("policy.json");
+        let legacy = serde_json::json!({ "saved_at": 1, "sha256": "00", "policy": policy("telnet") });
+        std::fs::write(&path, legacy.to_string()).unwrap();
+        assert!(load(&path).unwrap_err().0.contains("is not a saved cyberwall policy"));
+    }
+}
@@ -0,0 +1,321 @@
+use cyberwall_core::{EngineError, EngineResult};
+use serde::Serialize;
+use std::net::IpAddr;
+
+const PROC_CONNTRACK: &str = "/proc/net/nf_conntrack";
+
+/// Keys accepted in a match expression, in the order they are documented
+pub const MATCH_KEYS: &str = "proto, src, dst, sport, dport, state, mark";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConntrackTuple {
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    pub sport: Option<u16>,
+    pub dport: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConntrackEntry {
+    pub family: String,
+    pub protocol: String,
+    pub state: Option<String>,
+    pub timeout: u64,
+    pub original: ConntrackTuple,
+    pub reply: Option<ConntrackTuple>,
+    pub mark: u32,
+    pub zone: u16,
+    pub flags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConntrackFilter {
+    pub protocol: Option<String>,
+    pub src: Option<IpAddr>,
+    pub dst: Option<IpAddr>,
+    pub sport: Option<u16>,
+    pub dport: Option<u16>,
+    pub state: Option<String>,
+    pub mark: Option<u32>,
+}
+
+impl ConntrackFilter {
+    /// Parses a `key=value[,key=value...]` match expression, e.g. `proto=tcp,dport=22`; every term must match.
+    /// Addresses and ports are compared with the original direction of the flow, so `src` is the side that opened it.
+    pub fn parse(expr: &str) -> EngineResult<Self> {
+        let mut filter = Self::default();
+        for part in expr.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| EngineError(format!("Invalid conntrack match term '{}', expected key=value with a key out of {}", part, MATCH_KEYS)))?;
+            let bad = |e: &dyn std::fmt::Display| EngineError(format!("Invalid value for '{}': {}", key, e));
+            match key {
+                "proto" | "protocol" => filter.protocol = Some(value.to_lowercase()),
+                "src" => filter.src = Some(value.parse().map_err(|e| bad(&e))?),
+                "dst" => filter.dst = Some(value.parse().map_err(|e| bad(&e))?),
+                "sport" => filter.sport = Some(value.parse().map_err(|e| bad(&e))?),
+                "dport" => filter.dport = Some(value.parse().map_err(|e| bad(&e))?),
+                "state" => filter.state = Some(value.to_uppercase()),
+                "mark" => filter.mark = Some(value.parse().map_err(|e| bad(&e))?),
+                _ => return Err(EngineError(format!("Unknown conntrack match key '{}' (expected one of {})", key, MATCH_KEYS))),
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.protocol.is_none()
+            && self.src.is_none()
+            && self.dst.is_none()
+            && self.sport.is_none()
+            && self.dport.is_none()
+            && self.state.is_none()
+            && self.mark.is_none()
+    }
+
+    pub fn matches(&self, entry: &ConntrackEntry) -> bool {
+        let t = &entry.original;
+        self.protocol.as_ref().is_none_or(|p| *p == entry.protocol)
+            && self.src.is_none_or(|a| a == t.src)
+            && self.dst.is_none_or(|a| a == t.dst)
+            && self.sport.is_none_or(|p| Some(p) == t.sport)
+            && self.dport.is_none_or(|p| Some(p) == t.dport)
+            && self.state.as_ref().is_none_or(|s| Some(s) == entry.state.as_ref())
+            && self.mark.is_none_or(|m| m == entry.mark)
+    }
+}
+
+/// Parses one line of `/proc/net/nf_conntrack` (or `conntrack -L -o extended`)
+pub fn parse_entry(line: &str) -> Option<ConntrackEntry> {
+    let mut tokens = line.split_whitespace();
+    let family = tokens.next()?.to_string();
+    let _l3num = tokens.next()?;
+    let protocol = tokens.next()?.to_string();
+    let _l4num = tokens.next()?;
+    let timeout = tokens.next()?.parse().ok()?;
+
+    let mut state = None;
+    let mut flags = Vec::new();
+    let mut mark = 0;
+    let mut zone = 0;
+    let mut tuples: Vec<TupleBuilder> = Vec::new();
+
+    for token in tokens {
+        if let Some(flag) = token.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            flags.push(flag.to_string());
+            continue;
+        }
+        let Some((key, value)) = token.split_once('=') else {
+            state = Some(token.to_string());
+            continue;
+        };
+        match key {
+            "src" => {
+                tuples.push(TupleBuilder::default());
+                tuples.last_mut()?.src = value.parse().ok();
+            }
+            "dst" => tuples.last_mut()?.dst = value.parse().ok(),
+            "sport" => tuples.last_mut()?.sport = value.parse().ok(),
+            "dport" => tuples.last_mut()?.dport = value.parse().ok(),
+            "mark" => mark = value.parse().unwrap_or(0),
+            "zone" => zone = value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    let mut tuples = tuples.into_iter().filter_map(TupleBuilder::build);
+    Some(ConntrackEntry {
+        family,
+        protocol,
+        state,
+        timeout,
+        original: tuples.next()?,
+        reply: tuples.next(),
+        mark,
+        zone,
+        flags,
+    })
+}
+
+#[derive(Default)]
+struct TupleBuilder {
+    src: Option<IpAddr>,
+    dst: Option<IpAddr>,
+    sport: Option<u16>,
+    dport: Option<u16>,
+}
+
+impl TupleBuilder {
+    fn build(self) -> Option<ConntrackTuple> {
+        Some(ConntrackTuple {
+            src: self.src?,
+            dst: self.dst?,
+            sport: self.sport,
+            dport: self.dport,
+        })
+    }
+}
+
+pub struct ConntrackTable;
+
+impl ConntrackTable {
+    /// Lists tracked connections matching the filter
+    pub async fn list(filter: &ConntrackFilter) -> EngineResult<Vec<ConntrackEntry>> {
+        let raw = match tokio::fs::read_to_string(PROC_CONNTRACK).await {
+            Ok(raw) => raw,
+            Err(_) => {
+                let output = tokio::process::Command::new("conntrack")
+                    .args(["-L", "-o", "extended"])
+                    .output()
+                    .await
+                    .map_err(|e| EngineError(format!("Failed to execute conntrack command: {}", e)))?;
+                if !output.status.success() {
+                    return Err(EngineError(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+                }
+                String::from_utf8_lossy(&output.stdout).into_owned()
+            }
+        };
+
+        Ok(raw
+            .lines()
+            .filter_map(parse_entry)
+            .filter(|entry| filter.matches(entry))
+            .collect())
+    }
+
+    /// Deletes tracked connections matching the filter, returning how many were removed
+    pub async fn flush(filter: &ConntrackFilter) -> EngineResult<usize> {
+        let entries = Self::list(filter).await?;
+
+        if filter.is_empty() {
+            // Without -f conntrack only flushes the IPv4 table
+            for family in ["ipv4", "ipv6"] {
+                run_conntrack(&["-F".to_string(), "-f".to_string(), family.to_string()]).await?;
+            }
+            return Ok(entries.len());
+        }
+
+        let mut removed = 0;
+        for entry in &entries {
+            let t = &entry.original;
+            let family = if t.src.is_ipv6() { "ipv6" } else { "ipv4" };
+            // conntrack assumes ipv4 and rejects v6 addresses without -f
+            let mut args = vec![
+                "-D".to_string(),
+                "-f".to_string(),
+                family.to_string(),
+                "-p".to_string(),
+                entry.protocol.clone(),
+                "-s".to_string(),
+                t.src.to_string(),
+                "-d".to_string(),
+                t.dst.to_string(),
+            ];
+            if let Some(sport) = t.sport {
+                args.extend(["--sport".to_string(), sport.to_string()]);
+            }
+            if let Some(dport) = t.dport {
+                args.extend(["--dport".to_string(), dport.to_string()]);
+            }
+            match run_conntrack(&args).await {
+                Ok(()) => removed += 1,
+                // Entries may expire between listing and deletion, which is not an error
+                Err(e) if already_gone(&e) => {}
+                Err(e) => {
+                    return Err(EngineError(format!("Removed {} of {} connections before conntrack failed: {}", removed, entries.len(), e)))
+                }
+            }
+        }
+        Ok(removed)
+    }
+}
+
+/// `conntrack -D` exits non-zero when the flow has already expired; only that case is benign
+fn already_gone(err: &EngineError) -> bool {
+    err.0.contains(" 0 flow entries have been deleted") || err.0.contains("such conntrack doesn't exist")
+}
+
+async fn run_conntrack(args: &[String]) -> EngineResult<()> {
+    let output = tokio::process::Command::new("conntrack")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| EngineError(format!("Failed to execute conntrack command: {}", e)))?;
+    if !output.status.success() {
+        return Err(EngineError(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lines as /proc/net/nf_conntrack prints them on a 6.x kernel
+    const TCP_V4: &str = "ipv4     2 tcp      6 431999 ESTABLISHED src=10.0.0.2 dst=10.0.0.1 sport=51544 dport=22 src=10.0.0.1 dst=10.0.0.2 sport=22 dport=51544 [ASSURED] mark=0 zone=0 use=2";
+    const UDP_V6: &str = "ipv6     10 udp      17 27 src=2001:0db8:0000:0000:0000:0000:0000:0002 dst=2001:0db8:0000:0000:0000:0000:0000:0053 sport=40114 dport=53 [UNREPLIED] src=2001:0db8:0000:0000:0000:0000:0000:0053 dst=2001:0db8:0000:0000:0000:0000:0000:0002 sport=53 dport=40114 mark=1129775105 zone=3 use=2";
+    const ICMP_V4: &str = "ipv4     2 icmp     1 29 src=192.0.2.7 dst=192.0.2.1 type=8 code=0 id=17 src=192.0.2.1 dst=192.0.2.7 type=0 code=0 id=17 mark=0 zone=0 use=2";
+
+    #[test]
+    fn parses_tcp_udp_and_icmp_entries() {
+        let tcp = parse_entry(TCP_V4).unwrap();
+        assert_eq!((tcp.family.as_str(), tcp.protocol.as_str(), tcp.state.as_deref(), tcp.timeout), ("ipv4", "tcp", Some("ESTABLISHED"), 431999));
+        assert_eq!(tcp.original, ConntrackTuple { src: "10.0.0.2".parse().unwrap(), dst: "10.0.0.1".parse().unwrap(), sport: Some(51544), dport: Some(22) });
+        assert_eq!(tcp.reply.as_ref().unwrap().sport, Some(22));
+        assert_eq!(tcp.flags, vec!["ASSURED"]);
+
+        // UDP has no state, the flag sits between the tuples and v6 addresses are written out in full
+        let udp = parse_entry(UDP_V6).unwrap();
+        assert_eq!((udp.state, udp.mark, udp.zone), (None, 0x43570001, 3));
+        assert_eq!(udp.original.dst, "2001:db8::53".parse::<IpAddr>().unwrap());
+        assert_eq!(udp.flags, vec!["UNREPLIED"]);
+
+        let icmp = parse_entry(ICMP_V4).unwrap();
+        assert_eq!((icmp.original.sport, icmp.original.dport), (None, None));
+        assert!(icmp.reply.is_some());
+    }
+
+    #[test]
+    fn rejects_truncated_lines() {
+        assert!(parse_entry("").is_none());
+        assert!(parse_entry("ipv4     2 tcp      6 notanumber ESTABLISHED src=10.0.0.2 dst=10.0.0.1").is_none());
+        // A tuple needs both addresses
+        assert!(parse_entry("ipv4     2 tcp      6 10 ESTABLISHED src=10.0.0.2 sport=1 dport=2").is_none());
+    }
+
+    #[test]
+    fn filter_parses_every_key() {
+        let filter = ConntrackFilter::parse(" proto=TCP, src=10.0.0.2,dst=10.0.0.1,sport=51544,dport=22,state=established,mark=0 ").unwrap();
+        assert_eq!((filter.protocol.as_deref(), filter.state.as_deref()), (Some("tcp"), Some("ESTABLISHED")));
+        assert_eq!((filter.sport, filter.dport, filter.mark), (Some(51544), Some(22), Some(0)));
+        assert!(!filter.is_empty());
+        assert!(ConntrackFilter::parse("").unwrap().is_empty());
+
+        assert!(ConntrackFilter::parse("ssh from lan").unwrap_err().0.contains("expected key=value"));
+        assert!(ConntrackFilter::parse("rule=ssh").unwrap_err().0.contains("Unknown conntrack match key 'rule'"));
+        assert!(ConntrackFilter::parse("dport=http").unwrap_err().0.contains("Invalid value for 'dport'"));
+        assert!(ConntrackFilter::parse("src=10.0.0.0/8").is_err());
+    }
+
+    #[test]
+    fn filter_matches_the_original_tuple() {
+        let (tcp, udp) = (parse_entry(TCP_V4).unwrap(), parse_entry(UDP_V6).unwrap());
+        let matches = |expr: &str, entry: &ConntrackEntry| ConntrackFilter::parse(expr).unwrap().matches(entry);
+        assert!(matches("", &tcp) && matches("", &udp));
+        assert!(matches("proto=tcp,dport=22,src=10.0.0.2", &tcp));
+        assert!(!matches("proto=tcp,dport=22,src=10.0.0.2", &udp));
+        // The reply tuple is not matched
+        assert!(!matches("src=10.0.0.1", &tcp));
+        assert!(matches("dst=2001:db8::53,dport=53", &udp));
+        assert!(matches("state=established", &tcp) && !matches("state=established", &udp));
+        assert!(matches("mark=1129775105", &udp) && !matches("mark=1129775105", &tcp));
+    }
+
+    #[test]
+    fn only_expired_flows_are_benign_delete_failures() {
+        assert!(already_gone(&EngineError("conntrack v1.4.8 (conntrack-tools): 0 flow entries have been deleted.".to_string())));
+        assert!(already_gone(&EngineError("conntrack v1.4.5 (conntrack-tools): Operation failed: such conntrack doesn't exist".to_string())));
+        assert!(!already_gone(&EngineError("conntrack v1.4.8 (conntrack-tools): Operation failed: Operation not permitted".to_string())));
+        assert!(!already_gone(&EngineError("Failed to execute conntrack command: No such file or directory (os error 2)".to_string())));
+    }
+}
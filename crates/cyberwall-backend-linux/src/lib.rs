@@ -1,13 +1,73 @@
+pub mod boot;
+pub mod conntrack;
+pub mod nflog;
+pub mod nft;
+pub mod zones;
+
 use async_trait::async_trait;
+use cyberwall_core::export::{nat_chain_for, render_nft, render_nft_nat_chains, render_nft_nat_rule, NftOptions};
+use cyberwall_core::import::policy_from_nft_json;
 use cyberwall_core::{
-    EngineError, EngineResult, FirewallEngine, FirewallPolicy, FirewallRule, FirewallStatus, ProfileType, RuleAction, RuleDirection,
+    EmergencyMode, EngineError, EngineResult, FirewallEngine, FirewallPolicy, FirewallRule, FirewallStatus, NatRule, ProfileType, RuleStats,
 };
+use std::path::PathBuf;
+use zones::{LinkState, ZoneConfig, ZONES_PATH};
 
-pub struct LinuxFirewallEngine;
+pub struct LinuxFirewallEngine {
+    zones_path: PathBuf,
+    saved_policy_path: PathBuf,
+}
 
 impl LinuxFirewallEngine {
     pub fn new() -> Self {
-        Self
+        Self { zones_path: PathBuf::from(ZONES_PATH), saved_policy_path: PathBuf::from(boot::SAVED_POLICY_PATH) }
+    }
+
+    /// Uses a zone configuration file other than the default
+    pub fn with_zones_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.zones_path = path.into();
+        self
+    }
+
+    /// Saves applied policies somewhere other than the default boot restore path
+    pub fn with_saved_policy_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.saved_policy_path = path.into();
+        self
+    }
+
+    fn persist(&self, policy: &FirewallPolicy) -> EngineResult<()> {
+        boot::save(&self.saved_policy_path, policy).map_err(|e| EngineError(format!("Rules are live but will not survive a reboot: {}", e)))
+    }
+
+    /// Records the live NAT rules in the saved policy after a NAT change
+    async fn persist_nat(&self) -> EngineResult<()> {
+        // A saved policy that fails its MAC is reported, never silently overwritten
+        let mut policy = match self.saved_policy_path.exists() {
+            true => boot::load(&self.saved_policy_path)?.policy,
+            false => FirewallPolicy {
+                name: "cyberwall".to_string(),
+                version: "live".to_string(),
+                rules: self.list_rules().await?,
+                nat_rules: Vec::new(),
+                allow_neighbor_discovery: true,
+            },
+        };
+        policy.nat_rules = self.list_nat_rules().await?;
+        self.persist(&policy)
+    }
+
+    fn zones(&self) -> EngineResult<ZoneConfig> {
+        ZoneConfig::load(&self.zones_path)
+    }
+
+    /// Re-resolves zone membership against the current interfaces and NetworkManager connections
+    pub async fn refresh_zones(&self) -> EngineResult<()> {
+        let config = self.zones()?;
+        // Without a live table the zones are picked up by the next policy apply
+        if nft::table_exists(nft::TABLE).await {
+            nft::run_nft(&["-f", "-"], Some(&config.render(&LinkState::read().await))).await?;
+        }
+        Ok(())
     }
 }
 
@@ -22,65 +82,171 @@ impl FirewallEngine for LinuxFirewallEngine {
             .map(|o| o.status.success())
             .unwrap_or(false);
 
+        let table_active = is_nft_active && nft::table_exists(nft::TABLE).await;
         Ok(FirewallStatus {
             enabled: is_nft_active,
-            outbound_blocked: false,
+            outbound_blocked: nft::table_exists(nft::SHIELD_TABLE).await,
+            emergency: nft::emergency_kind().await,
             defender_active: false,
-            profile_private: is_nft_active,
-            profile_public: is_nft_active,
-            profile_domain: is_nft_active,
+            profiles: self.zones()?.profile_status(table_active, &LinkState::read().await),
             platform: "Linux".to_string(),
             backend_driver: "Linux Kernel nftables / eBPF Engine".to_string(),
         })
     }
 
     async fn set_enabled(&self, enabled: bool) -> EngineResult<()> {
-        let output = tokio::process::Command::new("nft")
-            .arg("flush")
-            .arg("ruleset")
-            .output()
-            .await
-            .map_err(|e| EngineError(format!("Failed to execute nft command: {}", e)))?;
-
-        if !output.status.success() {
-            let _ = tokio::process::Command::new("ufw")
-                .arg(if enabled { "enable" } else { "disable" })
-                .output()
-                .await;
+        // An engaged emergency mode is only lifted through the logged break-glass release
+        if let Some(kind) = nft::emergency_kind().await {
+            return Err(EngineError(format!("{:?} mode is engaged; release it through break-glass before enabling or disabling the firewall", kind)));
+        }
+        if enabled {
+            // Reinstalls the saved policy after a disable removed the table
+            if !nft::table_exists(nft::TABLE).await && self.saved_policy_path.exists() {
+                let saved = boot::load(&self.saved_policy_path)?;
+                return self.apply_policy(&saved.policy).await;
+            }
+            return Ok(());
+        }
+        // Only the cyberwall table goes; the shield and emergency tables stay in place
+        match nft::run_nft(&["delete", "table", nft::FAMILY, nft::TABLE], None).await {
+            Err(e) if !nft::is_missing(&e) => {
+                let ufw = tokio::process::Command::new("ufw").arg("disable").output().await;
+                if !ufw.is_ok_and(|o| o.status.success()) {
+                    return Err(e);
+                }
+            }
+            _ => {}
         }
-
         Ok(())
     }
 
+    async fn set_profile_enabled(&self, profile: ProfileType, enabled: bool) -> EngineResult<()> {
+        let mut config = self.zones()?;
+        for target in ProfileType::CONCRETE.into_iter().filter(|p| profile.covers(*p)) {
+            config.zone_mut(target).enabled = enabled;
+        }
+        config.save(&self.zones_path)?;
+        self.refresh_zones().await
+    }
+
     async fn set_outbound_block(&self, blocked: bool) -> EngineResult<()> {
         if blocked {
-            let _ = tokio::process::Command::new("iptables")
-                .args(&["-A", "OUTPUT", "-j", "DROP"])
-                .output()
-                .await;
+            nft::run_nft(&["-f", "-"], Some(&nft::render_shield())).await?;
         } else {
-            let _ = tokio::process::Command::new("iptables")
-                .args(&["-D", "OUTPUT", "-j", "DROP"])
-                .output()
-                .await;
+            match nft::run_nft(&["delete", "table", nft::FAMILY, nft::SHIELD_TABLE], None).await {
+                Err(e) if !nft::is_missing(&e) => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn set_emergency(&self, mode: Option<&EmergencyMode>) -> EngineResult<()> {
+        match mode {
+            Some(mode) => {
+                nft::run_nft(&["-f", "-"], Some(&nft::render_emergency(mode))).await?;
+                // The emergency chains already drop non-peer packets whatever their conntrack state;
+                // flushing only makes open sessions fail fast instead of timing out, so it must not fail the mode
+                if let Err(e) = conntrack::ConntrackTable::flush(&conntrack::ConntrackFilter::default()).await {
+                    eprintln!("[CYBERWALL] WARNING: {:?} mode is engaged but tracked connections were not flushed: {}", mode.kind(), e);
+                }
+            }
+            None => match nft::run_nft(&["delete", "table", nft::FAMILY, nft::EMERGENCY_TABLE], None).await {
+                Err(e) if !nft::is_missing(&e) => return Err(e),
+                _ => {}
+            },
         }
         Ok(())
     }
 
     async fn list_rules(&self) -> EngineResult<Vec<FirewallRule>> {
-        Ok(vec![
-            FirewallRule {
-                name: "Split2ops Linux nftables Core Chain".to_string(),
-                enabled: true,
-                action: RuleAction::Allow,
-                direction: RuleDirection::Inbound,
-                profile: ProfileType::All,
-                application: Some("/usr/local/bin/cyberwalld".to_string()),
+        match nft::table_json().await? {
+            Some(json) => Ok(policy_from_nft_json(&json)?.0),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn rule_stats(&self) -> EngineResult<Vec<RuleStats>> {
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+
+        // A rule spanning both address families is installed as one nft rule per family
+        let mut stats: Vec<RuleStats> = Vec::new();
+        // Uncommented rules are the zone chain jumps, which carry no counters
+        for r in nft::list_table().await?.into_iter().filter(|r| !r.is_nat() && !r.is_zone()) {
+            let Some(name) = r.comment else {
+                continue;
+            };
+            let last_hit = r.last_used_ms.map(|used| now_ms.saturating_sub(used) / 1000);
+            match stats.iter_mut().find(|s| s.name == name) {
+                Some(existing) => {
+                    existing.packets += r.packets;
+                    existing.bytes += r.bytes;
+                    existing.last_hit = existing.last_hit.max(last_hit);
+                }
+                None => stats.push(RuleStats { name, packets: r.packets, bytes: r.bytes, last_hit }),
             }
-        ])
+        }
+        Ok(stats)
     }
 
-    async fn apply_policy(&self, _policy: &FirewallPolicy) -> EngineResult<()> {
-        Ok(())
+    async fn list_nat_rules(&self) -> EngineResult<Vec<NatRule>> {
+        match nft::table_json().await? {
+            Some(json) => Ok(policy_from_nft_json(&json)?.1),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    async fn add_nat_rule(&self, rule: &NatRule) -> EngineResult<()> {
+        rule.validate().map_err(EngineError)?;
+        if self.list_nat_rules().await?.iter().any(|r| r.name == rule.name) {
+            return Err(EngineError(format!("A NAT rule named '{}' already exists", rule.name)));
+        }
+        let script = format!(
+            "{}add rule {} {} {} {}\n",
+            render_nft_nat_chains(),
+            nft::FAMILY,
+            nft::TABLE,
+            nat_chain_for(rule.kind),
+            render_nft_nat_rule(rule)
+        );
+        nft::run_nft(&["-f", "-"], Some(&script)).await?;
+        self.persist_nat().await
+    }
+
+    async fn remove_nat_rule(&self, name: &str) -> EngineResult<()> {
+        let rule = nft::list_table()
+            .await?
+            .into_iter()
+            .find(|r| r.is_nat() && r.comment.as_deref() == Some(name))
+            .ok_or_else(|| EngineError(format!("No NAT rule named '{}'", name)))?;
+        let handle = rule.handle.to_string();
+        nft::run_nft(&["delete", "rule", nft::FAMILY, nft::TABLE, &rule.chain, "handle", &handle], None).await?;
+        self.persist_nat().await
+    }
+
+    async fn apply_policy(&self, policy: &FirewallPolicy) -> EngineResult<()> {
+        for rule in &policy.rules {
+            rule.validate().map_err(EngineError)?;
+            if rule.application.is_some() {
+                return Err(EngineError(format!(
+                    "Rule '{}' is scoped to an application, which nftables cannot match",
+                    rule.name
+                )));
+            }
+        }
+        for rule in &policy.nat_rules {
+            rule.validate().map_err(EngineError)?;
+        }
+        let zones = self.zones()?.render(&LinkState::read().await);
+        let script = render_nft(policy, NftOptions::default()) + &zones;
+        if nft::run_nft(&["-f", "-"], Some(&script)).await.is_err() {
+            // Kernels older than 6.4 reject the `last` statement; fall back to plain counters
+            let script = render_nft(policy, NftOptions { track_last_hit: false, ..NftOptions::default() }) + &zones;
+            nft::run_nft(&["-f", "-"], Some(&script)).await?;
+        }
+        self.persist(policy)
     }
 }
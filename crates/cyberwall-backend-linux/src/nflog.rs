@@ -0,0 +1,538 @@
+use cyberwall_core::{EngineError, EngineResult};
+use serde::Serialize;
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket};
+
+pub use cyberwall_core::export::DEFAULT_NFLOG_GROUP;
+
+const NFULA_TIMESTAMP: u16 = 3;
+const NFULA_IFINDEX_INDEV: u16 = 4;
+const NFULA_IFINDEX_OUTDEV: u16 = 5;
+const NFULA_PAYLOAD: u16 = 9;
+const NFULA_PREFIX: u16 = 10;
+const NFULA_UID: u16 = 11;
+
+/// Structured record for a packet dropped by a cyberwall Block rule
+#[derive(Debug, Clone, Serialize)]
+pub struct DropEvent {
+    pub timestamp: String,
+    pub rule: String,
+    pub protocol: String,
+    pub src: Option<IpAddr>,
+    pub dst: Option<IpAddr>,
+    pub sport: Option<u16>,
+    pub dport: Option<u16>,
+    pub in_interface: Option<String>,
+    pub out_interface: Option<String>,
+    pub uid: Option<u32>,
+}
+
+/// Raw attributes of one NFULNL_MSG_PACKET message
+#[derive(Debug, Default)]
+pub struct NflogPacket {
+    pub prefix: Option<String>,
+    pub timestamp: Option<(u64, u64)>,
+    pub indev: Option<u32>,
+    pub outdev: Option<u32>,
+    pub uid: Option<u32>,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PacketHeaders {
+    pub protocol: String,
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    pub sport: Option<u16>,
+    pub dport: Option<u16>,
+}
+
+fn be_u16(b: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(b.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn be_u32(b: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(b.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn be_u64(b: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(b.get(at..at + 8)?.try_into().ok()?))
+}
+
+fn protocol_name(proto: u8) -> String {
+    match proto {
+        1 => "icmp".to_string(),
+        6 => "tcp".to_string(),
+        17 => "udp".to_string(),
+        58 => "icmpv6".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Follows IPv6 extension headers to the upper-layer protocol; also reports whether the packet is a
+/// non-first fragment, which carries no transport header
+fn ipv6_upper_layer(mut next: u8, mut rest: &[u8]) -> Option<(u8, &[u8], bool)> {
+    let mut later_fragment = false;
+    loop {
+        let len = match next {
+            // Hop-by-hop, routing, destination options, mobility, HIP and shim6 share the generic layout
+            0 | 43 | 60 | 135 | 139 | 140 => (*rest.get(1)? as usize + 1) * 8,
+            44 => {
+                later_fragment = be_u16(rest, 2)? >> 3 != 0;
+                8
+            }
+            // Authentication header length is in 4-octet units
+            51 => (*rest.get(1)? as usize + 2) * 4,
+            _ => return Some((next, rest, later_fragment)),
+        };
+        next = *rest.first()?;
+        rest = rest.get(len..)?;
+    }
+}
+
+/// Decodes the network and transport headers of a raw IPv4/IPv6 packet
+pub fn decode_headers(packet: &[u8]) -> Option<PacketHeaders> {
+    let (proto, src, dst, l4, later_fragment) = match packet.first()? >> 4 {
+        4 => {
+            let ihl = (packet[0] & 0x0f) as usize * 4;
+            if ihl < 20 {
+                return None;
+            }
+            let src: [u8; 4] = packet.get(12..16)?.try_into().ok()?;
+            let dst: [u8; 4] = packet.get(16..20)?.try_into().ok()?;
+            let later_fragment = be_u16(packet, 6)? & 0x1fff != 0;
+            (*packet.get(9)?, IpAddr::V4(Ipv4Addr::from(src)), IpAddr::V4(Ipv4Addr::from(dst)), packet.get(ihl..)?, later_fragment)
+        }
+        6 => {
+            let src: [u8; 16] = packet.get(8..24)?.try_into().ok()?;
+            let dst: [u8; 16] = packet.get(24..40)?.try_into().ok()?;
+            let (proto, l4, later_fragment) = ipv6_upper_layer(*packet.get(6)?, packet.get(40..)?)?;
+            (proto, IpAddr::V6(Ipv6Addr::from(src)), IpAddr::V6(Ipv6Addr::from(dst)), l4, later_fragment)
+        }
+        _ => return None,
+    };
+
+    let (sport, dport) = match proto {
+        6 | 17 if !later_fragment => (be_u16(l4, 0), be_u16(l4, 2)),
+        _ => (None, None),
+    };
+
+    Some(PacketHeaders { protocol: protocol_name(proto), src, dst, sport, dport })
+}
+
+/// Parses the netlink attributes following the nfgenmsg header of an NFLOG packet message
+pub fn parse_attributes(mut attrs: &[u8]) -> NflogPacket {
+    let mut packet = NflogPacket::default();
+    while attrs.len() >= 4 {
+        let len = u16::from_ne_bytes([attrs[0], attrs[1]]) as usize;
+        let kind = u16::from_ne_bytes([attrs[2], attrs[3]]) & 0x3fff;
+        if len < 4 || len > attrs.len() {
+            break;
+        }
+        let value = &attrs[4..len];
+        match kind {
+            NFULA_PREFIX => {
+                let end = value.iter().position(|b| *b == 0).unwrap_or(value.len());
+                packet.prefix = Some(String::from_utf8_lossy(&value[..end]).into_owned());
+            }
+            NFULA_TIMESTAMP => packet.timestamp = be_u64(value, 0).zip(be_u64(value, 8)),
+            NFULA_IFINDEX_INDEV => packet.indev = be_u32(value, 0),
+            NFULA_IFINDEX_OUTDEV => packet.outdev = be_u32(value, 0),
+            NFULA_UID => packet.uid = be_u32(value, 0),
+            NFULA_PAYLOAD => packet.payload = value.to_vec(),
+            _ => {}
+        }
+        let aligned = (len + 3) & !3;
+        attrs = attrs.get(aligned..).unwrap_or_default();
+    }
+    packet
+}
+
+fn interface_name(index: u32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut buf = [0 as libc::c_char; libc::IF_NAMESIZE];
+        // SAFETY: buf is IF_NAMESIZE bytes as required by if_indextoname
+        let ptr = unsafe { libc::if_indextoname(index, buf.as_mut_ptr()) };
+        if !ptr.is_null() {
+            // SAFETY: if_indextoname wrote a NUL-terminated name into buf
+            let name = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) };
+            return Some(name.to_string_lossy().into_owned());
+        }
+    }
+    Some(format!("if{}", index))
+}
+
+impl DropEvent {
+    pub fn from_packet(packet: &NflogPacket) -> Self {
+        let headers = decode_headers(&packet.payload);
+        let timestamp = packet
+            .timestamp
+            .and_then(|(sec, usec)| chrono::DateTime::from_timestamp(sec as i64, (usec * 1000) as u32))
+            .unwrap_or_else(chrono::Utc::now);
+
+        DropEvent {
+            timestamp: timestamp.to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            rule: packet.prefix.clone().unwrap_or_else(|| "unknown".to_string()),
+            protocol: headers.as_ref().map(|h| h.protocol.clone()).unwrap_or_else(|| "unknown".to_string()),
+            src: headers.as_ref().map(|h| h.src),
+            dst: headers.as_ref().map(|h| h.dst),
+            sport: headers.as_ref().and_then(|h| h.sport),
+            dport: headers.as_ref().and_then(|h| h.dport),
+            in_interface: packet.indev.and_then(interface_name),
+            out_interface: packet.outdev.and_then(interface_name),
+            uid: packet.uid,
+        }
+    }
+
+    /// Shapes the drop as a cybersiem ingest event
+    pub fn to_siem_event(&self) -> serde_json::Value {
+        let fmt_ep = |addr: Option<IpAddr>, port: Option<u16>| match (addr, port) {
+            (Some(a), Some(p)) => format!("{}:{}", a, p),
+            (Some(a), None) => a.to_string(),
+            _ => "?".to_string(),
+        };
+        serde_json::json!({
+            "timestamp": self.timestamp,
+            "source": "cyberwall",
+            "severity": "WARN",
+            "event_type": "FIREWALL_DROP",
+            "message": format!(
+                "Rule '{}' dropped {} {} -> {}",
+                self.rule,
+                self.protocol,
+                fmt_ep(self.src, self.sport),
+                fmt_ep(self.dst, self.dport)
+            ),
+            "node_ip": self.dst.map(|a| a.to_string()).unwrap_or_default(),
+        })
+    }
+}
+
+/// Destinations for decoded drop events
+pub struct DropSink {
+    pub writer: Box<dyn Write + Send>,
+    pub siem: Option<(UdpSocket, String)>,
+}
+
+impl DropSink {
+    pub fn emit(&mut self, event: &DropEvent) -> EngineResult<()> {
+        let line = serde_json::to_string(event).map_err(|e| EngineError(e.to_string()))?;
+        writeln!(self.writer, "{}", line).map_err(|e| EngineError(format!("Failed to write drop log: {}", e)))?;
+        self.writer.flush().map_err(|e| EngineError(e.to_string()))?;
+        if let Some((socket, target)) = &self.siem {
+            // SIEM forwarding is best-effort; the local log stays authoritative
+            let _ = socket.send_to(event.to_siem_event().to_string().as_bytes(), target.as_str());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use socket::NflogCollector;
+
+#[cfg(target_os = "linux")]
+mod socket {
+    use super::{parse_attributes, DropEvent, DropSink};
+    use cyberwall_core::{EngineError, EngineResult};
+    use std::io;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    const NETLINK_NETFILTER: libc::c_int = 12;
+    const NFNL_SUBSYS_ULOG: u16 = 4;
+    const NFULNL_MSG_PACKET: u16 = 0;
+    const NFULNL_MSG_CONFIG: u16 = 1;
+    const NFULA_CFG_CMD: u16 = 1;
+    const NFULA_CFG_MODE: u16 = 2;
+    const NFULA_CFG_FLAGS: u16 = 6;
+    const NFULNL_CFG_CMD_BIND: u8 = 1;
+    const NFULNL_COPY_PACKET: u8 = 2;
+    const NFULNL_CFG_F_UID: u16 = 0x0008;
+    const NLMSG_HDRLEN: usize = 16;
+    const NFGENMSG_LEN: usize = 4;
+    const NLMSG_ERROR: u16 = 2;
+    /// How long a read waits before `run` checks its stop flag again
+    const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Netlink subscriber for one NFLOG group
+    pub struct NflogCollector {
+        fd: libc::c_int,
+        group: u16,
+        seq: u32,
+    }
+
+    impl NflogCollector {
+        pub fn open(group: u16) -> EngineResult<Self> {
+            // SAFETY: plain socket(2) call, the fd is owned by the returned collector
+            let fd = unsafe { libc::socket(libc::AF_NETLINK, libc::SOCK_RAW | libc::SOCK_CLOEXEC, NETLINK_NETFILTER) };
+            if fd < 0 {
+                return Err(EngineError(format!("Failed to open netfilter netlink socket: {}", io::Error::last_os_error())));
+            }
+            let mut collector = Self { fd, group, seq: 0 };
+
+            // SAFETY: sockaddr_nl is plain old data, zeroed is a valid initial value
+            let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+            addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+            // SAFETY: addr is a valid sockaddr_nl for the duration of the call
+            let rc = unsafe {
+                libc::bind(
+                    fd,
+                    &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                    std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+                )
+            };
+            if rc < 0 {
+                return Err(EngineError(format!("Failed to bind netlink socket: {}", io::Error::last_os_error())));
+            }
+
+            collector.configure(NFULA_CFG_CMD, &[NFULNL_CFG_CMD_BIND])?;
+            let mut mode = 0xffffu32.to_be_bytes().to_vec();
+            mode.extend([NFULNL_COPY_PACKET, 0]);
+            collector.configure(NFULA_CFG_MODE, &mode)?;
+            // Without this flag the kernel leaves NFULA_UID out of every packet
+            collector.configure(NFULA_CFG_FLAGS, &NFULNL_CFG_F_UID.to_be_bytes())?;
+
+            let timeout = libc::timeval { tv_sec: 0, tv_usec: POLL_INTERVAL.as_micros() as libc::suseconds_t };
+            // SAFETY: timeout is a valid timeval for the duration of the call
+            let rc = unsafe {
+                libc::setsockopt(
+                    fd,
+                    libc::SOL_SOCKET,
+                    libc::SO_RCVTIMEO,
+                    &timeout as *const libc::timeval as *const libc::c_void,
+                    std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+                )
+            };
+            if rc < 0 {
+                return Err(EngineError(format!("Failed to set netlink receive timeout: {}", io::Error::last_os_error())));
+            }
+            Ok(collector)
+        }
+
+        fn configure(&mut self, attr: u16, value: &[u8]) -> EngineResult<()> {
+            self.seq += 1;
+            let attr_len = 4 + value.len();
+            let total = NLMSG_HDRLEN + NFGENMSG_LEN + ((attr_len + 3) & !3);
+
+            let mut msg = Vec::with_capacity(total);
+            msg.extend((total as u32).to_ne_bytes());
+            msg.extend(((NFNL_SUBSYS_ULOG << 8) | NFULNL_MSG_CONFIG).to_ne_bytes());
+            msg.extend(((libc::NLM_F_REQUEST | libc::NLM_F_ACK) as u16).to_ne_bytes());
+            msg.extend(self.seq.to_ne_bytes());
+            msg.extend(0u32.to_ne_bytes());
+            msg.extend([libc::AF_UNSPEC as u8, 0]);
+            msg.extend(self.group.to_be_bytes());
+            msg.extend((attr_len as u16).to_ne_bytes());
+            msg.extend(attr.to_ne_bytes());
+            msg.extend(value);
+            msg.resize(total, 0);
+
+            // SAFETY: msg is a valid buffer of msg.len() bytes
+            let sent = unsafe { libc::send(self.fd, msg.as_ptr() as *const libc::c_void, msg.len(), 0) };
+            if sent < 0 {
+                return Err(EngineError(format!("Failed to configure NFLOG group: {}", io::Error::last_os_error())));
+            }
+
+            let mut buf = vec![0u8; 8192];
+            let len = self.recv(&mut buf).map_err(|e| EngineError(format!("Failed to read from netlink socket: {}", e)))?;
+            if len >= NLMSG_HDRLEN + 4 && u16::from_ne_bytes([buf[4], buf[5]]) == NLMSG_ERROR {
+                let errno = i32::from_ne_bytes([buf[16], buf[17], buf[18], buf[19]]);
+                if errno != 0 {
+                    return Err(EngineError(format!(
+                        "Kernel rejected NFLOG group {} configuration: {}",
+                        self.group,
+                        io::Error::from_raw_os_error(-errno)
+                    )));
+                }
+            }
+            Ok(())
+        }
+
+        fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+            // SAFETY: buf is a valid writable buffer of buf.len() bytes
+            let len = unsafe { libc::recv(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0) };
+            if len < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(len as usize)
+        }
+
+        /// Blocks reading NFLOG messages and emits a DropEvent for each logged packet until `stop` is set
+        pub fn run(&mut self, sink: &mut DropSink, stop: &AtomicBool) -> EngineResult<()> {
+            let mut buf = vec![0u8; 65536];
+            while !stop.load(Ordering::Relaxed) {
+                let len = match self.recv(&mut buf) {
+                    Ok(len) => len,
+                    Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted) => continue,
+                    Err(e) => return Err(EngineError(format!("Failed to read from netlink socket: {}", e))),
+                };
+                let mut data = &buf[..len];
+                while data.len() >= NLMSG_HDRLEN {
+                    let msg_len = u32::from_ne_bytes([data[0], data[1], data[2], data[3]]) as usize;
+                    let msg_type = u16::from_ne_bytes([data[4], data[5]]);
+                    if msg_len < NLMSG_HDRLEN || msg_len > data.len() {
+                        break;
+                    }
+                    if msg_type == (NFNL_SUBSYS_ULOG << 8) | NFULNL_MSG_PACKET {
+                        if let Some(attrs) = data.get(NLMSG_HDRLEN + NFGENMSG_LEN..msg_len) {
+                            sink.emit(&DropEvent::from_packet(&parse_attributes(attrs)))?;
+                        }
+                    }
+                    data = data.get((msg_len + 3) & !3..).unwrap_or_default();
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for NflogCollector {
+        fn drop(&mut self) {
+            // SAFETY: fd was opened by NflogCollector::open and is closed exactly once
+            unsafe { libc::close(self.fd) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 20-byte IPv4 header from 192.0.2.7 to 192.0.2.1 followed by `l4`
+    fn ipv4(proto: u8, frag: u16, l4: &[u8]) -> Vec<u8> {
+        let mut p = vec![0x45, 0, 0, 0, 0x12, 0x34];
+        p.extend(frag.to_be_bytes());
+        p.extend([64, proto, 0, 0, 192, 0, 2, 7, 192, 0, 2, 1]);
+        p.extend(l4);
+        p
+    }
+
+    /// 40-byte IPv6 header from 2001:db8::2 to 2001:db8::53 followed by `rest`
+    fn ipv6(next: u8, rest: &[u8]) -> Vec<u8> {
+        let mut p = vec![0x60, 0, 0, 0];
+        p.extend((rest.len() as u16).to_be_bytes());
+        p.extend([next, 64]);
+        p.extend("2001:db8::2".parse::<Ipv6Addr>().unwrap().octets());
+        p.extend("2001:db8::53".parse::<Ipv6Addr>().unwrap().octets());
+        p.extend(rest);
+        p
+    }
+
+    /// Source port 51544, destination port 22, SYN
+    const TCP: [u8; 20] = [0xc9, 0x58, 0x00, 0x16, 0, 0, 0, 1, 0, 0, 0, 0, 0x50, 0x02, 0xfa, 0xf0, 0, 0, 0, 0];
+    /// Source port 40114, destination port 53
+    const UDP: [u8; 8] = [0x9c, 0xb2, 0x00, 0x35, 0x00, 0x08, 0, 0];
+
+    fn ports(headers: &PacketHeaders) -> (&str, Option<u16>, Option<u16>) {
+        (headers.protocol.as_str(), headers.sport, headers.dport)
+    }
+
+    #[test]
+    fn decodes_ipv4_tcp_and_udp() {
+        let tcp = decode_headers(&ipv4(6, 0x4000, &TCP)).unwrap();
+        assert_eq!(ports(&tcp), ("tcp", Some(51544), Some(22)));
+        assert_eq!((tcp.src, tcp.dst), ("192.0.2.7".parse().unwrap(), "192.0.2.1".parse().unwrap()));
+        assert_eq!(ports(&decode_headers(&ipv4(17, 0, &UDP)).unwrap()), ("udp", Some(40114), Some(53)));
+
+        // Options push the transport header back
+        let mut with_options = ipv4(17, 0, &[1, 1, 1, 0]);
+        with_options[0] = 0x46;
+        with_options.extend(UDP);
+        assert_eq!(ports(&decode_headers(&with_options).unwrap()), ("udp", Some(40114), Some(53)));
+
+        // A later fragment starts with payload, not ports
+        assert_eq!(ports(&decode_headers(&ipv4(17, 0x00b9, &UDP)).unwrap()), ("udp", None, None));
+    }
+
+    #[test]
+    fn decodes_ipv6_tcp_and_udp() {
+        let tcp = decode_headers(&ipv6(6, &TCP)).unwrap();
+        assert_eq!(ports(&tcp), ("tcp", Some(51544), Some(22)));
+        assert_eq!(tcp.dst, "2001:db8::53".parse::<IpAddr>().unwrap());
+        assert_eq!(ports(&decode_headers(&ipv6(17, &UDP)).unwrap()), ("udp", Some(40114), Some(53)));
+        assert_eq!(ports(&decode_headers(&ipv6(58, &[128, 0, 0, 0])).unwrap()), ("icmpv6", None, None));
+    }
+
+    #[test]
+    fn walks_ipv6_extension_headers() {
+        // Hop-by-hop (router alert, 8 bytes), destination options (16 bytes), then a first fragment
+        let mut rest = vec![60, 0, 5, 2, 0, 0, 1, 0];
+        rest.extend([44, 1, 1, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        rest.extend([17, 0, 0x00, 0x01, 0, 0, 0, 42]);
+        rest.extend(UDP);
+        assert_eq!(ports(&decode_headers(&ipv6(0, &rest)).unwrap()), ("udp", Some(40114), Some(53)));
+
+        // Authentication header: payload length 4 means (4 + 2) * 4 = 24 bytes
+        let mut ah = vec![6, 4, 0, 0];
+        ah.extend([0; 20]);
+        ah.extend(TCP);
+        assert_eq!(ports(&decode_headers(&ipv6(51, &ah)).unwrap()), ("tcp", Some(51544), Some(22)));
+
+        // A non-first fragment has no ports even though the fragment header names TCP
+        let mut later = vec![6, 0, 0x05, 0xa9, 0, 0, 0, 42];
+        later.extend(TCP);
+        assert_eq!(ports(&decode_headers(&ipv6(44, &later)).unwrap()), ("tcp", None, None));
+
+        // ESP hides the upper layer
+        assert_eq!(ports(&decode_headers(&ipv6(50, &[0; 16])).unwrap()), ("50", None, None));
+    }
+
+    #[test]
+    fn truncated_packets_do_not_decode() {
+        assert!(decode_headers(&[]).is_none());
+        assert!(decode_headers(&ipv4(6, 0, &[])[..19]).is_none());
+        assert!(decode_headers(&ipv6(6, &[])[..39]).is_none());
+        // An extension header running past the end of the packet
+        assert!(decode_headers(&ipv6(0, &[17, 3, 0, 0])).is_none());
+        let mut bad_ihl = ipv4(6, 0, &TCP);
+        bad_ihl[0] = 0x44;
+        assert!(decode_headers(&bad_ihl).is_none());
+        // A transport header cut short keeps the addresses
+        assert_eq!(ports(&decode_headers(&ipv4(6, 0, &TCP[..3])).unwrap()), ("tcp", Some(51544), None));
+    }
+
+    fn attr(kind: u16, value: &[u8]) -> Vec<u8> {
+        let len = 4 + value.len() as u16;
+        let mut a = len.to_ne_bytes().to_vec();
+        a.extend(kind.to_ne_bytes());
+        a.extend(value);
+        a.resize(a.len().next_multiple_of(4), 0);
+        a
+    }
+
+    #[test]
+    fn parses_nflog_attributes() {
+        let payload = ipv4(17, 0, &UDP);
+        let mut timestamp = 1_790_000_000u64.to_be_bytes().to_vec();
+        timestamp.extend(250_000u64.to_be_bytes());
+        let attrs = [
+            attr(NFULA_PREFIX, b"no smb on public\0"),
+            attr(NFULA_TIMESTAMP, &timestamp),
+            attr(NFULA_IFINDEX_INDEV, &2u32.to_be_bytes()),
+            // Unknown attributes and the netlink byte-order flag are skipped
+            attr(1, &[0, 0x08, 0x00, 0x03]),
+            attr(NFULA_UID | 0x4000, &1000u32.to_be_bytes()),
+            attr(NFULA_PAYLOAD, &payload),
+        ]
+        .concat();
+        let packet = parse_attributes(&attrs);
+        assert_eq!(packet.prefix.as_deref(), Some("no smb on public"));
+        assert_eq!(packet.timestamp, Some((1_790_000_000, 250_000)));
+        assert_eq!((packet.indev, packet.outdev, packet.uid), (Some(2), None, Some(1000)));
+        assert_eq!(packet.payload, payload);
+        assert_eq!(ports(&decode_headers(&packet.payload).unwrap()), ("udp", Some(40114), Some(53)));
+    }
+
+    #[test]
+    fn stops_at_a_malformed_attribute() {
+        let mut attrs = attr(NFULA_PREFIX, b"ssh\0");
+        // Claims 64 bytes but only 8 follow
+        attrs.extend(64u16.to_ne_bytes());
+        attrs.extend(NFULA_PAYLOAD.to_ne_bytes());
+        attrs.extend([0x45, 0, 0, 0]);
+        let packet = parse_attributes(&attrs);
+        assert_eq!(packet.prefix.as_deref(), Some("ssh"));
+        assert!(packet.payload.is_empty());
+        assert!(parse_attributes(&[1, 0]).prefix.is_none());
+    }
+}
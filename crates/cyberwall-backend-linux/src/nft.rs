@@ -0,0 +1,208 @@
+use cyberwall_core::export::{EMERGENCY_MARK, NAT_CHAINS, NFT_FAMILY, NFT_TABLE, ZONE_CHAINS};
+use cyberwall_core::{EmergencyKind, EmergencyMode, EmergencyPeer, EngineError, EngineResult};
+use serde_json::Value;
+use std::fmt::Write;
+use tokio::io::AsyncWriteExt;
+
+pub const TABLE: &str = NFT_TABLE;
+pub const FAMILY: &str = NFT_FAMILY;
+/// Separate table for the outbound shield so policy applies never lift it
+pub const SHIELD_TABLE: &str = "cyberwall_shield";
+
+/// Separate table for emergency modes, evaluated before the shield and the policy
+pub const EMERGENCY_TABLE: &str = "cyberwall_emergency";
+
+/// A rule read back from the live cyberwall table
+#[derive(Debug, Clone)]
+pub struct NftRule {
+    pub chain: String,
+    pub handle: u64,
+    pub comment: Option<String>,
+    pub packets: u64,
+    pub bytes: u64,
+    pub last_used_ms: Option<u64>,
+}
+
+impl NftRule {
+    pub fn is_nat(&self) -> bool {
+        NAT_CHAINS.iter().any(|(chain, _)| *chain == self.chain)
+    }
+
+    pub fn is_zone(&self) -> bool {
+        ZONE_CHAINS.contains(&self.chain.as_str())
+    }
+}
+
+/// Renders the outbound shield table: all egress dropped on both families except loopback
+pub fn render_shield() -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "table {} {} {{}}", FAMILY, SHIELD_TABLE);
+    let _ = writeln!(out, "delete table {} {}", FAMILY, SHIELD_TABLE);
+    let _ = writeln!(out, "table {} {} {{", FAMILY, SHIELD_TABLE);
+    let _ = writeln!(out, "    chain output {{");
+    let _ = writeln!(out, "        type filter hook output priority filter - 10; policy accept;");
+    let _ = writeln!(out, "        oif \"lo\" accept");
+    let _ = writeln!(out, "        meta mark {:#x} accept", EMERGENCY_MARK);
+    let _ = writeln!(out, "        counter drop comment \"cyberwall outbound shield\"");
+    let _ = writeln!(out, "    }}");
+    let _ = writeln!(out, "}}");
+    out
+}
+
+fn emergency_comment(kind: EmergencyKind) -> &'static str {
+    match kind {
+        EmergencyKind::Isolate => "cyberwall emergency isolate",
+        EmergencyKind::Quarantine => "cyberwall emergency quarantine",
+    }
+}
+
+/// Marks and accepts a peer's packets so the shield and policy chains let them through as well
+fn peer_rule(peer: &EmergencyPeer, direction: &str, port_field: &str, established: bool) -> String {
+    let family = if peer.address.contains(':') { "ip6" } else { "ip" };
+    let mut rule = format!("{} {} {}", family, direction, peer.address);
+    if let Some(port) = peer.port {
+        let _ = write!(rule, " th {} {}", port_field, port);
+    }
+    if established {
+        rule.push_str(" ct state established,related");
+    }
+    let _ = write!(rule, " meta mark set {:#x} accept", EMERGENCY_MARK);
+    rule
+}
+
+/// Per-peer input and output rules: isolation admits the peers both ways, quarantine only lets this host reach them
+fn peer_rules(mode: &EmergencyMode) -> (Vec<String>, Vec<String>) {
+    let inbound = matches!(mode, EmergencyMode::Isolate { .. });
+    let (mut input, mut output) = (Vec::new(), Vec::new());
+    for peer in mode.peers() {
+        output.push(peer_rule(peer, "daddr", "dport", false));
+        match (inbound, peer.port) {
+            // Without a port the peer's address alone is the whole channel
+            (true, None) => input.push(peer_rule(peer, "saddr", "dport", false)),
+            (true, Some(_)) => {
+                input.push(peer_rule(peer, "saddr", "dport", false));
+                input.push(peer_rule(peer, "saddr", "sport", true));
+                output.push(peer_rule(peer, "daddr", "sport", true));
+            }
+            (false, _) => input.push(peer_rule(peer, "saddr", "sport", true)),
+        }
+    }
+    (input, output)
+}
+
+/// Renders the emergency table; only traffic to and from the mode's peers is accepted, and it is marked
+pub fn render_emergency(mode: &EmergencyMode) -> String {
+    let comment = emergency_comment(mode.kind());
+    let (input, output) = peer_rules(mode);
+    let mut out = String::new();
+    let _ = writeln!(out, "table {} {} {{}}", FAMILY, EMERGENCY_TABLE);
+    let _ = writeln!(out, "delete table {} {}", FAMILY, EMERGENCY_TABLE);
+    let _ = writeln!(out, "table {} {} {{", FAMILY, EMERGENCY_TABLE);
+    for (chain, iface, peers) in [("input", Some("iif"), input), ("output", Some("oif"), output), ("forward", None, Vec::new())] {
+        let _ = writeln!(out, "    chain {} {{", chain);
+        let _ = writeln!(out, "        type filter hook {} priority filter - 20; policy accept;", chain);
+        if let Some(iface) = iface {
+            let _ = writeln!(out, "        {} \"lo\" accept", iface);
+        }
+        for rule in peers {
+            let _ = writeln!(out, "        {}", rule);
+        }
+        let _ = writeln!(out, "        counter drop comment \"{}\"", comment);
+        let _ = writeln!(out, "    }}");
+    }
+    let _ = writeln!(out, "}}");
+    out
+}
+
+/// Emergency mode installed in the kernel, read back from the table's drop rule
+pub async fn emergency_kind() -> Option<EmergencyKind> {
+    let listing = run_nft(&["list", "table", FAMILY, EMERGENCY_TABLE], None).await.ok()?;
+    [EmergencyKind::Isolate, EmergencyKind::Quarantine].into_iter().find(|kind| listing.contains(emergency_comment(*kind)))
+}
+
+/// Parses `nft -j list table inet cyberwall` output into rules with their counters
+pub fn parse_ruleset(json: &str) -> EngineResult<Vec<NftRule>> {
+    let doc: Value = serde_json::from_str(json).map_err(|e| EngineError(format!("Invalid nft JSON output: {}", e)))?;
+    let items = doc
+        .get("nftables")
+        .and_then(Value::as_array)
+        .ok_or_else(|| EngineError("nft JSON output has no 'nftables' array".to_string()))?;
+
+    let mut rules = Vec::new();
+    for rule in items.iter().filter_map(|item| item.get("rule")) {
+        if rule.get("table").and_then(Value::as_str) != Some(TABLE) {
+            continue;
+        }
+        let mut parsed = NftRule {
+            chain: rule.get("chain").and_then(Value::as_str).unwrap_or_default().to_string(),
+            handle: rule.get("handle").and_then(Value::as_u64).unwrap_or_default(),
+            comment: rule.get("comment").and_then(Value::as_str).map(str::to_string),
+            packets: 0,
+            bytes: 0,
+            last_used_ms: None,
+        };
+        for expr in rule.get("expr").and_then(Value::as_array).into_iter().flatten() {
+            if let Some(counter) = expr.get("counter") {
+                parsed.packets = counter.get("packets").and_then(Value::as_u64).unwrap_or_default();
+                parsed.bytes = counter.get("bytes").and_then(Value::as_u64).unwrap_or_default();
+            } else if let Some(last) = expr.get("last") {
+                parsed.last_used_ms = last.get("used").and_then(Value::as_u64);
+            }
+        }
+        rules.push(parsed);
+    }
+    Ok(rules)
+}
+
+/// Runs `nft` with the given arguments, optionally feeding a script on stdin
+pub async fn run_nft(args: &[&str], stdin: Option<&str>) -> EngineResult<String> {
+    let mut child = tokio::process::Command::new("nft")
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| EngineError(format!("Failed to execute nft command: {}", e)))?;
+
+    if let Some(script) = stdin {
+        let mut pipe = child.stdin.take().ok_or_else(|| EngineError("nft stdin unavailable".to_string()))?;
+        pipe.write_all(script.as_bytes())
+            .await
+            .map_err(|e| EngineError(format!("Failed to write nft script: {}", e)))?;
+    }
+    drop(child.stdin.take());
+
+    let output = child
+        .wait_with_output()
+        .await
+        .map_err(|e| EngineError(format!("Failed to wait for nft command: {}", e)))?;
+    if !output.status.success() {
+        return Err(EngineError(format!("nft failed: {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns the live cyberwall table as nft JSON, or None when it is not installed
+pub async fn table_json() -> EngineResult<Option<String>> {
+    match run_nft(&["-j", "list", "table", FAMILY, TABLE], None).await {
+        Ok(json) => Ok(Some(json)),
+        Err(e) if is_missing(&e) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Reads the live cyberwall table; an absent table yields no rules
+pub async fn list_table() -> EngineResult<Vec<NftRule>> {
+    match table_json().await? {
+        Some(json) => parse_ruleset(&json),
+        None => Ok(Vec::new()),
+    }
+}
+
+pub fn is_missing(err: &EngineError) -> bool {
+    err.0.contains("No such file or directory")
+}
+
+pub async fn table_exists(table: &str) -> bool {
+    run_nft(&["list", "table", FAMILY, table], None).await.is_ok()
+}
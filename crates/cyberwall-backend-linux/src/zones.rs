@@ -0,0 +1,379 @@
+use crate::nft::{FAMILY, TABLE};
+use cyberwall_core::export::{
+    iface_field, quote_string, zone_chains_for, zone_set_for, DEFAULT_NFLOG_GROUP, ZONE_CHAINS, ZONE_SETS,
+};
+use cyberwall_core::{EngineError, EngineResult, ProfileStatus, ProfileType, RuleAction, RuleDirection};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::path::Path;
+
+/// Default location of the interface zone configuration
+pub const ZONES_PATH: &str = "/etc/cyberwall/zones.json";
+
+/// Binds network interfaces to a profile, standing in for Windows network profiles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Zone {
+    pub profile: ProfileType,
+    /// Interface names; a trailing `*` matches by prefix, e.g. `wlan*`
+    #[serde(default)]
+    pub interfaces: Vec<String>,
+    /// NetworkManager connection IDs; the device of each active connection joins the zone
+    #[serde(default)]
+    pub connections: Vec<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_allow")]
+    pub default_inbound: RuleAction,
+    #[serde(default = "default_allow")]
+    pub default_outbound: RuleAction,
+    #[serde(default)]
+    pub log_allowed: bool,
+    #[serde(default)]
+    pub log_dropped: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_allow() -> RuleAction {
+    RuleAction::Allow
+}
+
+impl Zone {
+    pub fn new(profile: ProfileType) -> Self {
+        Self {
+            profile,
+            interfaces: Vec::new(),
+            connections: Vec::new(),
+            enabled: true,
+            default_inbound: RuleAction::Allow,
+            default_outbound: RuleAction::Allow,
+            log_allowed: false,
+            log_dropped: false,
+        }
+    }
+
+    fn default_for(&self, direction: RuleDirection) -> RuleAction {
+        match direction {
+            RuleDirection::Inbound => self.default_inbound,
+            RuleDirection::Outbound => self.default_outbound,
+        }
+    }
+
+    fn label(&self) -> String {
+        format!("cyberwall zone {}", format!("{:?}", self.profile).to_lowercase())
+    }
+
+    /// Concrete interfaces currently in the zone
+    pub fn resolve(&self, links: &LinkState) -> Vec<String> {
+        let mut resolved: Vec<String> = Vec::new();
+        for pattern in &self.interfaces {
+            match pattern.strip_suffix('*') {
+                Some(prefix) => resolved.extend(links.interfaces.iter().filter(|i| i.starts_with(prefix)).cloned()),
+                None => resolved.push(pattern.clone()),
+            }
+        }
+        for (id, device) in &links.connections {
+            if self.connections.contains(id) {
+                resolved.push(device.clone());
+            }
+        }
+        resolved.sort();
+        resolved.dedup();
+        resolved
+    }
+}
+
+/// Interfaces present on the host and the devices of active NetworkManager connections
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkState {
+    pub interfaces: Vec<String>,
+    /// (connection ID, device) pairs
+    pub connections: Vec<(String, String)>,
+}
+
+impl LinkState {
+    /// Reads `/sys/class/net` and, when NetworkManager is running, its active connections
+    pub async fn read() -> Self {
+        let mut interfaces = Vec::new();
+        if let Ok(mut dir) = tokio::fs::read_dir("/sys/class/net").await {
+            while let Ok(Some(entry)) = dir.next_entry().await {
+                interfaces.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        interfaces.sort();
+
+        let connections = match tokio::process::Command::new("nmcli")
+            .args(["-t", "-f", "NAME,DEVICE", "connection", "show", "--active"])
+            .output()
+            .await
+        {
+            Ok(output) if output.status.success() => parse_nmcli_active(&String::from_utf8_lossy(&output.stdout)),
+            _ => Vec::new(),
+        };
+        Self { interfaces, connections }
+    }
+}
+
+/// Parses `nmcli -t -f NAME,DEVICE connection show --active`; terse mode escapes `:` in names as `\:`
+pub fn parse_nmcli_active(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, device) = line.rsplit_once(':')?;
+            (!device.is_empty()).then(|| (name.replace("\\:", ":"), device.to_string()))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ZoneConfig {
+    #[serde(default)]
+    pub zones: Vec<Zone>,
+}
+
+impl ZoneConfig {
+    /// Reads the zone file; a missing file means no zones are configured
+    pub fn load(path: &Path) -> EngineResult<Self> {
+        let raw = match std::fs::read_to_string(path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(EngineError(format!("Failed to read {}: {}", path.display(), e))),
+        };
+        let config: Self =
+            serde_json::from_str(&raw).map_err(|e| EngineError(format!("Invalid zone file {}: {}", path.display(), e)))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn save(&self, path: &Path) -> EngineResult<()> {
+        self.validate()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| EngineError(format!("Failed to create {}: {}", dir.display(), e)))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| EngineError(e.to_string()))?;
+        std::fs::write(path, json).map_err(|e| EngineError(format!("Failed to write {}: {}", path.display(), e)))
+    }
+
+    /// Each zone is a concrete profile, listed once, and an interface belongs to one zone only
+    pub fn validate(&self) -> EngineResult<()> {
+        let mut seen_profiles = Vec::new();
+        let mut seen_ifaces: Vec<&str> = Vec::new();
+        for zone in &self.zones {
+            if zone.profile == ProfileType::All {
+                return Err(EngineError("A zone must name a single profile, not All".to_string()));
+            }
+            if seen_profiles.contains(&zone.profile) {
+                return Err(EngineError(format!("Profile {:?} is configured by more than one zone", zone.profile)));
+            }
+            seen_profiles.push(zone.profile);
+            for iface in &zone.interfaces {
+                if iface.is_empty() || iface.len() > 15 {
+                    return Err(EngineError(format!("Invalid interface name '{}'", iface)));
+                }
+                if seen_ifaces.contains(&iface.as_str()) {
+                    return Err(EngineError(format!("Interface '{}' is bound to more than one zone", iface)));
+                }
+                seen_ifaces.push(iface);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn zone_mut(&mut self, profile: ProfileType) -> &mut Zone {
+        if let Some(idx) = self.zones.iter().position(|z| z.profile == profile) {
+            return &mut self.zones[idx];
+        }
+        self.zones.push(Zone::new(profile));
+        self.zones.last_mut().expect("zone just pushed")
+    }
+
+    /// Resolves each zone to concrete interfaces; an interface claimed by two zones stays in the first
+    pub fn resolve(&self, links: &LinkState) -> Vec<(&Zone, Vec<String>)> {
+        let mut claimed: Vec<String> = Vec::new();
+        self.zones
+            .iter()
+            .map(|zone| {
+                let members: Vec<String> = zone.resolve(links).into_iter().filter(|i| !claimed.contains(i)).collect();
+                claimed.extend(members.iter().cloned());
+                (zone, members)
+            })
+            .collect()
+    }
+
+    /// Reports every concrete profile; unconfigured ones behave as an enabled allow-all zone
+    pub fn profile_status(&self, table_active: bool, links: &LinkState) -> Vec<ProfileStatus> {
+        let resolved = self.resolve(links);
+        ProfileType::CONCRETE
+            .iter()
+            .map(|&profile| {
+                let (zone, interfaces) = resolved
+                    .iter()
+                    .find(|(z, _)| z.profile == profile)
+                    .map(|(z, i)| ((*z).clone(), i.clone()))
+                    .unwrap_or_else(|| (Zone::new(profile), Vec::new()));
+                ProfileStatus {
+                    profile,
+                    enabled: table_active && zone.enabled,
+                    default_inbound: zone.default_inbound,
+                    default_outbound: zone.default_outbound,
+                    log_allowed: zone.log_allowed,
+                    log_dropped: zone.log_dropped,
+                    log_target: (zone.log_allowed || zone.log_dropped).then(|| format!("nflog group {}", DEFAULT_NFLOG_GROUP)),
+                    interfaces,
+                }
+            })
+            .collect()
+    }
+
+    /// Renders an `nft -f` script that refills the zone sets and chains of the cyberwall table
+    pub fn render(&self, links: &LinkState) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "table {} {} {{", FAMILY, TABLE);
+        for (_, set) in ZONE_SETS {
+            let _ = writeln!(out, "    set {} {{", set);
+            let _ = writeln!(out, "        type ifname;");
+            let _ = writeln!(out, "    }}");
+        }
+        for chain in ZONE_CHAINS {
+            let _ = writeln!(out, "    chain {} {{", chain);
+            let _ = writeln!(out, "    }}");
+        }
+        let _ = writeln!(out, "}}");
+        for (_, set) in ZONE_SETS {
+            let _ = writeln!(out, "flush set {} {} {}", FAMILY, TABLE, set);
+        }
+        for chain in ZONE_CHAINS {
+            let _ = writeln!(out, "flush chain {} {} {}", FAMILY, TABLE, chain);
+        }
+        for (zone, members) in self.resolve(links).into_iter().filter(|(_, m)| !m.is_empty()) {
+            let Some(set) = zone_set_for(zone.profile) else {
+                continue;
+            };
+            let elements: Vec<String> = members.iter().map(|i| quote_string(i)).collect();
+            let _ = writeln!(out, "add element {} {} {} {{ {} }}", FAMILY, TABLE, set, elements.join(", "));
+
+            let label = zone.label();
+            for direction in [RuleDirection::Inbound, RuleDirection::Outbound] {
+                let (bypass, defaults) = zone_chains_for(direction);
+                let matcher = format!("{} @{}", iface_field(direction), set);
+                if !zone.enabled {
+                    let comment = quote_string(&format!("{} off", label));
+                    let _ = writeln!(out, "add rule {} {} {} {} accept comment {}", FAMILY, TABLE, bypass, matcher, comment);
+                    continue;
+                }
+                let (log, verdict) = match zone.default_for(direction) {
+                    RuleAction::Block => (zone.log_dropped, "drop"),
+                    RuleAction::Allow => (zone.log_allowed, "accept"),
+                };
+                if verdict == "accept" && !log {
+                    continue;
+                }
+                let log_stmt = if log {
+                    format!(" log prefix {} group {}", quote_string(&label), DEFAULT_NFLOG_GROUP)
+                } else {
+                    String::new()
+                };
+                let _ = writeln!(
+                    out,
+                    "add rule {} {} {} {} counter{} {} comment {}",
+                    FAMILY,
+                    TABLE,
+                    defaults,
+                    matcher,
+                    log_stmt,
+                    verdict,
+                    quote_string(&format!("{} default", label))
+                );
+            }
+        }
+        out
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub use monitor::LinkMonitor;
+
+#[cfg(target_os = "linux")]
+mod monitor {
+    use cyberwall_core::{EngineError, EngineResult};
+    use std::io;
+
+    const RTMGRP_LINK: u32 = 0x1;
+    const RTMGRP_IPV4_IFADDR: u32 = 0x10;
+    const RTMGRP_IPV6_IFADDR: u32 = 0x100;
+    /// Quiet period that ends a burst of link events, so one change triggers one re-evaluation
+    const SETTLE_MS: libc::c_int = 500;
+
+    /// rtnetlink subscriber that wakes up when interfaces appear, vanish or change addresses
+    pub struct LinkMonitor {
+        fd: libc::c_int,
+    }
+
+    impl LinkMonitor {
+        pub fn open() -> EngineResult<Self> {
+            // SAFETY: plain socket(2) call, the fd is owned by the returned monitor
+            let fd = unsafe { libc::socket(libc::AF_NETLINK, libc::SOCK_RAW | libc::SOCK_CLOEXEC, libc::NETLINK_ROUTE) };
+            if fd < 0 {
+                return Err(EngineError(format!("Failed to open rtnetlink socket: {}", io::Error::last_os_error())));
+            }
+            let monitor = Self { fd };
+
+            // SAFETY: sockaddr_nl is plain old data, zeroed is a valid initial value
+            let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+            addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+            addr.nl_groups = RTMGRP_LINK | RTMGRP_IPV4_IFADDR | RTMGRP_IPV6_IFADDR;
+            // SAFETY: addr is a valid sockaddr_nl for the duration of the call
+            let rc = unsafe {
+                libc::bind(
+                    fd,
+                    &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                    std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+                )
+            };
+            if rc < 0 {
+                return Err(EngineError(format!("Failed to bind rtnetlink socket: {}", io::Error::last_os_error())));
+            }
+            Ok(monitor)
+        }
+
+        fn poll(&self, timeout_ms: libc::c_int) -> EngineResult<bool> {
+            let mut pfd = libc::pollfd { fd: self.fd, events: libc::POLLIN, revents: 0 };
+            // SAFETY: pfd is a valid pollfd array of length 1
+            let rc = unsafe { libc::poll(&mut pfd, 1, timeout_ms) };
+            if rc < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    return Ok(false);
+                }
+                return Err(EngineError(format!("Failed to poll rtnetlink socket: {}", err)));
+            }
+            Ok(rc > 0)
+        }
+
+        fn drain(&self) {
+            let mut buf = [0u8; 8192];
+            // SAFETY: buf is a valid writable buffer of buf.len() bytes
+            while unsafe { libc::recv(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), libc::MSG_DONTWAIT) } > 0 {}
+        }
+
+        /// Blocks until a link or address change has happened and the burst has settled
+        pub fn wait(&self) -> EngineResult<()> {
+            while !self.poll(-1)? {}
+            loop {
+                self.drain();
+                if !self.poll(SETTLE_MS)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    impl Drop for LinkMonitor {
+        fn drop(&mut self) {
+            // SAFETY: fd was opened by LinkMonitor::open and is closed exactly once
+            unsafe { libc::close(self.fd) };
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::translate::{NetFwRuleProps, RULE_GROUP};
+use windows::core::{IUnknown, Interface, BSTR, VARIANT};
+use windows::Win32::Foundation::{VARIANT_FALSE, VARIANT_TRUE};
+use windows::Win32::NetworkManagement::WindowsFirewall::{
+    INetFwPolicy2, INetFwRule, INetFwRules, NetFwPolicy2, NetFwRule, NET_FW_ACTION, NET_FW_RULE_DIRECTION,
+};
+use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED};
+use windows::Win32::System::Ole::IEnumVARIANT;
+
+fn com_err(context: &str) -> impl Fn(windows::core::Error) -> String + '_ {
+    move |e| format!("{}: {}", context, e.message())
+}
+
+fn rules_collection() -> Result<INetFwRules, String> {
+    unsafe {
+        // S_FALSE (already initialised on this thread) is fine; only hard failures matter
+        CoInitializeEx(None, COINIT_APARTMENTTHREADED).ok().map_err(com_err("CoInitializeEx failed"))?;
+        let policy: INetFwPolicy2 =
+            CoCreateInstance(&NetFwPolicy2, None, CLSCTX_INPROC_SERVER).map_err(com_err("Failed to open INetFwPolicy2"))?;
+        policy.Rules().map_err(com_err("Failed to read firewall rules"))
+    }
+}
+
+fn read_props(rule: &INetFwRule) -> windows::core::Result<NetFwRuleProps> {
+    unsafe {
+        let application = rule.ApplicationName()?.to_string();
+        Ok(NetFwRuleProps {
+            name: rule.Name()?.to_string(),
+            description: rule.Description()?.to_string(),
+            grouping: rule.Grouping()?.to_string(),
+            enabled: rule.Enabled()? == VARIANT_TRUE,
+            direction: rule.Direction()?.0,
+            action: rule.Action()?.0,
+            profiles: rule.Profiles()?,
+            protocol: rule.Protocol()?,
+            local_addresses: rule.LocalAddresses()?.to_string(),
+            remote_addresses: rule.RemoteAddresses()?.to_string(),
+            local_ports: rule.LocalPorts()?.to_string(),
+            remote_ports: rule.RemotePorts()?.to_string(),
+            application_name: (!application.is_empty()).then_some(application),
+        })
+    }
+}
+
+fn cyberwall_rules(rules: &INetFwRules) -> Result<Vec<NetFwRuleProps>, String> {
+    let mut out = Vec::new();
+    unsafe {
+        let items: IEnumVARIANT = rules
+            ._NewEnum()
+            .and_then(|e| e.cast())
+            .map_err(com_err("Failed to enumerate firewall rules"))?;
+        loop {
+            let mut slot = [VARIANT::default()];
+            let mut fetched = 0u32;
+            if items.Next(&mut slot, &mut fetched).is_err() || fetched == 0 {
+                break;
+            }
+            let rule: INetFwRule = match IUnknown::try_from(&slot[0]).and_then(|u| u.cast()) {
+                Ok(rule) => rule,
+                Err(_) => continue,
+            };
+            if rule.Grouping().map(|g| g.to_string() == RULE_GROUP).unwrap_or(false) {
+                out.push(read_props(&rule).map_err(com_err("Failed to read firewall rule"))?);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Reads every rule in the cyberwall group
+pub fn list_rules() -> Result<Vec<NetFwRuleProps>, String> {
+    cyberwall_rules(&rules_collection()?)
+}
+
+fn add_rule(rules: &INetFwRules, p: &NetFwRuleProps) -> Result<(), String> {
+    unsafe {
+        let rule: INetFwRule =
+            CoCreateInstance(&NetFwRule, None, CLSCTX_INPROC_SERVER).map_err(com_err("Failed to create INetFwRule"))?;
+        let set = || -> windows::core::Result<()> {
+            rule.SetName(&BSTR::from(p.name.as_str()))?;
+            rule.SetDescription(&BSTR::from(p.description.as_str()))?;
+            rule.SetGrouping(&BSTR::from(p.grouping.as_str()))?;
+            rule.SetDirection(NET_FW_RULE_DIRECTION(p.direction))?;
+            rule.SetAction(NET_FW_ACTION(p.action))?;
+            rule.SetProfiles(p.profiles)?;
+            // Ports can only be set once the protocol is TCP or UDP
+            rule.SetProtocol(p.protocol)?;
+            if !p.local_ports.is_empty() {
+                rule.SetLocalPorts(&BSTR::from(p.local_ports.as_str()))?;
+            }
+            if !p.remote_ports.is_empty() {
+                rule.SetRemotePorts(&BSTR::from(p.remote_ports.as_str()))?;
+            }
+            rule.SetLocalAddresses(&BSTR::from(p.local_addresses.as_str()))?;
+            rule.SetRemoteAddresses(&BSTR::from(p.remote_addresses.as_str()))?;
+            if let Some(app) = &p.application_name {
+                rule.SetApplicationName(&BSTR::from(app.as_str()))?;
+            }
+            rule.SetEnabled(if p.enabled { VARIANT_TRUE } else { VARIANT_FALSE })?;
+            rules.Add(&rule)
+        };
+        set().map_err(|e| format!("Failed to add firewall rule '{}': {}", p.name, e.message()))
+    }
+}
+
+fn replace_group(rules: &INetFwRules, props: &[NetFwRuleProps]) -> Result<(), String> {
+    for existing in cyberwall_rules(rules)? {
+        unsafe { rules.Remove(&BSTR::from(existing.name.as_str())) }.map_err(com_err("Failed to remove firewall rule"))?;
+    }
+    props.iter().try_for_each(|p| add_rule(rules, p))
+}
+
+/// Replaces the cyberwall rule group with the given rules, putting the previous group back if any step fails
+pub fn apply(props: &[NetFwRuleProps]) -> Result<(), String> {
+    let rules = rules_collection()?;
+    let snapshot = cyberwall_rules(&rules)?;
+    if let Err(e) = replace_group(&rules, props) {
+        return Err(match replace_group(&rules, &snapshot) {
+            Ok(()) => format!("{}; the previous rules were restored", e),
+            Err(restore) => format!("{}; restoring the previous rules also failed: {}", e, restore),
+        });
+    }
+    Ok(())
+}
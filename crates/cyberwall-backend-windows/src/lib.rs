@@ -1,8 +1,15 @@
+#[cfg(windows)]
+mod com;
+pub mod translate;
+
 use async_trait::async_trait;
 use cyberwall_core::{
-    EngineError, EngineResult, FirewallEngine, FirewallPolicy, FirewallRule, FirewallStatus, ProfileType, RuleAction, RuleDirection,
+    EmergencyMode, EngineError, EngineResult, FirewallEngine, FirewallPolicy, FirewallRule, FirewallStatus, NatRule, ProfileType, RuleStats,
 };
 
+#[cfg(windows)]
+use com::{apply as com_apply, list_rules as com_list_rules};
+
 pub struct WindowsFirewallEngine;
 
 impl WindowsFirewallEngine {
@@ -32,15 +39,16 @@ impl FirewallEngine for WindowsFirewallEngine {
         .await
         .map_err(|e| EngineError(e.to_string()))?;
 
+        let profiles = translate::profiles_from_netsh(&run_netsh(&["advfirewall", "show", "allprofiles"]).await?)?;
+
         Ok(FirewallStatus {
             enabled: fw_enabled,
             outbound_blocked,
             defender_active,
-            profile_private: fw_enabled,
-            profile_public: fw_enabled,
-            profile_domain: fw_enabled,
+            profiles,
             platform: "Windows".to_string(),
             backend_driver: "Win32 COM INetFwPolicy2 + Netsh Advfirewall Service".to_string(),
+            emergency: None,
         })
     }
 
@@ -59,6 +67,12 @@ impl FirewallEngine for WindowsFirewallEngine {
         Ok(())
     }
 
+    async fn set_profile_enabled(&self, profile: ProfileType, enabled: bool) -> EngineResult<()> {
+        let args = translate::netsh_profile_state_args(profile, enabled);
+        run_netsh(&args.iter().map(String::as_str).collect::<Vec<_>>()).await?;
+        Ok(())
+    }
+
     async fn set_outbound_block(&self, blocked: bool) -> EngineResult<()> {
         tokio::task::spawn_blocking(move || {
             if blocked {
@@ -74,20 +88,68 @@ impl FirewallEngine for WindowsFirewallEngine {
         Ok(())
     }
 
+    async fn set_emergency(&self, _mode: Option<&EmergencyMode>) -> EngineResult<()> {
+        Err(EngineError("Emergency modes are only available on Linux".to_string()))
+    }
+
     async fn list_rules(&self) -> EngineResult<Vec<FirewallRule>> {
-        Ok(vec![
-            FirewallRule {
-                name: "Split2ops Cyberwall Core Ruleset".to_string(),
-                enabled: true,
-                action: RuleAction::Allow,
-                direction: RuleDirection::Inbound,
-                profile: ProfileType::All,
-                application: Some("cyberwalld.exe".to_string()),
-            }
-        ])
+        let props = run_com(com_list_rules).await?;
+        translate::rules_from_props(&props)
     }
 
-    async fn apply_policy(&self, _policy: &FirewallPolicy) -> EngineResult<()> {
-        Ok(())
+    async fn rule_stats(&self) -> EngineResult<Vec<RuleStats>> {
+        Err(EngineError("Windows Firewall does not expose per-rule hit counters".to_string()))
+    }
+
+    async fn list_nat_rules(&self) -> EngineResult<Vec<NatRule>> {
+        Ok(Vec::new())
+    }
+
+    async fn add_nat_rule(&self, _rule: &NatRule) -> EngineResult<()> {
+        Err(EngineError("NAT and port forwarding are not managed by Windows Firewall".to_string()))
+    }
+
+    async fn remove_nat_rule(&self, _name: &str) -> EngineResult<()> {
+        Err(EngineError("NAT and port forwarding are not managed by Windows Firewall".to_string()))
+    }
+
+    async fn apply_policy(&self, policy: &FirewallPolicy) -> EngineResult<()> {
+        let props = translate::policy_props(policy)?;
+        run_com(move || com_apply(&props)).await
+    }
+}
+
+async fn run_netsh(args: &[&str]) -> EngineResult<String> {
+    let output = tokio::process::Command::new("netsh")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| EngineError(format!("Failed to execute netsh command: {}", e)))?;
+    // netsh writes its error text to stdout, not stderr
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        return Err(EngineError(format!("netsh failed: {}", stdout.trim())));
     }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+async fn run_com<T, F>(f: F) -> EngineResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| EngineError(e.to_string()))?
+        .map_err(EngineError)
+}
+
+#[cfg(not(windows))]
+fn com_list_rules() -> Result<Vec<translate::NetFwRuleProps>, String> {
+    Err("Windows Firewall COM API is only available on Windows".to_string())
+}
+
+#[cfg(not(windows))]
+fn com_apply(_props: &[translate::NetFwRuleProps]) -> Result<(), String> {
+    Err("Windows Firewall COM API is only available on Windows".to_string())
 }
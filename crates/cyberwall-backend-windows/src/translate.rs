@@ -0,0 +1,530 @@
+use cyberwall_core::export::{netsh_add_command, windows_rule_specs, WindowsRuleSpec};
+use cyberwall_core::import::{netsh_blocks, netsh_rule};
+use cyberwall_core::{
+    EngineError, EngineResult, FirewallPolicy, FirewallRule, ProfileStatus, ProfileType, Protocol, RuleAction,
+};
+use std::collections::HashMap;
+
+/// Rule group that marks Windows Firewall rules as owned by cyberwall
+pub const RULE_GROUP: &str = "cyberwall";
+
+pub const NET_FW_IP_PROTOCOL_ICMPV4: i32 = 1;
+pub const NET_FW_IP_PROTOCOL_TCP: i32 = 6;
+pub const NET_FW_IP_PROTOCOL_UDP: i32 = 17;
+pub const NET_FW_IP_PROTOCOL_ICMPV6: i32 = 58;
+pub const NET_FW_IP_PROTOCOL_ANY: i32 = 256;
+
+pub const NET_FW_PROFILE2_DOMAIN: i32 = 0x1;
+pub const NET_FW_PROFILE2_PRIVATE: i32 = 0x2;
+pub const NET_FW_PROFILE2_PUBLIC: i32 = 0x4;
+pub const NET_FW_PROFILE2_ALL: i32 = 0x7FFF_FFFF;
+
+pub const NET_FW_RULE_DIR_IN: i32 = 1;
+pub const NET_FW_RULE_DIR_OUT: i32 = 2;
+
+pub const NET_FW_ACTION_BLOCK: i32 = 0;
+pub const NET_FW_ACTION_ALLOW: i32 = 1;
+
+/// Property set of one `INetFwRule`, in the representation the COM API expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetFwRuleProps {
+    pub name: String,
+    pub description: String,
+    pub grouping: String,
+    pub enabled: bool,
+    pub direction: i32,
+    pub action: i32,
+    pub profiles: i32,
+    pub protocol: i32,
+    /// `*` or a comma separated address list
+    pub local_addresses: String,
+    pub remote_addresses: String,
+    /// Empty when the protocol has no ports, `*` for any port
+    pub local_ports: String,
+    pub remote_ports: String,
+    pub application_name: Option<String>,
+}
+
+fn protocol_number(keyword: &str) -> i32 {
+    match keyword {
+        "TCP" => NET_FW_IP_PROTOCOL_TCP,
+        "UDP" => NET_FW_IP_PROTOCOL_UDP,
+        "ICMPv4" => NET_FW_IP_PROTOCOL_ICMPV4,
+        "ICMPv6" => NET_FW_IP_PROTOCOL_ICMPV6,
+        _ => NET_FW_IP_PROTOCOL_ANY,
+    }
+}
+
+fn profile_mask(keyword: &str) -> i32 {
+    match keyword {
+        "domain" => NET_FW_PROFILE2_DOMAIN,
+        "private" => NET_FW_PROFILE2_PRIVATE,
+        "public" => NET_FW_PROFILE2_PUBLIC,
+        _ => NET_FW_PROFILE2_ALL,
+    }
+}
+
+fn star_list(values: &[String]) -> String {
+    if values.is_empty() {
+        "*".to_string()
+    } else {
+        values.join(",")
+    }
+}
+
+impl From<&WindowsRuleSpec> for NetFwRuleProps {
+    fn from(spec: &WindowsRuleSpec) -> Self {
+        let protocol = protocol_number(spec.protocol);
+        let has_ports = protocol == NET_FW_IP_PROTOCOL_TCP || protocol == NET_FW_IP_PROTOCOL_UDP;
+        Self {
+            name: spec.name.clone(),
+            description: "Managed by cyberwall".to_string(),
+            grouping: RULE_GROUP.to_string(),
+            enabled: spec.enabled,
+            direction: if spec.inbound { NET_FW_RULE_DIR_IN } else { NET_FW_RULE_DIR_OUT },
+            action: if spec.allow { NET_FW_ACTION_ALLOW } else { NET_FW_ACTION_BLOCK },
+            profiles: profile_mask(spec.profile),
+            protocol,
+            local_addresses: star_list(&spec.local_addresses),
+            remote_addresses: star_list(&spec.remote_addresses),
+            local_ports: if has_ports { star_list(&spec.local_ports) } else { String::new() },
+            remote_ports: if has_ports { star_list(&spec.remote_ports) } else { String::new() },
+            application_name: spec.program.clone(),
+        }
+    }
+}
+
+impl NetFwRuleProps {
+    /// Field map in `netsh ... show rule` form, so COM reads share the netsh dump parser
+    pub fn to_netsh_fields(&self) -> HashMap<String, String> {
+        let profiles = if self.profiles & NET_FW_PROFILE2_ALL == NET_FW_PROFILE2_ALL {
+            "Any".to_string()
+        } else {
+            [
+                (NET_FW_PROFILE2_DOMAIN, "Domain"),
+                (NET_FW_PROFILE2_PRIVATE, "Private"),
+                (NET_FW_PROFILE2_PUBLIC, "Public"),
+            ]
+            .iter()
+            .filter(|(bit, _)| self.profiles & bit != 0)
+            .map(|(_, name)| *name)
+            .collect::<Vec<_>>()
+            .join(",")
+        };
+        let protocol = match self.protocol {
+            NET_FW_IP_PROTOCOL_TCP => "TCP".to_string(),
+            NET_FW_IP_PROTOCOL_UDP => "UDP".to_string(),
+            NET_FW_IP_PROTOCOL_ICMPV4 => "ICMPv4".to_string(),
+            NET_FW_IP_PROTOCOL_ICMPV6 => "ICMPv6".to_string(),
+            NET_FW_IP_PROTOCOL_ANY => "Any".to_string(),
+            other => other.to_string(),
+        };
+        let mut fields = HashMap::from([
+            ("rule name".to_string(), self.name.clone()),
+            ("enabled".to_string(), if self.enabled { "Yes" } else { "No" }.to_string()),
+            ("direction".to_string(), if self.direction == NET_FW_RULE_DIR_IN { "In" } else { "Out" }.to_string()),
+            ("profiles".to_string(), profiles),
+            ("grouping".to_string(), self.grouping.clone()),
+            ("localip".to_string(), self.local_addresses.clone()),
+            ("remoteip".to_string(), self.remote_addresses.clone()),
+            ("protocol".to_string(), protocol),
+            ("localport".to_string(), self.local_ports.clone()),
+            ("remoteport".to_string(), self.remote_ports.clone()),
+            ("action".to_string(), if self.action == NET_FW_ACTION_ALLOW { "Allow" } else { "Block" }.to_string()),
+        ]);
+        if let Some(program) = &self.application_name {
+            fields.insert("program".to_string(), program.clone());
+        }
+        fields
+    }
+}
+
+/// Translates a policy into the Windows Firewall rules that implement it
+pub fn policy_props(policy: &FirewallPolicy) -> EngineResult<Vec<NetFwRuleProps>> {
+    if let Some(nat) = policy.nat_rules.first() {
+        return Err(EngineError(format!(
+            "NAT rule '{}' cannot be applied: NAT and port forwarding are not managed by Windows Firewall",
+            nat.name
+        )));
+    }
+    let mut props = Vec::new();
+    for rule in &policy.rules {
+        rule.validate().map_err(EngineError)?;
+        props.extend(windows_rule_specs(rule).iter().map(NetFwRuleProps::from));
+    }
+    Ok(props)
+}
+
+/// Translates a policy into `netsh advfirewall` commands, for hosts where COM is unavailable
+pub fn policy_netsh_commands(policy: &FirewallPolicy, installed: &[String]) -> EngineResult<Vec<String>> {
+    policy_props(policy)?;
+    let mut commands: Vec<String> = installed
+        .iter()
+        .map(|name| format!("netsh advfirewall firewall delete rule name=\"{}\"", name.replace('"', "'")))
+        .collect();
+    for rule in &policy.rules {
+        commands.extend(windows_rule_specs(rule).iter().map(netsh_add_command));
+    }
+    Ok(commands)
+}
+
+/// Converts rules read back from Windows Firewall, rejoining TCP/UDP pairs that one cyberwall rule was split into
+pub fn rules_from_props(props: &[NetFwRuleProps]) -> EngineResult<Vec<FirewallRule>> {
+    let mut rules = Vec::new();
+    for p in props.iter().filter(|p| p.grouping == RULE_GROUP) {
+        rules.extend(netsh_rule(&p.to_netsh_fields()).map_err(|e| EngineError(format!("Rule '{}': {}", p.name, e)))?);
+    }
+    Ok(merge_protocol_split(rules))
+}
+
+/// Parses `netsh advfirewall firewall show rule name=all verbose` output, keeping only cyberwall rules
+pub fn rules_from_netsh_dump(dump: &str) -> EngineResult<Vec<FirewallRule>> {
+    let mut rules = Vec::new();
+    for block in netsh_blocks(dump) {
+        if block.get("grouping").map(String::as_str) != Some(RULE_GROUP) {
+            continue;
+        }
+        let name = block.get("rule name").cloned().unwrap_or_default();
+        rules.extend(netsh_rule(&block).map_err(|e| EngineError(format!("Rule '{}': {}", name, e)))?);
+    }
+    Ok(merge_protocol_split(rules))
+}
+
+fn merge_protocol_split(rules: Vec<FirewallRule>) -> Vec<FirewallRule> {
+    let mut merged: Vec<FirewallRule> = Vec::with_capacity(rules.len());
+    for mut rule in rules {
+        // `0.0.0.0/0` or `::/0` is how a family restriction without addresses is written
+        if rule.local_addresses.is_empty() && matches!(rule.remote_addresses.as_slice(), [a] if a == "0.0.0.0/0" || a == "::/0") {
+            rule.remote_addresses.clear();
+        }
+        if let Some(prev) = merged.last_mut() {
+            let pair = matches!(
+                (prev.protocol, rule.protocol),
+                (Some(Protocol::Tcp), Some(Protocol::Udp)) | (Some(Protocol::Udp), Some(Protocol::Tcp))
+            );
+            if pair && (FirewallRule { protocol: prev.protocol, ..rule.clone() }) == *prev {
+                prev.protocol = None;
+                continue;
+            }
+        }
+        merged.push(rule);
+    }
+    merged
+}
+
+fn profile_keyword(profile: ProfileType) -> &'static str {
+    match profile {
+        ProfileType::Domain => "domainprofile",
+        ProfileType::Private => "privateprofile",
+        ProfileType::Public => "publicprofile",
+        ProfileType::All => "allprofiles",
+    }
+}
+
+/// Arguments for `netsh` that switch one profile (or all of them) on or off
+pub fn netsh_profile_state_args(profile: ProfileType, enabled: bool) -> Vec<String> {
+    ["advfirewall", "set", profile_keyword(profile), "state", if enabled { "on" } else { "off" }]
+        .iter()
+        .map(|a| a.to_string())
+        .collect()
+}
+
+/// Parses `netsh advfirewall show allprofiles` output into per-profile settings
+pub fn profiles_from_netsh(output: &str) -> EngineResult<Vec<ProfileStatus>> {
+    let mut profiles: Vec<ProfileStatus> = Vec::new();
+    for line in output.lines().map(str::trim) {
+        if let Some(name) = line.strip_suffix(" Profile Settings:") {
+            let profile = name.parse().map_err(EngineError)?;
+            profiles.push(ProfileStatus {
+                profile,
+                enabled: false,
+                default_inbound: RuleAction::Allow,
+                default_outbound: RuleAction::Allow,
+                log_allowed: false,
+                log_dropped: false,
+                log_target: None,
+                interfaces: Vec::new(),
+            });
+            continue;
+        }
+        let (Some(current), Some((key, value))) = (profiles.last_mut(), line.split_once("  ")) else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "State" => current.enabled = value.eq_ignore_ascii_case("on"),
+            "Firewall Policy" => {
+                // e.g. BlockInbound,AllowOutbound
+                for part in value.split(',').map(str::trim) {
+                    let action = if part.starts_with("Block") { RuleAction::Block } else { RuleAction::Allow };
+                    if part.ends_with("Inbound") || part.ends_with("InboundAlways") {
+                        current.default_inbound = action;
+                    } else if part.ends_with("Outbound") {
+                        current.default_outbound = action;
+                    }
+                }
+            }
+            "LogAllowedConnections" => current.log_allowed = value.eq_ignore_ascii_case("enable"),
+            "LogDroppedConnections" => current.log_dropped = value.eq_ignore_ascii_case("enable"),
+            "FileName" if !value.eq_ignore_ascii_case("notconfigured") => current.log_target = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    if profiles.is_empty() {
+        return Err(EngineError("netsh output contained no profile settings".to_string()));
+    }
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cyberwall_core::{AddressFamily, NatKind, NatRule, PortRange, RuleDirection};
+
+    const SHOW_ALLPROFILES: &str = "
+Domain Profile Settings:
+----------------------------------------------------------------------
+State                                 ON
+Firewall Policy                       BlockInbound,AllowOutbound
+LocalFirewallRules                    N/A (GPO-store only)
+LocalConSecRules                      N/A (GPO-store only)
+InboundUserNotification               Disable
+RemoteManagement                      Disable
+UnicastResponseToMulticast            Enable
+
+Logging:
+LogAllowedConnections                 Disable
+LogDroppedConnections                 Enable
+FileName                              %systemroot%\\system32\\LogFiles\\Firewall\\pfirewall.log
+MaxFileSize                           4096
+
+Private Profile Settings:
+----------------------------------------------------------------------
+State                                 ON
+Firewall Policy                       BlockInboundAlways,BlockOutbound
+LocalFirewallRules                    N/A (GPO-store only)
+
+Logging:
+LogAllowedConnections                 Enable
+LogDroppedConnections                 Disable
+FileName                              NotConfigured
+MaxFileSize                           4096
+
+Public Profile Settings:
+----------------------------------------------------------------------
+State                                 OFF
+Firewall Policy                       AllowInbound,AllowOutbound
+
+Logging:
+LogAllowedConnections                 Disable
+LogDroppedConnections                 Disable
+FileName                              NotConfigured
+MaxFileSize                           4096
+Ok.
+";
+
+    const SHOW_RULES: &str = "
+Rule Name:                            dns
+----------------------------------------------------------------------
+Description:                          Managed by cyberwall
+Enabled:                              Yes
+Direction:                            Out
+Profiles:                             Domain,Private,Public
+Grouping:                             cyberwall
+LocalIP:                              Any
+RemoteIP:                             192.0.2.53/255.255.255.255,2001:db8::53
+Protocol:                             TCP
+LocalPort:                            Any
+RemotePort:                           53
+Edge traversal:                       No
+Action:                               Allow
+
+Rule Name:                            dns
+----------------------------------------------------------------------
+Description:                          Managed by cyberwall
+Enabled:                              Yes
+Direction:                            Out
+Profiles:                             Domain,Private,Public
+Grouping:                             cyberwall
+LocalIP:                              Any
+RemoteIP:                             192.0.2.53/255.255.255.255,2001:db8::53
+Protocol:                             UDP
+LocalPort:                            Any
+RemotePort:                           53
+Edge traversal:                       No
+Action:                               Allow
+
+Rule Name:                            Core Networking - DHCP (DHCP-In)
+----------------------------------------------------------------------
+Enabled:                              Yes
+Direction:                            In
+Profiles:                             Domain,Private,Public
+Grouping:                             Core Networking
+LocalIP:                              Any
+RemoteIP:                             Any
+Protocol:                             UDP
+LocalPort:                            68
+RemotePort:                           67
+Action:                               Allow
+
+Rule Name:                            ping v4
+----------------------------------------------------------------------
+Enabled:                              No
+Direction:                            In
+Profiles:                             Public
+Grouping:                             cyberwall
+LocalIP:                              Any
+RemoteIP:                             0.0.0.0/0
+Protocol:                             ICMPv4
+Action:                               Block
+Ok.
+";
+
+    fn port(p: u16) -> PortRange {
+        PortRange { start: p, end: p }
+    }
+
+    fn sample_policy() -> FirewallPolicy {
+        FirewallPolicy {
+            name: "test".to_string(),
+            version: "1".to_string(),
+            allow_neighbor_discovery: true,
+            nat_rules: Vec::new(),
+            rules: vec![
+                FirewallRule {
+                    protocol: Some(Protocol::Tcp),
+                    local_ports: vec![port(22), PortRange { start: 8000, end: 8100 }],
+                    remote_addresses: vec!["10.0.0.0/8".to_string(), "fd00::/8".to_string()],
+                    ..FirewallRule::new("ssh", RuleAction::Allow, RuleDirection::Inbound)
+                },
+                FirewallRule {
+                    // Read-back infers the family from the addresses
+                    family: AddressFamily::Ipv4,
+                    remote_ports: vec![port(53)],
+                    remote_addresses: vec!["192.0.2.53".to_string()],
+                    ..FirewallRule::new("dns", RuleAction::Allow, RuleDirection::Outbound)
+                },
+                FirewallRule {
+                    family: AddressFamily::Ipv4,
+                    protocol: Some(Protocol::Icmp),
+                    profile: ProfileType::Public,
+                    ..FirewallRule::new("ping v4", RuleAction::Block, RuleDirection::Inbound)
+                },
+                FirewallRule {
+                    family: AddressFamily::Ipv6,
+                    protocol: Some(Protocol::IcmpV6),
+                    ..FirewallRule::new("ping v6", RuleAction::Allow, RuleDirection::Inbound)
+                },
+                FirewallRule {
+                    enabled: false,
+                    protocol: Some(Protocol::Udp),
+                    remote_ports: vec![port(123)],
+                    application: Some("C:\\Windows\\System32\\w32tm.exe".to_string()),
+                    ..FirewallRule::new("ntp", RuleAction::Allow, RuleDirection::Outbound)
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn policy_props_translates_each_rule() {
+        let props = policy_props(&sample_policy()).unwrap();
+        assert_eq!(props.len(), 6, "the port rule without a protocol becomes a TCP and a UDP rule");
+
+        let ssh = &props[0];
+        assert_eq!((ssh.direction, ssh.action, ssh.protocol), (NET_FW_RULE_DIR_IN, NET_FW_ACTION_ALLOW, NET_FW_IP_PROTOCOL_TCP));
+        assert_eq!(ssh.profiles, NET_FW_PROFILE2_ALL);
+        assert_eq!((ssh.local_ports.as_str(), ssh.remote_ports.as_str()), ("22,8000-8100", "*"));
+        assert_eq!((ssh.local_addresses.as_str(), ssh.remote_addresses.as_str()), ("*", "10.0.0.0/8,fd00::/8"));
+        assert_eq!(ssh.grouping, RULE_GROUP);
+
+        assert_eq!([props[1].protocol, props[2].protocol], [NET_FW_IP_PROTOCOL_TCP, NET_FW_IP_PROTOCOL_UDP]);
+        assert!(props[1..3].iter().all(|p| p.name == "dns" && p.remote_ports == "53" && p.direction == NET_FW_RULE_DIR_OUT));
+
+        let ping = &props[3];
+        assert_eq!((ping.protocol, ping.profiles, ping.action), (NET_FW_IP_PROTOCOL_ICMPV4, NET_FW_PROFILE2_PUBLIC, NET_FW_ACTION_BLOCK));
+        assert_eq!(ping.remote_addresses, "0.0.0.0/0", "an IPv4-only rule without addresses is scoped by address");
+        assert_eq!((ping.local_ports.as_str(), ping.remote_ports.as_str()), ("", ""));
+        assert_eq!(props[4].remote_addresses, "::/0");
+
+        let ntp = &props[5];
+        assert!(!ntp.enabled);
+        assert_eq!(ntp.application_name.as_deref(), Some("C:\\Windows\\System32\\w32tm.exe"));
+    }
+
+    #[test]
+    fn policy_props_rejects_nat() {
+        let mut policy = sample_policy();
+        policy.nat_rules.push(NatRule {
+            name: "fwd".to_string(),
+            enabled: true,
+            kind: NatKind::Masquerade,
+            protocol: None,
+            in_interface: None,
+            out_interface: None,
+            source: None,
+            destination: None,
+            destination_port: None,
+            to_address: None,
+            to_port: None,
+        });
+        assert!(policy_props(&policy).unwrap_err().0.contains("'fwd'"));
+    }
+
+    #[test]
+    fn rules_from_props_reverses_policy_props() {
+        let policy = sample_policy();
+        let mut props = policy_props(&policy).unwrap();
+        props.push(NetFwRuleProps { grouping: "Core Networking".to_string(), ..props[0].clone() });
+        assert_eq!(rules_from_props(&props).unwrap(), policy.rules);
+    }
+
+    #[test]
+    fn merge_protocol_split_only_joins_identical_pairs() {
+        let base = FirewallRule { remote_ports: vec![port(53)], ..FirewallRule::new("dns", RuleAction::Allow, RuleDirection::Outbound) };
+        let tcp = FirewallRule { protocol: Some(Protocol::Tcp), ..base.clone() };
+        let udp = FirewallRule { protocol: Some(Protocol::Udp), ..base.clone() };
+        assert_eq!(merge_protocol_split(vec![tcp.clone(), udp.clone()]), vec![base.clone()]);
+        assert_eq!(merge_protocol_split(vec![udp.clone(), tcp.clone()]), vec![base]);
+
+        let other_port = FirewallRule { remote_ports: vec![port(853)], ..udp.clone() };
+        assert_eq!(merge_protocol_split(vec![tcp.clone(), other_port.clone()]), vec![tcp.clone(), other_port]);
+        assert_eq!(merge_protocol_split(vec![tcp.clone(), tcp.clone()]).len(), 2);
+
+        let scoped = FirewallRule { family: AddressFamily::Ipv6, remote_addresses: vec!["::/0".to_string()], ..tcp.clone() };
+        assert_eq!(merge_protocol_split(vec![scoped])[0].remote_addresses, Vec::<String>::new());
+    }
+
+    #[test]
+    fn rules_from_netsh_dump_keeps_cyberwall_rules() {
+        let rules = rules_from_netsh_dump(SHOW_RULES).unwrap();
+        assert_eq!(rules.len(), 2);
+        let dns = &rules[0];
+        assert_eq!((dns.name.as_str(), dns.protocol, dns.direction), ("dns", None, RuleDirection::Outbound));
+        assert_eq!(dns.remote_addresses, vec!["192.0.2.53/32", "2001:db8::53"]);
+        assert_eq!((dns.remote_ports.clone(), dns.profile), (vec![port(53)], ProfileType::All));
+        let ping = &rules[1];
+        assert_eq!((ping.protocol, ping.family, ping.profile), (Some(Protocol::Icmp), AddressFamily::Ipv4, ProfileType::Public));
+        assert!(!ping.enabled && ping.action == RuleAction::Block && ping.remote_addresses.is_empty());
+    }
+
+    #[test]
+    fn profiles_from_netsh_reads_each_profile() {
+        let profiles = profiles_from_netsh(SHOW_ALLPROFILES).unwrap();
+        let summary: Vec<_> = profiles
+            .iter()
+            .map(|p| (p.profile, p.enabled, p.default_inbound, p.default_outbound, p.log_allowed, p.log_dropped))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (ProfileType::Domain, true, RuleAction::Block, RuleAction::Allow, false, true),
+                (ProfileType::Private, true, RuleAction::Block, RuleAction::Block, true, false),
+                (ProfileType::Public, false, RuleAction::Allow, RuleAction::Allow, false, false),
+            ]
+        );
+        assert_eq!(profiles[0].log_target.as_deref(), Some("%systemroot%\\system32\\LogFiles\\Firewall\\pfirewall.log"));
+        assert_eq!(profiles[1].log_target, None);
+        assert!(profiles_from_netsh("Ok.\n").is_err());
+    }
+}
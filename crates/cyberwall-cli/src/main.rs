@@ -1,7 +1,26 @@
-use clap::{Parser, Subcommand};
+mod output;
+
+use clap::{Parser, Subcommand, ValueEnum};
 use colored::*;
+use cyberwall_backend_linux::boot::{self, SAVED_POLICY_PATH};
+use cyberwall_backend_linux::conntrack::{ConntrackFilter, ConntrackTable};
+use cyberwall_backend_linux::nflog::{DropSink, DEFAULT_NFLOG_GROUP};
+use cyberwall_backend_linux::zones::{LinkState, ZoneConfig, ZONES_PATH};
+#[cfg(target_os = "linux")]
+use cyberwall_backend_linux::LinuxFirewallEngine;
+#[cfg(not(target_os = "linux"))]
 use cyberwall_backend_windows::WindowsFirewallEngine;
-use cyberwall_core::FirewallEngine;
+use cyberwall_core::export::{export_policy, ExportFormat};
+use cyberwall_core::import::{import_policy, ImportFormat};
+use cyberwall_core::ipc::{IpcClient, IpcEndpoint, DEFAULT_SOCKET_PATH, TOKEN_ENV};
+use cyberwall_core::audit::{self, Actor};
+use cyberwall_core::health::unix_now;
+use cyberwall_core::signing::{self, SignedDocument, SigningConfig, TrustStore};
+use cyberwall_core::state::{AuditEntry, StateStore};
+use cyberwall_core::{EmergencyKind, EmergencyMode, EmergencyPeer, FirewallEngine, FirewallPolicy, NatKind, NatRule, ProfileType, Protocol, RuleAction};
+use output::{or_dash, EmergencyStatus, ExportSummary, ImportSummary, Output, OutputFormat, Tone, VerifyReport};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 #[derive(Parser)]
 #[command(name = "cyberwall")]
@@ -9,6 +28,15 @@ use cyberwall_core::FirewallEngine;
 #[command(version = "1.0.0")]
 #[command(about = "Split2ops Cyberwall Enterprise Commercial Firewall CLI", long_about = None)]
 struct Cli {
+    /// Send firewall operations through a running aegisd (socket path or tcp://host:port)
+    #[arg(long, global = true, value_name = "ENDPOINT", num_args = 0..=1, default_missing_value = DEFAULT_SOCKET_PATH)]
+    daemon: Option<IpcEndpoint>,
+    /// Result format: table, json, yaml or plain (tab-separated)
+    #[arg(long, short = 'o', global = true, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+    /// Print only errors and requested documents (including json/yaml results); rely on the exit code
+    #[arg(long, short = 'q', global = true)]
+    quiet: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -17,7 +45,7 @@ struct Cli {
 enum Commands {
     /// Display live OS firewall status, profile breakdown, and backend engine info
     Status {
-        /// Output status as JSON
+        /// Output status as JSON (same as --output json)
         #[arg(long)]
         json: bool,
     },
@@ -25,72 +53,1047 @@ enum Commands {
     Enable,
     /// Disable the OS firewall across all profiles
     Disable,
+    /// Switch the firewall on or off for one profile (private, public, domain or all)
+    Profile {
+        name: ProfileType,
+        state: ProfileState,
+    },
     /// Engage emergency outbound isolation shield (airplane/lockdown mode)
     Lock,
     /// Disengage outbound isolation shield
     Unlock,
     /// List active OS firewall filtering rules
-    Rules,
+    Rules {
+        /// Show packet/byte hit counters, most-hit rules first
+        #[arg(long)]
+        stats: bool,
+    },
+    /// Collect packets dropped by Block rules as structured JSON lines whatever the --output format (Linux NFLOG)
+    Droplog {
+        /// NFLOG group the Block rules log to
+        #[arg(long, default_value_t = DEFAULT_NFLOG_GROUP)]
+        group: u16,
+        /// Append JSON lines to this file instead of stdout
+        #[arg(long = "out", value_name = "FILE")]
+        file: Option<std::path::PathBuf>,
+        /// Forward each drop to a cybersiem collector, e.g. 127.0.0.1:5140
+        #[arg(long)]
+        siem: Option<String>,
+    },
+    /// Inspect or flush the kernel connection tracking table (Linux)
+    Conntrack {
+        #[command(subcommand)]
+        action: ConntrackAction,
+    },
+    /// Manage NAT, port forwarding and masquerade rules
+    Nat {
+        #[command(subcommand)]
+        action: NatAction,
+    },
+    /// Import, export and convert firewall policies
+    Policy {
+        #[command(subcommand)]
+        action: PolicyAction,
+    },
+    /// Inspect interface zones and keep them in sync with link changes (Linux)
+    Zones {
+        #[command(subcommand)]
+        action: ZoneAction,
+    },
+    /// Isolate or quarantine the host, overriding the active policy until a break-glass release
+    Emergency {
+        #[command(subcommand)]
+        action: EmergencyAction,
+    },
+    /// Show or verify the hash-chained log of administrative actions
+    Audit {
+        #[command(subcommand)]
+        action: AuditAction,
+    },
+    /// Re-apply the last applied policy after a reboot, refusing it if it was modified outside cyberwall (Linux)
+    Restore {
+        /// Saved policy written whenever a policy is applied
+        #[arg(long, default_value = SAVED_POLICY_PATH)]
+        file: std::path::PathBuf,
+        /// Write a systemd unit that runs the restore before network-pre.target, instead of restoring
+        #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = "/etc/systemd/system")]
+        install_unit: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ProfileState {
+    On,
+    Off,
+}
+
+#[derive(Subcommand)]
+enum PolicyAction {
+    /// Convert an existing firewall dump into a cyberwall policy
+    Import {
+        /// Source format: iptables-save, nft-json or netsh
+        #[arg(long = "from")]
+        format: ImportFormat,
+        /// Dump file to import
+        file: std::path::PathBuf,
+        /// Name of the resulting policy
+        #[arg(long, default_value = "imported")]
+        name: String,
+        /// Write the policy JSON to this file instead of stdout
+        #[arg(long = "out", value_name = "FILE")]
+        out: Option<std::path::PathBuf>,
+        /// Apply the imported policy to this host immediately
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Render a cyberwall policy for another firewall or as a report
+    Export {
+        /// Target format: nft, iptables, ip6tables, netsh, powershell, markdown or html
+        #[arg(long = "to")]
+        format: ExportFormat,
+        /// Policy JSON file to export
+        file: std::path::PathBuf,
+        /// Write the result to this file instead of stdout
+        #[arg(long = "out", value_name = "FILE")]
+        out: Option<std::path::PathBuf>,
+    },
+    /// Apply a policy file, checking its signature against the trusted keys
+    Apply {
+        /// Policy JSON file; its signature is read from <file>.sig
+        file: std::path::PathBuf,
+    },
+    /// Generate an Ed25519 policy signing key; trust it by copying <out>.pub into the trusted-keys directory
+    Keygen {
+        /// Signing key file to create
+        #[arg(long)]
+        out: std::path::PathBuf,
+    },
+    /// Sign a policy or aegisd manifest, writing the detached signature to <file>.sig
+    Sign {
+        /// Document to sign
+        file: std::path::PathBuf,
+        /// Signing key from `cyberwall policy keygen`
+        #[arg(long)]
+        key: std::path::PathBuf,
+    },
+    /// Check a signed policy or manifest against the trusted keys; exits non-zero unless it verifies
+    Verify {
+        /// Document to check; its signature is read from <file>.sig
+        file: std::path::PathBuf,
+        /// Trusted-keys directory (defaults to the one in the signing config)
+        #[arg(long)]
+        trusted_keys: Option<std::path::PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum NatAction {
+    /// Add a NAT rule
+    Add {
+        /// Unique rule name
+        name: String,
+        /// NAT type: snat, dnat, masquerade or redirect
+        #[arg(long)]
+        kind: NatKind,
+        /// Match protocol (tcp/udp), required when ports are used
+        #[arg(long)]
+        proto: Option<Protocol>,
+        /// Match packets arriving on this interface
+        #[arg(long)]
+        in_interface: Option<String>,
+        /// Match packets leaving through this interface
+        #[arg(long)]
+        out_interface: Option<String>,
+        /// Match source address or CIDR
+        #[arg(long)]
+        source: Option<String>,
+        /// Match destination address or CIDR
+        #[arg(long)]
+        destination: Option<String>,
+        /// Match destination port
+        #[arg(long)]
+        dport: Option<u16>,
+        /// Translated address (snat/dnat)
+        #[arg(long)]
+        to_address: Option<std::net::IpAddr>,
+        /// Translated port (dnat/redirect/masquerade)
+        #[arg(long)]
+        to_port: Option<u16>,
+    },
+    /// List installed NAT rules
+    List,
+    /// Remove a NAT rule by name
+    Remove {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConntrackAction {
+    /// List tracked connections (proto, tuple, state, timeout, mark)
+    List {
+        /// Comma-separated key=value terms that must all match: proto, src, dst, sport, dport, state, mark.
+        /// Addresses and ports are those of the side that opened the flow, e.g. proto=tcp,dport=22,src=10.0.0.2
+        #[arg(long = "match")]
+        filter: Option<String>,
+    },
+    /// Delete tracked connections so new rules apply to existing flows
+    Flush {
+        /// Same terms as `conntrack list --match`; without it the whole table is flushed
+        #[arg(long = "match")]
+        filter: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum EmergencyAction {
+    /// Drop all traffic in both directions except to and from the management channel
+    Isolate {
+        /// Management peer kept reachable: address, CIDR or addr:port (repeatable)
+        #[arg(long = "allow", value_name = "PEER", required = true)]
+        management: Vec<EmergencyPeer>,
+    },
+    /// Only allow outbound connections to remediation servers; list the DNS resolver too if they are named by hostname
+    Quarantine {
+        /// Remediation server: address, CIDR or addr:port (repeatable)
+        #[arg(long = "server", value_name = "PEER", required = true)]
+        remediation: Vec<EmergencyPeer>,
+    },
+    /// Show which emergency mode is in force
+    Status,
+    /// Break-glass: lift the emergency mode and return to the active policy
+    Release {
+        /// Why the lockdown is being lifted; kept in the audit log
+        #[arg(long)]
+        reason: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum AuditAction {
+    /// List the most recent entries, newest first
+    Show {
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: u32,
+    },
+    /// Walk the whole hash chain; exits non-zero if any entry was altered, removed or reordered
+    Verify,
+}
+
+#[derive(Subcommand)]
+enum ZoneAction {
+    /// Show each zone with the interfaces it currently resolves to
+    Show,
+    /// Re-evaluate zone membership whenever interfaces or NetworkManager connections change
+    Watch,
+}
+
+#[cfg(target_os = "linux")]
+fn platform_engine() -> Box<dyn FirewallEngine> {
+    Box::new(LinuxFirewallEngine::new())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn platform_engine() -> Box<dyn FirewallEngine> {
+    Box::new(WindowsFirewallEngine::new())
+}
+
+fn format_time(secs: u64) -> String {
+    chrono::DateTime::from_timestamp(secs as i64, 0).map(|t| t.format("%Y-%m-%d %H:%M:%SZ").to_string()).unwrap_or_else(|| secs.to_string())
+}
+
+fn format_last_hit(last_hit: Option<u64>) -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    match last_hit {
+        Some(ts) => format!("{}s ago", now.saturating_sub(ts)),
+        None => "never / untracked".to_string(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn run_drop_collector(group: u16, sink: &mut DropSink, stop: &AtomicBool) -> cyberwall_core::EngineResult<()> {
+    cyberwall_backend_linux::nflog::NflogCollector::open(group)?.run(sink, stop)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn run_drop_collector(_group: u16, _sink: &mut DropSink, _stop: &AtomicBool) -> cyberwall_core::EngineResult<()> {
+    Err(cyberwall_core::EngineError("NFLOG drop logging is only available on Linux".to_string()))
+}
+
+#[cfg(target_os = "linux")]
+async fn watch_zones(out: Output) -> cyberwall_core::EngineResult<()> {
+    let engine = LinuxFirewallEngine::new();
+    let monitor = Arc::new(cyberwall_backend_linux::zones::LinkMonitor::open()?);
+    loop {
+        engine.refresh_zones().await?;
+        out.progress("[CYBERWALL CLI] Zones re-evaluated against current interfaces.");
+        let monitor = monitor.clone();
+        tokio::task::spawn_blocking(move || monitor.wait())
+            .await
+            .map_err(|e| cyberwall_core::EngineError(format!("Link monitor task failed: {}", e)))??;
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn watch_zones(_out: Output) -> cyberwall_core::EngineResult<()> {
+    Err(cyberwall_core::EngineError("Interface zones are only available on Linux".to_string()))
+}
+
+#[cfg(target_os = "linux")]
+async fn restore_policy(file: &std::path::Path) -> cyberwall_core::EngineResult<FirewallPolicy> {
+    let saved = boot::load(file)?;
+    LinuxFirewallEngine::new().with_saved_policy_path(file).apply_policy(&saved.policy).await?;
+    Ok(saved.policy)
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn restore_policy(_file: &std::path::Path) -> cyberwall_core::EngineResult<FirewallPolicy> {
+    Err(cyberwall_core::EngineError("Boot restore is only needed on Linux; Windows keeps its rules across reboots".to_string()))
+}
+
+impl Commands {
+    /// Name under which an administrative command is written to the audit log; None for read-only commands
+    fn audited(&self) -> Option<&'static str> {
+        match self {
+            Commands::Enable => Some("enable"),
+            Commands::Disable => Some("disable"),
+            Commands::Profile { .. } => Some("profile"),
+            Commands::Lock => Some("lock"),
+            Commands::Unlock => Some("unlock"),
+            Commands::Nat { action: NatAction::Add { .. } } => Some("nat add"),
+            Commands::Nat { action: NatAction::Remove { .. } } => Some("nat remove"),
+            Commands::Conntrack { action: ConntrackAction::Flush { .. } } => Some("conntrack flush"),
+            Commands::Policy { action: PolicyAction::Apply { .. } } => Some("policy apply"),
+            Commands::Policy { action: PolicyAction::Import { apply: true, .. } } => Some("policy import --apply"),
+            Commands::Restore { install_unit: None, .. } => Some("restore"),
+            Commands::Emergency { action: EmergencyAction::Isolate { .. } } => Some("emergency isolate"),
+            Commands::Emergency { action: EmergencyAction::Quarantine { .. } } => Some("emergency quarantine"),
+            Commands::Emergency { action: EmergencyAction::Release { .. } } => Some("emergency release"),
+            _ => None,
+        }
+    }
+}
+
+async fn status_summary(engine: &dyn FirewallEngine) -> Option<String> {
+    engine.get_status().await.ok().map(|s| audit::status_summary(&s))
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
-    let engine = WindowsFirewallEngine::new();
+    let out = Output::new(cli.output, cli.quiet);
+    let daemon = cli.daemon.clone();
+    let engine: Box<dyn FirewallEngine> = match cli.daemon {
+        Some(endpoint) => Box::new(IpcClient::new(endpoint, std::env::var(TOKEN_ENV).ok())),
+        None => platform_engine(),
+    };
+    let result = match cli.command.audited() {
+        Some(action) => run_audited(action, cli.command, engine.as_ref(), daemon, out).await,
+        None => run(cli.command, engine.as_ref(), daemon, out).await,
+    };
+    match result {
+        Err(e) if out.structured() => {
+            out.fail(e.as_ref())?;
+            std::process::exit(1);
+        }
+        other => other,
+    }
+}
 
-    match cli.command {
+async fn run_audited(
+    action: &'static str,
+    command: Commands,
+    engine: &dyn FirewallEngine,
+    daemon: Option<IpcEndpoint>,
+    out: Output,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // An unavailable audit log is reported but does not stop an administrator acting in an emergency
+    let store = StateStore::open_default()
+        .inspect_err(|e| eprintln!("{}", format!("[CYBERWALL CLI] WARNING: audit log unavailable, this action is not recorded: {}", e).yellow().bold()))
+        .ok();
+    let before = status_summary(engine).await;
+    let result = run(command, engine, daemon, out).await;
+    if let Some(store) = store {
+        let actor = Actor::current();
+        let entry = AuditEntry {
+            at: unix_now(),
+            source: "cli".to_string(),
+            actor: actor.name,
+            uid: actor.uid,
+            peer: "local".to_string(),
+            action: action.to_string(),
+            args: std::env::args().skip(1).collect(),
+            before,
+            after: status_summary(engine).await,
+            ok: result.is_ok(),
+            detail: result.as_ref().err().map(|e| e.to_string()).unwrap_or_else(|| "ok".to_string()),
+            ..Default::default()
+        };
+        if let Err(e) = store.audit().record(&entry) {
+            eprintln!("{}", format!("[CYBERWALL CLI] WARNING: failed to record audit entry: {}", e).yellow().bold());
+        }
+    }
+    result
+}
+
+async fn run(command: Commands, engine: &dyn FirewallEngine, daemon: Option<IpcEndpoint>, out: Output) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
         Commands::Status { json } => {
             let status = engine.get_status().await?;
-            if json {
-                println!("{}", serde_json::to_string_pretty(&status)?);
-            } else {
-                println!("{}", "=========================================================".cyan());
-                println!("{}", "       SPLIT2OPS SOFTWARE CYBERWALL ENTERPRISE CLI       ".bold().green());
-                println!("{}", "=========================================================".cyan());
-                println!(" Platform Engine   : {}", status.platform.bold());
-                println!(" Backend Driver    : {}", status.backend_driver.yellow());
-                println!(" Firewall Status   : {}", if status.enabled { "ENABLED (Green)".green().bold() } else { "DISABLED (Red)".red().bold() });
-                println!(" Outbound Shield   : {}", if status.outbound_blocked { "BLOCKED (Red)".red().bold() } else { "NORMAL (Allow)".green() });
-                println!(" Windows Defender  : {}", if status.defender_active { "ACTIVE (Green)".green() } else { "INACTIVE (Red)".red() });
-                println!("{}", "---------------------------------------------------------".cyan());
-                println!(" Private Profile   : {}", if status.profile_private { "ON".green() } else { "OFF".red() });
-                println!(" Public Profile    : {}", if status.profile_public { "ON".green() } else { "OFF".red() });
-                println!(" Domain Profile    : {}", if status.profile_domain { "ON".green() } else { "OFF".red() });
-                println!("{}", "=========================================================".cyan());
-            }
+            out.json_if(json).emit(
+                &status,
+                |status| {
+                    println!("{}", "=========================================================".cyan());
+                    println!("{}", "       SPLIT2OPS SOFTWARE CYBERWALL ENTERPRISE CLI       ".bold().green());
+                    println!("{}", "=========================================================".cyan());
+                    println!(" Platform Engine   : {}", status.platform.bold());
+                    println!(" Backend Driver    : {}", status.backend_driver.yellow());
+                    println!(" Firewall Status   : {}", if status.enabled { "ENABLED (Green)".green().bold() } else { "DISABLED (Red)".red().bold() });
+                    println!(" Outbound Shield   : {}", if status.outbound_blocked { "BLOCKED (Red)".red().bold() } else { "NORMAL (Allow)".green() });
+                    println!(" Emergency Mode    : {}", match status.emergency {
+                        Some(kind) => format!("{:?} (break-glass to release)", kind).to_uppercase().red().bold(),
+                        None => "NONE".green(),
+                    });
+                    println!(" Windows Defender  : {}", if status.defender_active { "ACTIVE (Green)".green() } else { "INACTIVE (Red)".red() });
+                    for p in &status.profiles {
+                        let action = |a: RuleAction| match a {
+                            RuleAction::Allow => "allow".green(),
+                            RuleAction::Block => "block".red(),
+                        };
+                        let logging = match (p.log_allowed, p.log_dropped) {
+                            (true, true) => "allowed+dropped",
+                            (true, false) => "allowed",
+                            (false, true) => "dropped",
+                            (false, false) => "off",
+                        };
+                        println!("{}", "---------------------------------------------------------".cyan());
+                        println!(" {:<18}: {}", format!("{:?} Profile", p.profile), if p.enabled { "ON".green() } else { "OFF".red() });
+                        println!("   Default In/Out  : {} / {}", action(p.default_inbound), action(p.default_outbound));
+                        println!("   Logging         : {}{}", logging, p.log_target.as_ref().map(|t| format!(" ({})", t)).unwrap_or_default());
+                        if !p.interfaces.is_empty() {
+                            println!("   Interfaces      : {}", p.interfaces.join(", "));
+                        }
+                    }
+                    println!("{}", "=========================================================".cyan());
+                },
+                |status| {
+                    println!("enabled\t{}", status.enabled);
+                    println!("outbound_blocked\t{}", status.outbound_blocked);
+                    println!("emergency\t{}", status.emergency.map(|k| format!("{:?}", k).to_lowercase()).unwrap_or_else(|| "none".to_string()));
+                    println!("defender_active\t{}", status.defender_active);
+                    println!("platform\t{}", status.platform);
+                    println!("backend_driver\t{}", status.backend_driver);
+                    for p in &status.profiles {
+                        println!(
+                            "profile\t{}\t{}\t{}\t{}",
+                            format!("{:?}", p.profile).to_lowercase(),
+                            if p.enabled { "on" } else { "off" },
+                            format!("{:?}", p.default_inbound).to_lowercase(),
+                            format!("{:?}", p.default_outbound).to_lowercase()
+                        );
+                    }
+                },
+            )?;
         }
         Commands::Enable => {
-            println!("[CYBERWALL CLI] Transmitting ENABLE signal to OS Kernel...");
+            out.progress("[CYBERWALL CLI] Transmitting ENABLE signal to OS Kernel...");
             engine.set_enabled(true).await?;
-            println!("{}", "[CYBERWALL CLI] SUCCESS: OS Firewall enabled across all profiles.".green().bold());
+            out.done("enable", Tone::Success, "OS Firewall enabled across all profiles.".to_string(), serde_json::Value::Null)?;
         }
         Commands::Disable => {
-            println!("[CYBERWALL CLI] Transmitting DISABLE signal to OS Kernel...");
+            out.progress("[CYBERWALL CLI] Transmitting DISABLE signal to OS Kernel...");
             engine.set_enabled(false).await?;
-            println!("{}", "[CYBERWALL CLI] SUCCESS: OS Firewall disabled across all profiles.".yellow().bold());
+            out.done("disable", Tone::Caution, "OS Firewall disabled across all profiles.".to_string(), serde_json::Value::Null)?;
+        }
+        Commands::Profile { name, state } => {
+            let enabled = matches!(state, ProfileState::On);
+            engine.set_profile_enabled(name, enabled).await?;
+            out.done(
+                "profile",
+                if enabled { Tone::Success } else { Tone::Caution },
+                format!("{:?} profile switched {}.", name, if enabled { "ON" } else { "OFF" }),
+                serde_json::json!({ "profile": name, "enabled": enabled }),
+            )?;
         }
         Commands::Lock => {
-            println!("[CYBERWALL CLI] Engaging Emergency Outbound Isolation Shield...");
+            out.progress("[CYBERWALL CLI] Engaging Emergency Outbound Isolation Shield...");
             engine.set_outbound_block(true).await?;
-            println!("{}", "[CYBERWALL CLI] ALERT: Outbound network traffic is now BLOCKED!".red().bold());
+            out.done("lock", Tone::Alert, "Outbound network traffic is now BLOCKED!".to_string(), serde_json::Value::Null)?;
         }
         Commands::Unlock => {
-            println!("[CYBERWALL CLI] Disengaging Outbound Isolation Shield...");
+            out.progress("[CYBERWALL CLI] Disengaging Outbound Isolation Shield...");
             engine.set_outbound_block(false).await?;
-            println!("{}", "[CYBERWALL CLI] SUCCESS: Outbound network traffic RESTORED.".green().bold());
+            out.done("unlock", Tone::Success, "Outbound network traffic RESTORED.".to_string(), serde_json::Value::Null)?;
+        }
+        Commands::Rules { stats: true } => {
+            let mut stats = engine.rule_stats().await?;
+            stats.sort_by(|a, b| b.packets.cmp(&a.packets).then(b.bytes.cmp(&a.bytes)));
+            out.emit(
+                &stats,
+                |stats| {
+                    println!("{}", "=========================================================".cyan());
+                    println!("{}", "          SPLIT2OPS FIREWALL RULE HIT COUNTERS           ".bold().green());
+                    println!("{}", "=========================================================".cyan());
+                    for (idx, rule) in stats.iter().enumerate() {
+                        let packets = if rule.packets == 0 { rule.packets.to_string().red() } else { rule.packets.to_string().green() };
+                        println!("{}. Rule Name : {}", idx + 1, rule.name.bold());
+                        println!("   Packets   : {}", packets);
+                        println!("   Bytes     : {}", rule.bytes);
+                        println!("   Last Hit  : {}", format_last_hit(rule.last_hit));
+                        println!("{}", "---------------------------------------------------------".cyan());
+                    }
+                    let dead = stats.iter().filter(|r| r.packets == 0).count();
+                    println!(" Rules Never Hit: {}", if dead == 0 { dead.to_string().green() } else { dead.to_string().yellow().bold() });
+                    println!("{}", "=========================================================".cyan());
+                },
+                |stats| {
+                    for rule in stats {
+                        println!("{}\t{}\t{}\t{}", rule.name, rule.packets, rule.bytes, or_dash(rule.last_hit));
+                    }
+                },
+            )?;
         }
-        Commands::Rules => {
+        Commands::Rules { stats: false } => {
             let rules = engine.list_rules().await?;
-            println!("{}", "=========================================================".cyan());
-            println!("{}", "            SPLIT2OPS ACTIVE FIREWALL RULES             ".bold().green());
-            println!("{}", "=========================================================".cyan());
-            for (idx, rule) in rules.iter().enumerate() {
-                println!("{}. Rule Name : {}", idx + 1, rule.name.bold());
-                println!("   Action    : {:?}", rule.action);
-                println!("   Direction : {:?}", rule.direction);
-                println!("   App Path  : {:?}", rule.application);
-                println!("{}", "---------------------------------------------------------".cyan());
+            out.emit(
+                &rules,
+                |rules| {
+                    println!("{}", "=========================================================".cyan());
+                    println!("{}", "            SPLIT2OPS ACTIVE FIREWALL RULES             ".bold().green());
+                    println!("{}", "=========================================================".cyan());
+                    for (idx, rule) in rules.iter().enumerate() {
+                        println!("{}. Rule Name : {}", idx + 1, rule.name.bold());
+                        println!("   Action    : {:?}", rule.action);
+                        println!("   Direction : {:?}", rule.direction);
+                        println!("   Family    : {:?}", rule.family);
+                        println!("   App Path  : {:?}", rule.application);
+                        println!("{}", "---------------------------------------------------------".cyan());
+                    }
+                },
+                |rules| {
+                    for rule in rules {
+                        println!(
+                            "{}\t{}\t{}\t{}\t{}",
+                            rule.name,
+                            format!("{:?}", rule.action).to_lowercase(),
+                            format!("{:?}", rule.direction).to_lowercase(),
+                            if rule.enabled { "enabled" } else { "disabled" },
+                            or_dash(rule.application.as_ref())
+                        );
+                    }
+                },
+            )?;
+        }
+        Commands::Droplog { group, file, siem } => {
+            let writer: Box<dyn std::io::Write + Send> = match &file {
+                Some(path) => Box::new(std::fs::OpenOptions::new().create(true).append(true).open(path)?),
+                None => Box::new(std::io::stdout()),
+            };
+            let siem = match siem {
+                Some(target) => Some((std::net::UdpSocket::bind("0.0.0.0:0")?, target)),
+                None => None,
+            };
+            let mut sink = DropSink { writer, siem };
+            out.note(format!("[CYBERWALL CLI] Listening for dropped packets on NFLOG group {} (Ctrl+C to stop)...", group).cyan());
+            let stop = Arc::new(AtomicBool::new(false));
+            let flag = stop.clone();
+            let mut collector = tokio::task::spawn_blocking(move || run_drop_collector(group, &mut sink, &flag));
+            tokio::select! {
+                res = &mut collector => res??,
+                _ = tokio::signal::ctrl_c() => {
+                    // The blocking read wakes up within a poll interval and sees the flag
+                    stop.store(true, Ordering::Relaxed);
+                    collector.await??;
+                    out.note("\n[CYBERWALL CLI] Drop log collector stopped.");
+                }
+            }
+        }
+        Commands::Policy { action } => match action {
+            PolicyAction::Import { format, file, name, out: path, apply } => {
+                let input = std::fs::read_to_string(&file)?;
+                let report = import_policy(format, &input, &name)?;
+                let json = serde_json::to_string_pretty(&report.policy)?;
+                if let Some(path) = &path {
+                    std::fs::write(path, &json)?;
+                }
+                if apply {
+                    SigningConfig::load_default()?.allow_unsigned("An imported policy")?;
+                    engine.apply_policy(&report.policy).await?;
+                }
+                let summary = ImportSummary { policy: &report.policy, skipped: &report.skipped, written_to: path.as_deref(), applied: apply };
+                if out.structured() {
+                    return out.emit(&summary, |_| {}, |_| {});
+                }
+                // Without --out the policy itself is the result, in every format and even with --quiet
+                if path.is_none() {
+                    println!("{}", json);
+                }
+                out.note(
+                    format!(
+                        "[CYBERWALL CLI] Imported {} rules and {} NAT rules from {}.",
+                        report.policy.rules.len(),
+                        report.policy.nat_rules.len(),
+                        file.display()
+                    )
+                    .green()
+                    .bold(),
+                );
+                if !report.skipped.is_empty() && !out.quiet {
+                    eprintln!("{}", format!("[CYBERWALL CLI] WARNING: {} rules could not be represented:", report.skipped.len()).yellow().bold());
+                    for skipped in &report.skipped {
+                        eprintln!("  {} {}", "-".yellow(), skipped.source.bold());
+                        eprintln!("    {}", skipped.reason);
+                    }
+                }
+                if apply {
+                    out.note(format!("[CYBERWALL CLI] SUCCESS: Policy '{}' applied.", name).green().bold());
+                }
+            }
+            PolicyAction::Export { format, file, out: path } => {
+                let policy: FirewallPolicy = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+                let rendered = export_policy(&policy, format);
+                if let Some(path) = &path {
+                    std::fs::write(path, &rendered)?;
+                }
+                if out.structured() {
+                    let summary = ExportSummary {
+                        policy: &policy.name,
+                        format: format!("{:?}", format).to_lowercase(),
+                        written_to: path.as_deref(),
+                        rendered: path.is_none().then_some(rendered.as_str()),
+                    };
+                    return out.emit(&summary, |_| {}, |_| {});
+                }
+                match &path {
+                    Some(path) => out.note(format!("[CYBERWALL CLI] Policy '{}' exported to {}.", policy.name, path.display()).green().bold()),
+                    None => print!("{}", rendered),
+                }
+            }
+            PolicyAction::Apply { file } => {
+                let signing = SigningConfig::load_default()?;
+                let (raw, signer) = signing.read_verified(&file)?;
+                let document = String::from_utf8(raw).map_err(|_| format!("{} is not UTF-8", file.display()))?;
+                let policy: FirewallPolicy = serde_json::from_str(&document)?;
+                if let Some(key) = &signer {
+                    out.progress(format!("[CYBERWALL CLI] Signature OK: signed by trusted key '{}' ({}).", key.name, key.key_id()));
+                }
+                match (daemon, signing::read_signature(&file)?) {
+                    // The daemon checks the signature again against its own trusted keys
+                    (Some(endpoint), Some(signature)) => {
+                        IpcClient::new(endpoint, std::env::var(TOKEN_ENV).ok()).apply_signed_policy(SignedDocument { document, signature }).await?
+                    }
+                    _ => engine.apply_policy(&policy).await?,
+                }
+                out.done(
+                    "policy apply",
+                    Tone::Success,
+                    format!("Policy '{}' v{} applied ({} rules).", policy.name, policy.version, policy.rules.len()),
+                    serde_json::json!({
+                        "policy": policy.name,
+                        "version": policy.version,
+                        "rules": policy.rules.len(),
+                        "signed_by": signer.as_ref().map(|k| k.key_id()),
+                    }),
+                )?;
+            }
+            PolicyAction::Keygen { out: key_path } => {
+                let key = signing::generate_key();
+                signing::write_keypair(&key_path, &key)?;
+                let key_id = signing::key_id(&key.verifying_key());
+                out.done(
+                    "policy keygen",
+                    Tone::Success,
+                    format!("Wrote {} (key id {}).", key_path.display(), key_id),
+                    serde_json::json!({ "key": key_path, "public_key": key_path.with_extension("pub"), "key_id": key_id }),
+                )?;
+                out.progress(format!(
+                    "Trust it with: cp {} {}/  (add not_before = / not_after = lines to bound a rotation window)",
+                    key_path.with_extension("pub").display(),
+                    SigningConfig::load_default()?.trusted_keys.display()
+                ));
+            }
+            PolicyAction::Sign { file, key } => {
+                let signature = signing::sign_file(&signing::load_secret(&key)?, &file)?;
+                let path = signing::signature_path(&file);
+                out.done(
+                    "policy sign",
+                    Tone::Success,
+                    format!("Signed {} with key {} -> {}.", file.display(), signature.key_id, path.display()),
+                    serde_json::json!({ "file": file, "key_id": signature.key_id, "signature": path }),
+                )?;
             }
+            PolicyAction::Verify { file, trusted_keys } => {
+                let trusted_keys = match trusted_keys {
+                    Some(dir) => dir,
+                    None => SigningConfig::load_default()?.trusted_keys,
+                };
+                let document = std::fs::read(&file)?;
+                let verified = signing::read_signature(&file).and_then(|signature| {
+                    let signature = signature.ok_or_else(|| cyberwall_core::EngineError(format!("{} is not signed (no {})", file.display(), signing::signature_path(&file).display())))?;
+                    let store = TrustStore::load(&trusted_keys)?;
+                    store.verify(&document, &signature, unix_now()).cloned()
+                });
+                let report = VerifyReport {
+                    file: &file,
+                    verified: verified.is_ok(),
+                    key_name: verified.as_ref().ok().map(|k| k.name.clone()),
+                    key_id: verified.as_ref().ok().map(|k| k.key_id()),
+                    not_before: verified.as_ref().ok().and_then(|k| k.not_before),
+                    not_after: verified.as_ref().ok().and_then(|k| k.not_after),
+                    error: verified.as_ref().err().map(|e| e.to_string()),
+                };
+                out.emit(
+                    &report,
+                    |r| match &r.error {
+                        None => {
+                            let window = match (r.not_before, r.not_after) {
+                                (None, None) => "no expiry".to_string(),
+                                (from, until) => format!("valid {} .. {}", or_dash(from), or_dash(until)),
+                            };
+                            println!(
+                                "{}",
+                                format!(
+                                    "[CYBERWALL CLI] VERIFIED: {} is signed by trusted key '{}' ({}, {}).",
+                                    file.display(),
+                                    or_dash(r.key_name.as_ref()),
+                                    or_dash(r.key_id.as_ref()),
+                                    window
+                                )
+                                .green()
+                                .bold()
+                            );
+                        }
+                        Some(e) => eprintln!("{}", format!("[CYBERWALL CLI] NOT VERIFIED: {}", e).red().bold()),
+                    },
+                    |r| match &r.error {
+                        None => println!("verified\t{}\t{}", or_dash(r.key_name.as_ref()), or_dash(r.key_id.as_ref())),
+                        Some(e) => println!("not_verified\t{}", e),
+                    },
+                )?;
+                if !report.verified {
+                    std::process::exit(1);
+                }
+            }
+        },
+        Commands::Nat { action } => match action {
+            NatAction::Add { name, kind, proto, in_interface, out_interface, source, destination, dport, to_address, to_port } => {
+                let rule = NatRule {
+                    name,
+                    enabled: true,
+                    kind,
+                    protocol: proto,
+                    in_interface,
+                    out_interface,
+                    source,
+                    destination,
+                    destination_port: dport,
+                    to_address,
+                    to_port,
+                };
+                out.progress(format!("[CYBERWALL CLI] Installing {:?} rule '{}'...", rule.kind, rule.name));
+                engine.add_nat_rule(&rule).await?;
+                out.done("nat add", Tone::Success, format!("NAT rule '{}' installed.", rule.name), serde_json::to_value(&rule)?)?;
+            }
+            NatAction::List => {
+                let rules = engine.list_nat_rules().await?;
+                let matched = |rule: &NatRule| -> Vec<String> {
+                    [
+                        rule.in_interface.as_ref().map(|i| format!("in={}", i)),
+                        rule.out_interface.as_ref().map(|i| format!("out={}", i)),
+                        rule.source.as_ref().map(|a| format!("src={}", a)),
+                        rule.destination.as_ref().map(|a| format!("dst={}", a)),
+                        rule.protocol.map(|p| format!("proto={}", p)),
+                        rule.destination_port.map(|p| format!("dport={}", p)),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .collect()
+                };
+                let target = |rule: &NatRule| match (rule.to_address, rule.to_port) {
+                    (Some(a), Some(p)) => format!("{}:{}", a, p),
+                    (Some(a), None) => a.to_string(),
+                    (None, Some(p)) => format!(":{}", p),
+                    (None, None) => "-".to_string(),
+                };
+                out.emit(
+                    &rules,
+                    |rules| {
+                        println!("{}", "=========================================================".cyan());
+                        println!("{}", "             SPLIT2OPS ACTIVE NAT RULES                  ".bold().green());
+                        println!("{}", "=========================================================".cyan());
+                        for (idx, rule) in rules.iter().enumerate() {
+                            let matched = matched(rule);
+                            println!("{}. Rule Name : {}", idx + 1, rule.name.bold());
+                            println!("   Type      : {:?}", rule.kind);
+                            println!("   Match     : {}", if matched.is_empty() { "any".to_string() } else { matched.join(" ") });
+                            println!("   Translate : {}", target(rule).yellow());
+                            println!("{}", "---------------------------------------------------------".cyan());
+                        }
+                    },
+                    |rules| {
+                        for rule in rules {
+                            let matched = matched(rule);
+                            println!(
+                                "{}\t{}\t{}\t{}",
+                                rule.name,
+                                format!("{:?}", rule.kind).to_lowercase(),
+                                if matched.is_empty() { "any".to_string() } else { matched.join(" ") },
+                                target(rule)
+                            );
+                        }
+                    },
+                )?;
+            }
+            NatAction::Remove { name } => {
+                engine.remove_nat_rule(&name).await?;
+                out.done("nat remove", Tone::Success, format!("NAT rule '{}' removed.", name), serde_json::json!({ "name": name }))?;
+            }
+        },
+        Commands::Conntrack { action } => match action {
+            ConntrackAction::List { filter } => {
+                let filter = ConntrackFilter::parse(filter.as_deref().unwrap_or(""))?;
+                let entries = ConntrackTable::list(&filter).await?;
+                out.emit(
+                    &entries,
+                    |entries| {
+                        println!("{}", "=========================================================".cyan());
+                        println!("{}", "          SPLIT2OPS CONNECTION TRACKING TABLE            ".bold().green());
+                        println!("{}", "=========================================================".cyan());
+                        for entry in entries {
+                            let t = &entry.original;
+                            println!(
+                                " {:<5} {:>39}:{:<5} -> {:>39}:{:<5} {:<12} {:>7}s mark={}",
+                                entry.protocol.bold(),
+                                t.src,
+                                t.sport.map(|p| p.to_string()).unwrap_or_default(),
+                                t.dst,
+                                t.dport.map(|p| p.to_string()).unwrap_or_default(),
+                                entry.state.as_deref().unwrap_or("-").yellow(),
+                                entry.timeout,
+                                entry.mark
+                            );
+                        }
+                        println!("{}", "---------------------------------------------------------".cyan());
+                        println!(" Tracked Connections: {}", entries.len());
+                        println!("{}", "=========================================================".cyan());
+                    },
+                    |entries| {
+                        for entry in entries {
+                            let t = &entry.original;
+                            println!(
+                                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                                entry.protocol,
+                                t.src,
+                                or_dash(t.sport),
+                                t.dst,
+                                or_dash(t.dport),
+                                entry.state.as_deref().unwrap_or("-"),
+                                entry.timeout,
+                                entry.mark
+                            );
+                        }
+                    },
+                )?;
+            }
+            ConntrackAction::Flush { filter } => {
+                let filter = ConntrackFilter::parse(filter.as_deref().unwrap_or(""))?;
+                out.progress("[CYBERWALL CLI] Flushing connection tracking entries...");
+                let removed = ConntrackTable::flush(&filter).await?;
+                out.done("conntrack flush", Tone::Success, format!("{} conntrack entries removed.", removed), serde_json::json!({ "removed": removed }))?;
+            }
+        },
+        Commands::Zones { action } => match action {
+            ZoneAction::Show => {
+                let config = ZoneConfig::load(std::path::Path::new(ZONES_PATH))?;
+                let links = LinkState::read().await;
+                let resolved = config.resolve(&links);
+                let zones: Vec<serde_json::Value> = resolved
+                    .iter()
+                    .map(|(zone, members)| serde_json::json!({ "zone": zone, "resolved_interfaces": members }))
+                    .collect();
+                out.emit(
+                    &zones,
+                    |_| {
+                        println!("{}", "=========================================================".cyan());
+                        println!("{}", "              SPLIT2OPS INTERFACE ZONES                  ".bold().green());
+                        println!("{}", "=========================================================".cyan());
+                        for (zone, members) in &resolved {
+                            let state = if zone.enabled { "ON".green() } else { "OFF".red() };
+                            println!(" {:<8} [{}]", format!("{:?}", zone.profile).bold(), state);
+                            println!("   Patterns:    {}", zone.interfaces.join(", "));
+                            println!("   Connections: {}", zone.connections.join(", "));
+                            println!("   Resolved:    {}", if members.is_empty() { "-".to_string() } else { members.join(", ") });
+                        }
+                        println!("{}", "=========================================================".cyan());
+                    },
+                    |_| {
+                        for (zone, members) in &resolved {
+                            println!(
+                                "{}\t{}\t{}",
+                                format!("{:?}", zone.profile).to_lowercase(),
+                                if zone.enabled { "on" } else { "off" },
+                                if members.is_empty() { "-".to_string() } else { members.join(",") }
+                            );
+                        }
+                    },
+                )?;
+            }
+            ZoneAction::Watch => {
+                out.progress("[CYBERWALL CLI] Watching interface and NetworkManager changes (Ctrl+C to stop)...");
+                watch_zones(out).await?;
+            }
+        },
+        Commands::Emergency { action } => {
+            let (mode, reason) = match action {
+                EmergencyAction::Status => {
+                    let status = engine.get_status().await?;
+                    let report = EmergencyStatus { emergency: status.emergency };
+                    return out.emit(
+                        &report,
+                        |r| match r.emergency {
+                            Some(kind) => println!("{}", format!("[CYBERWALL CLI] EMERGENCY {:?} mode is in force.", kind).to_uppercase().red().bold()),
+                            None => println!("{}", "[CYBERWALL CLI] No emergency mode in force.".green().bold()),
+                        },
+                        |r| println!("{}", r.emergency.map(|k| format!("{:?}", k).to_lowercase()).unwrap_or_else(|| "none".to_string())),
+                    );
+                }
+                EmergencyAction::Isolate { management } => (Some(EmergencyMode::Isolate { management }), None),
+                EmergencyAction::Quarantine { remediation } => (Some(EmergencyMode::Quarantine { remediation }), None),
+                EmergencyAction::Release { reason } => (None, Some(reason)),
+            };
+            match &mode {
+                Some(mode) => {
+                    let peers: Vec<String> = mode.peers().iter().map(|p| p.to_string()).collect();
+                    out.progress(format!("[CYBERWALL CLI] Engaging emergency {:?} mode (reachable: {})...", mode.kind(), peers.join(", ")));
+                }
+                None => out.progress(format!("[CYBERWALL CLI] Break-glass: releasing emergency mode ({})...", reason.as_deref().unwrap_or_default())),
+            }
+            match daemon {
+                Some(endpoint) => {
+                    IpcClient::new(endpoint, std::env::var(TOKEN_ENV).ok()).set_emergency_with_reason(mode.as_ref(), reason.as_deref()).await?
+                }
+                None => engine.set_emergency(mode.as_ref()).await?,
+            }
+            match mode {
+                Some(mode) => out.done(
+                    if mode.kind() == EmergencyKind::Isolate { "emergency isolate" } else { "emergency quarantine" },
+                    Tone::Alert,
+                    format!("Host is in {:?} mode; release with `cyberwall emergency release --reason ...`.", mode.kind()),
+                    serde_json::to_value(&mode)?,
+                )?,
+                None => out.done(
+                    "emergency release",
+                    Tone::Success,
+                    "Emergency mode released; the active policy is back in force.".to_string(),
+                    serde_json::json!({ "reason": reason }),
+                )?,
+            }
+        }
+        Commands::Audit { action } => match action {
+            AuditAction::Show { limit } => {
+                let entries = StateStore::open_default()?.audit().recent(limit)?;
+                out.emit(
+                    &entries,
+                    |entries| {
+                        println!("{}", "=========================================================".cyan());
+                        println!("{}", "            SPLIT2OPS ADMINISTRATIVE AUDIT LOG           ".bold().green());
+                        println!("{}", "=========================================================".cyan());
+                        for entry in entries {
+                            let result = if entry.ok { "OK".green().bold() } else { "FAILED".red().bold() };
+                            let uid = entry.uid.map(|u| format!(" uid={}", u)).unwrap_or_default();
+                            println!("#{} {}  {}  [{}]", entry.id, format_time(entry.at), entry.action.bold(), result);
+                            println!("   Actor     : {}{} via {} ({})", entry.actor, uid, entry.source, entry.peer);
+                            if !entry.args.is_empty() {
+                                println!("   Arguments : {}", entry.args.join(" "));
+                            }
+                            if let Some(before) = &entry.before {
+                                println!("   Before    : {}", before);
+                            }
+                            if let Some(after) = &entry.after {
+                                println!("   After     : {}", after);
+                            }
+                            if !entry.ok {
+                                println!("   Error     : {}", entry.detail.red());
+                            }
+                            println!("{}", "---------------------------------------------------------".cyan());
+                        }
+                        if entries.is_empty() {
+                            println!(" No administrative actions recorded yet.");
+                        }
+                    },
+                    |entries| {
+                        for entry in entries {
+                            println!(
+                                "{}\t{}\t{}\t{}\t{}\t{}",
+                                entry.id,
+                                entry.at,
+                                entry.action,
+                                if entry.ok { "ok" } else { "failed" },
+                                entry.actor,
+                                entry.source
+                            );
+                        }
+                    },
+                )?;
+            }
+            AuditAction::Verify => {
+                let report = StateStore::open_default()?.audit().verify()?;
+                out.emit(
+                    &report,
+                    |report| {
+                        let head = report.head.as_deref().unwrap_or("-");
+                        if report.intact() {
+                            println!(
+                                "{}",
+                                format!("[CYBERWALL CLI] AUDIT CHAIN INTACT: {} entries, head {}.", report.entries, head).green().bold()
+                            );
+                            if report.unchained > 0 {
+                                println!("{}", format!("[CYBERWALL CLI] {} older entries predate the chain and cannot be checked.", report.unchained).yellow());
+                            }
+                        } else {
+                            eprintln!("{}", format!("[CYBERWALL CLI] AUDIT CHAIN BROKEN: {} problems in {} entries.", report.problems.len(), report.entries).red().bold());
+                            for problem in &report.problems {
+                                eprintln!("  {} {}", "-".red(), problem);
+                            }
+                        }
+                    },
+                    |report| {
+                        println!("{}\t{}\t{}", if report.intact() { "intact" } else { "broken" }, report.entries, report.head.as_deref().unwrap_or("-"));
+                        for problem in &report.problems {
+                            println!("problem\t{}", problem);
+                        }
+                    },
+                )?;
+                if !report.intact() {
+                    std::process::exit(1);
+                }
+            }
+        },
+        Commands::Restore { file, install_unit: Some(dir) } => {
+            let binary = std::env::current_exe()?;
+            let path = boot::install_restore_unit(&dir, &binary, &file)?;
+            out.done("restore --install-unit", Tone::Success, format!("Wrote {}.", path.display()), serde_json::json!({ "unit": path }))?;
+            out.progress(format!("Enable with: systemctl daemon-reload && systemctl enable {}", boot::RESTORE_UNIT));
+        }
+        Commands::Restore { file, install_unit: None } => {
+            out.progress(format!("[CYBERWALL CLI] Restoring saved policy from {}...", file.display()));
+            let policy = restore_policy(&file).await?;
+            out.done(
+                "restore",
+                Tone::Success,
+                format!(
+                    "Policy '{}' v{} restored ({} rules, {} NAT rules).",
+                    policy.name,
+                    policy.version,
+                    policy.rules.len(),
+                    policy.nat_rules.len()
+                ),
+                serde_json::json!({ "policy": policy.name, "version": policy.version, "rules": policy.rules.len(), "nat_rules": policy.nat_rules.len() }),
+            )?;
         }
     }
 
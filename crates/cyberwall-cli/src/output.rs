@@ -0,0 +1,181 @@
+use clap::ValueEnum;
+use colored::*;
+use cyberwall_core::import::SkippedRule;
+use cyberwall_core::{EmergencyKind, FirewallPolicy};
+use serde::Serialize;
+use std::io::IsTerminal;
+use std::path::Path;
+
+/// How command results are written to stdout
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Banners and colors for people
+    #[default]
+    Table,
+    Json,
+    Yaml,
+    /// One record per line, tab-separated, for shell pipelines
+    Plain,
+}
+
+/// How an action's outcome is worded in table output
+#[derive(Clone, Copy)]
+pub enum Tone {
+    Success,
+    /// Succeeded, but protection is weaker than before
+    Caution,
+    /// Succeeded and traffic is now being cut off
+    Alert,
+}
+
+/// Outcome of a command that changes state; the stable schema for json and yaml output
+#[derive(Serialize)]
+pub struct ActionReport {
+    pub action: &'static str,
+    pub ok: bool,
+    pub message: String,
+    /// Command-specific fields, e.g. the number of conntrack entries removed
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    pub details: serde_json::Value,
+}
+
+/// `cyberwall policy import` in json and yaml output
+#[derive(Serialize)]
+pub struct ImportSummary<'a> {
+    pub policy: &'a FirewallPolicy,
+    pub skipped: &'a [SkippedRule],
+    pub written_to: Option<&'a Path>,
+    pub applied: bool,
+}
+
+/// `cyberwall policy export` in json and yaml output; `rendered` is only set when no file was written
+#[derive(Serialize)]
+pub struct ExportSummary<'a> {
+    pub policy: &'a str,
+    pub format: String,
+    pub written_to: Option<&'a Path>,
+    pub rendered: Option<&'a str>,
+}
+
+/// `cyberwall policy verify`
+#[derive(Serialize)]
+pub struct VerifyReport<'a> {
+    pub file: &'a Path,
+    pub verified: bool,
+    pub key_name: Option<String>,
+    pub key_id: Option<String>,
+    pub not_before: Option<u64>,
+    pub not_after: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// `cyberwall emergency status`
+#[derive(Serialize)]
+pub struct EmergencyStatus {
+    pub emergency: Option<EmergencyKind>,
+}
+
+/// Written in place of a result when a json or yaml command fails
+#[derive(Serialize)]
+pub struct ErrorReport {
+    pub ok: bool,
+    pub error: String,
+}
+
+type OutputResult = Result<(), Box<dyn std::error::Error>>;
+
+/// Output settings shared by every command
+#[derive(Clone, Copy)]
+pub struct Output {
+    pub format: OutputFormat,
+    pub quiet: bool,
+}
+
+impl Output {
+    /// Colors are only kept for table output on a terminal
+    pub fn new(format: OutputFormat, quiet: bool) -> Self {
+        if format != OutputFormat::Table || !std::io::stdout().is_terminal() {
+            colored::control::set_override(false);
+        }
+        Self { format, quiet }
+    }
+
+    /// Honours a command's legacy `--json` flag
+    pub fn json_if(self, json: bool) -> Self {
+        match json {
+            true => Self { format: OutputFormat::Json, ..self },
+            false => self,
+        }
+    }
+
+    /// True for the json and yaml formats
+    pub fn structured(&self) -> bool {
+        matches!(self.format, OutputFormat::Json | OutputFormat::Yaml)
+    }
+
+    /// Whether banners and progress lines are shown
+    pub fn chatty(&self) -> bool {
+        self.format == OutputFormat::Table && !self.quiet
+    }
+
+    /// Progress line for people; dropped by every other format and by --quiet
+    pub fn progress(&self, message: impl std::fmt::Display) {
+        if self.chatty() {
+            println!("{}", message);
+        }
+    }
+
+    /// Same as `progress`, on stderr for commands whose stdout is a document
+    pub fn note(&self, message: impl std::fmt::Display) {
+        if self.chatty() {
+            eprintln!("{}", message);
+        }
+    }
+
+    /// Writes a result: serialized for json and yaml, otherwise through the command's own renderers;
+    /// --quiet only silences the human formats, a requested json or yaml document is always written
+    pub fn emit<T: Serialize + ?Sized>(&self, value: &T, table: impl FnOnce(&T), plain: impl FnOnce(&T)) -> OutputResult {
+        if self.quiet && !self.structured() {
+            return Ok(());
+        }
+        match self.format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+            OutputFormat::Table => table(value),
+            OutputFormat::Plain => plain(value),
+        }
+        Ok(())
+    }
+
+    /// Reports a completed action
+    pub fn done(&self, action: &'static str, tone: Tone, message: String, details: serde_json::Value) -> OutputResult {
+        let report = ActionReport { action, ok: true, message, details };
+        self.emit(
+            &report,
+            |r| {
+                let line = match tone {
+                    Tone::Success => format!("[CYBERWALL CLI] SUCCESS: {}", r.message).green().bold(),
+                    Tone::Caution => format!("[CYBERWALL CLI] SUCCESS: {}", r.message).yellow().bold(),
+                    Tone::Alert => format!("[CYBERWALL CLI] ALERT: {}", r.message).red().bold(),
+                };
+                println!("{}", line);
+            },
+            |r| println!("{}", r.message),
+        )
+    }
+
+    /// Writes a failure as a document on stdout, so scripts parsing json or yaml always get one
+    pub fn fail(&self, error: &dyn std::error::Error) -> OutputResult {
+        let report = ErrorReport { ok: false, error: error.to_string() };
+        match self.format {
+            OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&report)?),
+            _ => println!("{}", serde_json::to_string_pretty(&report)?),
+        }
+        Ok(())
+    }
+}
+
+/// Table cell for an optional value
+pub fn or_dash<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string())
+}
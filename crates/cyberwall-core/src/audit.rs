@@ -0,0 +1,268 @@
+use crate::models::FirewallStatus;
+use crate::state::AuditEntry;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Who is running this process, as recorded in audit entries
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Actor {
+    pub uid: Option<u32>,
+    pub name: String,
+}
+
+impl Actor {
+    /// Real user of this process; a sudo caller is kept next to the account it switched to
+    pub fn current() -> Self {
+        let uid = current_uid();
+        let name = uid
+            .and_then(user_name)
+            .or_else(|| std::env::var("USER").ok())
+            .or_else(|| std::env::var("USERNAME").ok())
+            .unwrap_or_else(|| "unknown".to_string());
+        let name = match std::env::var("SUDO_USER") {
+            Ok(by) if !by.is_empty() && by != name => format!("{} (sudo by {})", name, by),
+            _ => name,
+        };
+        Self { uid, name }
+    }
+}
+
+#[cfg(unix)]
+fn current_uid() -> Option<u32> {
+    // SAFETY: getuid has no preconditions and cannot fail
+    Some(unsafe { libc::getuid() })
+}
+
+#[cfg(not(unix))]
+fn current_uid() -> Option<u32> {
+    None
+}
+
+/// Account name of a local uid, e.g. for the peer of a Unix control connection
+#[cfg(unix)]
+pub fn user_name(uid: u32) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    // SAFETY: every pointer refers to a live local of the size passed alongside it
+    let rc = unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+    if rc != 0 || result.is_null() {
+        return None;
+    }
+    // SAFETY: on success pw_name points into `buf` and is NUL-terminated
+    let name = unsafe { std::ffi::CStr::from_ptr(pwd.pw_name) };
+    name.to_str().ok().map(str::to_string)
+}
+
+#[cfg(not(unix))]
+pub fn user_name(_uid: u32) -> Option<String> {
+    None
+}
+
+/// One-line firewall state kept as the before/after of an audited action
+pub fn status_summary(status: &FirewallStatus) -> String {
+    let profiles: Vec<String> =
+        status.profiles.iter().map(|p| format!("{:?}={}", p.profile, if p.enabled { "on" } else { "off" }).to_lowercase()).collect();
+    let emergency = status.emergency.map(|k| format!(" emergency={:?}", k).to_lowercase()).unwrap_or_default();
+    format!(
+        "firewall={} outbound={} profiles=[{}]{}",
+        if status.enabled { "enabled" } else { "disabled" },
+        if status.outbound_blocked { "blocked" } else { "allowed" },
+        profiles.join(","),
+        emergency
+    )
+}
+
+/// Chain hash of an entry: SHA-256 over its JSON form with `hash` left empty, so it covers `id` and `prev_hash`
+pub fn entry_hash(entry: &AuditEntry) -> String {
+    let mut unsealed = entry.clone();
+    unsealed.hash.clear();
+    let bytes = serde_json::to_vec(&unsealed).unwrap_or_default();
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Outcome of walking the audit chain from the first entry to the last
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChainReport {
+    pub entries: u64,
+    /// Entries written before the log was chained; they cannot be checked
+    pub unchained: u64,
+    /// Hash of the newest entry
+    pub head: Option<String>,
+    pub problems: Vec<String>,
+}
+
+impl ChainReport {
+    pub fn intact(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+fn id_range(first: u64, last: u64) -> String {
+    match first == last {
+        true => format!("entry {}", first),
+        false => format!("entries {} to {}", first, last),
+    }
+}
+
+/// Checks ids are gapless, each entry links to the one before it, each hash matches and nothing follows `last_id`
+pub fn verify_chain(entries: &[AuditEntry], last_id: u64) -> ChainReport {
+    let mut report = ChainReport { entries: entries.len() as u64, ..Default::default() };
+    if let Some(first) = entries.first().filter(|e| e.id > 1) {
+        report.problems.push(format!("{} removed from the start", id_range(1, first.id - 1)));
+    }
+    let mut previous: Option<&AuditEntry> = None;
+    for entry in entries {
+        if let Some(prev) = previous {
+            if entry.id != prev.id + 1 {
+                report.problems.push(format!("{} missing", id_range(prev.id + 1, entry.id - 1)));
+            }
+        }
+        let chained_before = previous.is_some_and(|p| !p.hash.is_empty());
+        if entry.hash.is_empty() {
+            match chained_before {
+                true => report.problems.push(format!("entry {} has no hash after the chain started", entry.id)),
+                false => report.unchained += 1,
+            }
+            previous = Some(entry);
+            continue;
+        }
+        let expected_prev = previous.map(|p| p.hash.as_str()).unwrap_or("");
+        if entry.prev_hash != expected_prev {
+            report.problems.push(format!("entry {} does not link to the entry before it", entry.id));
+        }
+        if entry_hash(entry) != entry.hash {
+            report.problems.push(format!("entry {} was modified after it was written", entry.id));
+        }
+        previous = Some(entry);
+    }
+    let newest = previous.map(|p| p.id).unwrap_or(0);
+    if newest < last_id {
+        report.problems.push(format!("{} removed from the end", id_range(newest + 1, last_id)));
+    }
+    report.head = previous.map(|p| p.hash.clone()).filter(|h| !h.is_empty());
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::StateStore;
+
+    fn entry(id: u64) -> AuditEntry {
+        AuditEntry { id, at: 1_790_000_000 + id, source: "unix".to_string(), actor: "root".to_string(), action: "apply_policy".to_string(), ok: true, ..Default::default() }
+    }
+
+    /// Links and seals entries the way the audit repo does when it records them
+    fn seal(entries: &mut [AuditEntry]) {
+        let mut prev = String::new();
+        for entry in entries.iter_mut() {
+            entry.prev_hash = prev;
+            entry.hash = entry_hash(entry);
+            prev = entry.hash.clone();
+        }
+    }
+
+    fn chain(ids: std::ops::RangeInclusive<u64>) -> Vec<AuditEntry> {
+        let mut entries: Vec<AuditEntry> = ids.map(entry).collect();
+        seal(&mut entries);
+        entries
+    }
+
+    #[test]
+    fn intact_chain_verifies() {
+        let entries = chain(1..=4);
+        let report = verify_chain(&entries, 4);
+        assert!(report.intact(), "{:?}", report.problems);
+        assert_eq!((report.entries, report.unchained), (4, 0));
+        assert_eq!(report.head.as_deref(), Some(entries[3].hash.as_str()));
+        assert!(verify_chain(&[], 0).intact());
+    }
+
+    #[test]
+    fn modified_entry_is_reported() {
+        let mut entries = chain(1..=4);
+        entries[1].detail = "ok".to_string();
+        assert_eq!(verify_chain(&entries, 4).problems, vec!["entry 2 was modified after it was written"]);
+    }
+
+    #[test]
+    fn resealed_entry_breaks_the_next_link() {
+        // Rewriting an entry and recomputing its hash still leaves the following entry pointing at the old hash
+        let mut entries = chain(1..=4);
+        entries[1].ok = false;
+        entries[1].hash = entry_hash(&entries[1]);
+        assert_eq!(verify_chain(&entries, 4).problems, vec!["entry 3 does not link to the entry before it"]);
+
+        let mut entries = chain(1..=4);
+        entries[2].prev_hash = entries[0].hash.clone();
+        entries[2].hash = entry_hash(&entries[2]);
+        let problems = verify_chain(&entries, 4).problems;
+        assert!(problems.contains(&"entry 3 does not link to the entry before it".to_string()), "{:?}", problems);
+    }
+
+    #[test]
+    fn gaps_are_reported() {
+        let mut entries = chain(1..=6);
+        entries.drain(2..4);
+        let problems = verify_chain(&entries, 6).problems;
+        assert_eq!(problems, vec!["entries 3 to 4 missing", "entry 5 does not link to the entry before it"]);
+
+        let entries = chain(1..=3).split_off(1);
+        assert_eq!(verify_chain(&entries, 3).problems[0], "entry 1 removed from the start");
+    }
+
+    #[test]
+    fn truncated_tail_is_caught_by_the_last_id() {
+        let mut entries = chain(1..=5);
+        entries.truncate(3);
+        assert_eq!(verify_chain(&entries, 5).problems, vec!["entries 4 to 5 removed from the end"]);
+        // Without the recorded last id a clean truncation would be undetectable
+        assert!(verify_chain(&entries, 3).intact());
+        assert_eq!(verify_chain(&[], 2).problems, vec!["entries 1 to 2 removed from the end"]);
+    }
+
+    #[test]
+    fn unchained_prefix_is_counted_not_reported() {
+        let mut entries: Vec<AuditEntry> = (1..=5).map(entry).collect();
+        seal(&mut entries[2..]);
+        let report = verify_chain(&entries, 5);
+        assert!(report.intact(), "{:?}", report.problems);
+        assert_eq!(report.unchained, 2);
+
+        // Once the chain has started, an entry without a hash is an edit
+        entries[3].hash.clear();
+        let problems = verify_chain(&entries, 5).problems;
+        assert!(problems.contains(&"entry 4 has no hash after the chain started".to_string()), "{:?}", problems);
+    }
+
+    struct TempDir(std::path::PathBuf);
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn deleting_rows_from_the_database_is_detected() {
+        let dir = TempDir(std::env::temp_dir().join(format!("cyberwall-audit-{}", std::process::id())));
+        std::fs::create_dir_all(&dir.0).unwrap();
+        let path = dir.0.join("state.db");
+        let store = StateStore::open(&path).unwrap();
+        for id in 1..=4 {
+            store.audit().record(&entry(id)).unwrap();
+        }
+        assert!(store.audit().verify().unwrap().intact());
+
+        // Someone with write access to the file can drop the append-only triggers, but sqlite_sequence
+        // keeps the highest id handed out, so removing the newest rows still shows
+        let raw = rusqlite::Connection::open(&path).unwrap();
+        raw.execute_batch("DROP TRIGGER audit_log_no_delete; DELETE FROM audit_log WHERE id >= 3;").unwrap();
+        assert_eq!(store.audit().verify().unwrap().problems, vec!["entries 3 to 4 removed from the end"]);
+
+        // Resetting the sequence as well is the one deletion the chain cannot see
+        raw.execute("UPDATE sqlite_sequence SET seq = 2 WHERE name = 'audit_log'", []).unwrap();
+        assert!(store.audit().verify().unwrap().intact());
+    }
+}
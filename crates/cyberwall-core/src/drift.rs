@@ -0,0 +1,258 @@
+use crate::export::NDP_COMMENT;
+use crate::models::{cidr_family, AddressFamily, FirewallPolicy, FirewallRule};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// One field of a rule whose live value differs from the policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub expected: serde_json::Value,
+    pub live: serde_json::Value,
+}
+
+/// Difference between the live ruleset and the policy it should match
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuleDrift {
+    /// Enabled in the policy but not installed
+    Missing { rule: String },
+    /// Installed but not defined by the policy
+    Unexpected { rule: String },
+    /// Installed with different settings
+    Changed { rule: String, changes: Vec<FieldChange> },
+}
+
+impl fmt::Display for RuleDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleDrift::Missing { rule } => write!(f, "missing rule '{}'", rule),
+            RuleDrift::Unexpected { rule } => write!(f, "unexpected rule '{}'", rule),
+            RuleDrift::Changed { rule, changes } => {
+                let changes: Vec<String> = changes.iter().map(|c| format!("{} {} -> {}", c.field, c.expected, c.live)).collect();
+                write!(f, "changed rule '{}': {}", rule, changes.join(", "))
+            }
+        }
+    }
+}
+
+fn field_changes(expected: &FirewallRule, live: &FirewallRule) -> Vec<FieldChange> {
+    let (Ok(serde_json::Value::Object(expected)), Ok(serde_json::Value::Object(live))) = (serde_json::to_value(expected), serde_json::to_value(live)) else {
+        return Vec::new();
+    };
+    expected
+        .iter()
+        .filter(|(field, value)| live.get(*field) != Some(*value))
+        .map(|(field, value)| FieldChange { field: field.clone(), expected: value.clone(), live: live.get(field).cloned().unwrap_or_default() })
+        .collect()
+}
+
+/// Family a rule can actually match: an `any` rule whose addresses are all of one family only ever matches that one
+fn effective_family(rule: &FirewallRule) -> AddressFamily {
+    if rule.family != AddressFamily::Any {
+        return rule.family;
+    }
+    let mut families = rule.local_addresses.iter().chain(&rule.remote_addresses).filter_map(|a| cidr_family(a).ok());
+    match families.next() {
+        Some(first) if families.all(|f| f == first) => first,
+        _ => AddressFamily::Any,
+    }
+}
+
+/// Puts a rule in the form both sides are compared in; address and port order carry no meaning
+fn normalized(rule: &FirewallRule) -> FirewallRule {
+    let mut rule = rule.clone();
+    rule.family = effective_family(&rule);
+    for addrs in [&mut rule.local_addresses, &mut rule.remote_addresses] {
+        addrs.sort();
+        addrs.dedup();
+    }
+    for ports in [&mut rule.local_ports, &mut rule.remote_ports] {
+        ports.sort_by_key(|p| (p.start, p.end));
+        ports.dedup();
+    }
+    rule
+}
+
+/// Folds live rules sharing a name into one; a rule with v4 and v6 addresses is installed once per family
+fn merge_installed(live: &[FirewallRule]) -> Vec<FirewallRule> {
+    let mut merged: Vec<FirewallRule> = Vec::new();
+    for rule in live {
+        match merged.iter_mut().find(|r| r.name == rule.name) {
+            Some(existing) => {
+                existing.local_addresses.extend(rule.local_addresses.iter().cloned());
+                existing.remote_addresses.extend(rule.remote_addresses.iter().cloned());
+                if existing.family != rule.family {
+                    existing.family = AddressFamily::Any;
+                }
+            }
+            None => merged.push(rule.clone()),
+        }
+    }
+    merged.iter().map(normalized).collect()
+}
+
+/// Compares live rules with the policy by name; disabled policy rules may be absent, as some backends do not install them
+pub fn diff_rules(policy: &FirewallPolicy, live: &[FirewallRule]) -> Vec<RuleDrift> {
+    let live = merge_installed(live);
+    let mut drift = Vec::new();
+    for expected in &policy.rules {
+        match live.iter().find(|r| r.name == expected.name) {
+            None if expected.enabled => drift.push(RuleDrift::Missing { rule: expected.name.clone() }),
+            None => {}
+            Some(installed) => {
+                let changes = field_changes(&normalized(expected), installed);
+                if !changes.is_empty() {
+                    drift.push(RuleDrift::Changed { rule: expected.name.clone(), changes });
+                }
+            }
+        }
+    }
+    // The neighbor discovery rule comes from `allow_neighbor_discovery`, not from a policy rule
+    for installed in live.iter().filter(|r| r.name != NDP_COMMENT) {
+        if !policy.rules.iter().any(|r| r.name == installed.name) {
+            drift.push(RuleDrift::Unexpected { rule: installed.name.clone() });
+        }
+    }
+    drift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{render_nft, NftOptions};
+    use crate::import::policy_from_nft_json;
+    use crate::models::{PortRange, ProfileType, Protocol, RuleAction, RuleDirection};
+    use serde_json::Value;
+
+    /// `nft -j list table inet cyberwall` after loading `sample()` rendered with zone hooks, and without
+    const LIST_TABLE: &str = include_str!("../testdata/nft/list-table.json");
+    const LIST_TABLE_PORTABLE: &str = include_str!("../testdata/nft/list-table.portable.json");
+
+    fn policy(rules: Vec<FirewallRule>) -> FirewallPolicy {
+        FirewallPolicy { name: "test".to_string(), version: "1".to_string(), rules, nat_rules: Vec::new(), allow_neighbor_discovery: true }
+    }
+
+    fn live(listing: &str) -> Vec<FirewallRule> {
+        policy_from_nft_json(listing).unwrap().0
+    }
+
+    /// Commented rules per chain, in order, so a listing can be checked against the script it was loaded from
+    fn script_comments(script: &str) -> Vec<(String, String)> {
+        let mut chain = String::new();
+        let mut comments = Vec::new();
+        for line in script.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix("chain ").and_then(|l| l.strip_suffix(" {")) {
+                chain = name.to_string();
+            } else if let Some((_, comment)) = line.split_once(" comment \"") {
+                comments.push((chain.clone(), comment.trim_end_matches('"').to_string()));
+            }
+        }
+        comments
+    }
+
+    fn listing_comments(listing: &str) -> Vec<(String, String)> {
+        let doc: Value = serde_json::from_str(listing).unwrap();
+        doc["nftables"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|item| item.get("rule"))
+            .filter_map(|rule| Some((rule["chain"].as_str()?.to_string(), rule.get("comment")?.as_str()?.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn listings_match_the_rendered_scripts() {
+        // A renderer change that moves or renames rules needs the listings recaptured
+        for (opts, listing) in [(NftOptions::default(), LIST_TABLE), (NftOptions::portable(), LIST_TABLE_PORTABLE)] {
+            assert_eq!(listing_comments(listing), script_comments(&render_nft(&sample(), opts)));
+        }
+    }
+
+    fn sample() -> FirewallPolicy {
+        let port = |p: u16| PortRange { start: p, end: p };
+        policy(vec![
+            FirewallRule {
+                protocol: Some(Protocol::Tcp),
+                local_ports: vec![port(22)],
+                remote_addresses: vec!["10.0.0.0/8".to_string(), "192.168.1.5".to_string()],
+                ..FirewallRule::new("ssh from lan", RuleAction::Allow, RuleDirection::Inbound)
+            },
+            FirewallRule {
+                protocol: Some(Protocol::Udp),
+                remote_ports: vec![port(53)],
+                remote_addresses: vec!["2001:db8::53".to_string(), "192.0.2.53".to_string(), "198.51.100.53".to_string()],
+                ..FirewallRule::new("dns both families", RuleAction::Allow, RuleDirection::Outbound)
+            },
+            FirewallRule {
+                family: AddressFamily::Ipv6,
+                protocol: Some(Protocol::Tcp),
+                local_ports: vec![port(443), PortRange { start: 8000, end: 8100 }, port(80)],
+                ..FirewallRule::new("web v6 only", RuleAction::Allow, RuleDirection::Inbound)
+            },
+            FirewallRule {
+                profile: ProfileType::Public,
+                protocol: Some(Protocol::Tcp),
+                local_ports: vec![port(445)],
+                ..FirewallRule::new("no smb on public", RuleAction::Block, RuleDirection::Inbound)
+            },
+            FirewallRule {
+                remote_ports: vec![port(53)],
+                remote_addresses: vec!["192.0.2.53".to_string()],
+                ..FirewallRule::new("dns any transport", RuleAction::Allow, RuleDirection::Outbound)
+            },
+            FirewallRule { enabled: false, ..FirewallRule::new("disabled", RuleAction::Block, RuleDirection::Outbound) },
+        ])
+    }
+
+    #[test]
+    fn rendered_policy_reads_back_without_drift() {
+        let policy = sample();
+        for (opts, listing) in [(NftOptions::default(), LIST_TABLE), (NftOptions::portable(), LIST_TABLE_PORTABLE)] {
+            let live = live(listing);
+            let expected: Vec<&FirewallRule> = policy.rules.iter().filter(|r| r.enabled && (opts.zone_hooks || r.profile == ProfileType::All)).collect();
+            assert!(expected.iter().all(|e| live.iter().any(|r| r.name == e.name)), "rules lost on import: {:?}", live);
+            let drift: Vec<RuleDrift> = diff_rules(&policy, &live)
+                .into_iter()
+                .filter(|d| opts.zone_hooks || *d != RuleDrift::Missing { rule: "no smb on public".to_string() })
+                .collect();
+            assert_eq!(drift, Vec::new());
+        }
+    }
+
+    #[test]
+    fn split_family_rule_is_compared_as_one() {
+        let policy = sample();
+        let live = live(LIST_TABLE);
+        assert_eq!(live.iter().filter(|r| r.name == "dns both families").count(), 2);
+        let mut trimmed = live.clone();
+        let v6 = trimmed.iter().position(|r| r.name == "dns both families" && r.family == AddressFamily::Ipv6).unwrap();
+        trimmed.remove(v6);
+        let drift = diff_rules(&policy, &trimmed);
+        assert!(matches!(drift.as_slice(), [RuleDrift::Changed { rule, .. }] if rule == "dns both families"), "{:?}", drift);
+    }
+
+    #[test]
+    fn changed_and_stray_rules_are_reported() {
+        let policy = sample();
+        let mut live = live(LIST_TABLE);
+        live.iter_mut().find(|r| r.name == "ssh from lan").unwrap().local_ports = vec![PortRange { start: 2222, end: 2222 }];
+        live.retain(|r| r.name != "web v6 only");
+        live.push(FirewallRule::new("stray", RuleAction::Allow, RuleDirection::Inbound));
+        let drift = diff_rules(&policy, &live);
+        assert_eq!(drift.len(), 3, "{:?}", drift);
+        assert!(drift.contains(&RuleDrift::Missing { rule: "web v6 only".to_string() }));
+        assert!(drift.contains(&RuleDrift::Unexpected { rule: "stray".to_string() }));
+        assert!(matches!(&drift[0], RuleDrift::Changed { rule, changes } if rule == "ssh from lan" && changes[0].field == "local_ports"));
+    }
+
+    #[test]
+    fn any_family_with_v4_addresses_matches_ipv4_live_rule() {
+        let expected = FirewallRule { remote_addresses: vec!["192.0.2.1".to_string()], ..FirewallRule::new("r", RuleAction::Allow, RuleDirection::Inbound) };
+        let live = FirewallRule { family: AddressFamily::Ipv4, ..expected.clone() };
+        assert!(diff_rules(&policy(vec![expected.clone()]), &[live]).is_empty());
+        let widened = FirewallRule { remote_addresses: Vec::new(), ..expected.clone() };
+        assert!(!diff_rules(&policy(vec![expected]), &[widened]).is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::models::{FirewallPolicy, FirewallRule, FirewallStatus};
+use crate::models::{EmergencyMode, FirewallPolicy, FirewallRule, FirewallStatus, NatRule, ProfileType, RuleStats};
 use async_trait::async_trait;
 use std::fmt;
 
@@ -23,12 +23,30 @@ pub trait FirewallEngine: Send + Sync {
     /// Enables or disables the OS firewall across all profiles
     async fn set_enabled(&self, enabled: bool) -> EngineResult<()>;
 
+    /// Enables or disables the firewall for one profile; `ProfileType::All` targets every profile
+    async fn set_profile_enabled(&self, profile: ProfileType, enabled: bool) -> EngineResult<()>;
+
     /// Enables or disables outbound airplane/isolation mode shield
     async fn set_outbound_block(&self, blocked: bool) -> EngineResult<()>;
 
+    /// Engages an emergency mode on top of the active policy, replacing any engaged one; `None` releases it
+    async fn set_emergency(&self, mode: Option<&EmergencyMode>) -> EngineResult<()>;
+
     /// Lists active OS firewall rules
     async fn list_rules(&self) -> EngineResult<Vec<FirewallRule>>;
 
+    /// Returns packet/byte hit counters for each installed rule
+    async fn rule_stats(&self) -> EngineResult<Vec<RuleStats>>;
+
+    /// Lists installed NAT / port forwarding rules
+    async fn list_nat_rules(&self) -> EngineResult<Vec<NatRule>>;
+
+    /// Installs a single NAT rule alongside the active policy
+    async fn add_nat_rule(&self, rule: &NatRule) -> EngineResult<()>;
+
+    /// Removes the NAT rule with the given name
+    async fn remove_nat_rule(&self, name: &str) -> EngineResult<()>;
+
     /// Applies a declarative policy configuration
     async fn apply_policy(&self, policy: &FirewallPolicy) -> EngineResult<()>;
 }
@@ -0,0 +1,1097 @@
+use crate::models::{
+    cidr_family, AddressFamily, FirewallPolicy, FirewallRule, NatKind, NatRule, PortRange, ProfileType, Protocol,
+    RuleAction, RuleDirection,
+};
+use std::fmt::Write;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// nftables table owned by cyberwall; nothing outside it is ever touched
+pub const NFT_TABLE: &str = "cyberwall";
+pub const NFT_FAMILY: &str = "inet";
+pub const NDP_COMMENT: &str = "cyberwall: ICMPv6 neighbor discovery";
+/// Packet mark the emergency table puts on management and remediation traffic
+pub const EMERGENCY_MARK: u32 = 0x4357_0001;
+pub const EMERGENCY_COMMENT: &str = "cyberwall: emergency management channel";
+/// NFLOG group that Block rules log to unless configured otherwise
+pub const DEFAULT_NFLOG_GROUP: u16 = 100;
+pub const NAT_CHAINS: [(&str, &str); 2] = [("prerouting", "dstnat"), ("postrouting", "srcnat")];
+/// Regular chains for interface zones: bypass chains run before the policy, default chains after it
+pub const ZONE_CHAINS: [&str; 4] = ["zone_bypass_input", "zone_bypass_output", "zone_input", "zone_output"];
+/// Interface name sets holding the live members of each profile's zone
+pub const ZONE_SETS: [(ProfileType, &str); 3] = [
+    (ProfileType::Domain, "zone_domain"),
+    (ProfileType::Private, "zone_private"),
+    (ProfileType::Public, "zone_public"),
+];
+
+const NDP_TYPES: &str = "nd-router-solicit, nd-router-advert, nd-neighbor-solicit, nd-neighbor-advert, nd-redirect";
+const IP6TABLES_NDP_TYPES: [&str; 5] = [
+    "router-solicitation",
+    "router-advertisement",
+    "neighbour-solicitation",
+    "neighbour-advertisement",
+    "redirect",
+];
+const COMMENT_MAX: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// `nft -f` script
+    Nft,
+    /// `iptables-restore` input (IPv4 rules)
+    Iptables,
+    /// `ip6tables-restore` input (IPv6 rules)
+    Ip6tables,
+    /// `netsh advfirewall` batch script
+    Netsh,
+    /// PowerShell `New-NetFirewallRule` script
+    PowerShell,
+    /// Human-readable Markdown report
+    Markdown,
+    /// Human-readable HTML report
+    Html,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "nft" | "nftables" => Ok(ExportFormat::Nft),
+            "iptables" | "iptables-restore" => Ok(ExportFormat::Iptables),
+            "ip6tables" | "ip6tables-restore" => Ok(ExportFormat::Ip6tables),
+            "netsh" => Ok(ExportFormat::Netsh),
+            "powershell" | "ps1" => Ok(ExportFormat::PowerShell),
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "html" => Ok(ExportFormat::Html),
+            other => Err(format!(
+                "Unknown export format '{}' (expected nft, iptables, ip6tables, netsh, powershell, markdown or html)",
+                other
+            )),
+        }
+    }
+}
+
+/// Renders a policy for another tool or for humans
+pub fn export_policy(policy: &FirewallPolicy, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Nft => render_nft(policy, NftOptions::portable()),
+        ExportFormat::Iptables => render_iptables(policy, AddressFamily::Ipv4),
+        ExportFormat::Ip6tables => render_iptables(policy, AddressFamily::Ipv6),
+        ExportFormat::Netsh => render_netsh(policy),
+        ExportFormat::PowerShell => render_powershell(policy),
+        ExportFormat::Markdown => render_markdown(policy),
+        ExportFormat::Html => render_html(policy),
+    }
+}
+
+fn header_comment(prefix: &str, policy: &FirewallPolicy) -> String {
+    format!("{} Generated by cyberwall from policy '{}' version {}\n", prefix, policy.name, policy.version)
+}
+
+pub fn chain_for(direction: RuleDirection) -> &'static str {
+    match direction {
+        RuleDirection::Inbound => "input",
+        RuleDirection::Outbound => "output",
+    }
+}
+
+/// Addresses of one rule grouped per IP family; a rule with v4 and v6 peers becomes two variants
+struct FamilyVariant {
+    family: AddressFamily,
+    local: Vec<String>,
+    remote: Vec<String>,
+}
+
+fn family_variants(rule: &FirewallRule) -> Vec<FamilyVariant> {
+    if rule.local_addresses.is_empty() && rule.remote_addresses.is_empty() {
+        return vec![FamilyVariant { family: rule.family, local: Vec::new(), remote: Vec::new() }];
+    }
+    let mut variants = Vec::new();
+    for family in [AddressFamily::Ipv4, AddressFamily::Ipv6] {
+        if !rule.family.covers(family) {
+            continue;
+        }
+        let of_family = |addrs: &[String]| -> Vec<String> {
+            addrs.iter().filter(|a| cidr_family(a).ok() == Some(family)).cloned().collect()
+        };
+        let local = of_family(&rule.local_addresses);
+        let remote = of_family(&rule.remote_addresses);
+        if (local.is_empty() && !rule.local_addresses.is_empty()) || (remote.is_empty() && !rule.remote_addresses.is_empty()) {
+            continue;
+        }
+        variants.push(FamilyVariant { family, local, remote });
+    }
+    variants
+}
+
+// ---------------------------------------------------------------------------
+// nftables
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy)]
+pub struct NftOptions {
+    /// Emit the `last` statement (kernel 6.4+) so rules report when they were last hit
+    pub track_last_hit: bool,
+    /// NFLOG group that Block rules send dropped packets to
+    pub nflog_group: Option<u16>,
+    /// Declare the zone sets and chains that interface zones are filled into, and scope profile rules to them
+    pub zone_hooks: bool,
+    /// Accept packets carrying `EMERGENCY_MARK` ahead of every policy rule
+    pub emergency_bypass: bool,
+}
+
+impl NftOptions {
+    /// Options for scripts handed to other hosts: no kernel-version, collector or zone assumptions
+    pub fn portable() -> Self {
+        Self { track_last_hit: false, nflog_group: None, zone_hooks: false, emergency_bypass: false }
+    }
+}
+
+impl Default for NftOptions {
+    fn default() -> Self {
+        Self {
+            track_last_hit: true,
+            nflog_group: Some(DEFAULT_NFLOG_GROUP),
+            zone_hooks: true,
+            emergency_bypass: true,
+        }
+    }
+}
+
+pub fn iface_field(direction: RuleDirection) -> &'static str {
+    match direction {
+        RuleDirection::Inbound => "iifname",
+        RuleDirection::Outbound => "oifname",
+    }
+}
+
+/// Returns the interface set of a profile's zone; `All` has none because it matches every interface
+pub fn zone_set_for(profile: ProfileType) -> Option<&'static str> {
+    ZONE_SETS.iter().find(|(p, _)| *p == profile).map(|(_, set)| *set)
+}
+
+/// Returns the (bypass, default) zone chains for a direction
+pub fn zone_chains_for(direction: RuleDirection) -> (&'static str, &'static str) {
+    match direction {
+        RuleDirection::Inbound => (ZONE_CHAINS[0], ZONE_CHAINS[2]),
+        RuleDirection::Outbound => (ZONE_CHAINS[1], ZONE_CHAINS[3]),
+    }
+}
+
+pub fn nat_chain_for(kind: NatKind) -> &'static str {
+    match kind {
+        NatKind::Dnat | NatKind::Redirect => "prerouting",
+        NatKind::Snat | NatKind::Masquerade => "postrouting",
+    }
+}
+
+fn ip_keyword(addr: &str) -> &'static str {
+    if addr.contains(':') {
+        "ip6"
+    } else {
+        "ip"
+    }
+}
+
+fn family_match(family: AddressFamily) -> Option<&'static str> {
+    match family {
+        AddressFamily::Any => None,
+        AddressFamily::Ipv4 => Some("meta nfproto ipv4"),
+        AddressFamily::Ipv6 => Some("meta nfproto ipv6"),
+    }
+}
+
+fn nft_verdict(action: RuleAction) -> &'static str {
+    match action {
+        RuleAction::Allow => "accept",
+        RuleAction::Block => "drop",
+    }
+}
+
+pub fn quote_string(name: &str) -> String {
+    let cleaned: String = name.chars().filter(|c| *c != '"' && !c.is_control()).take(COMMENT_MAX).collect();
+    format!("\"{}\"", cleaned)
+}
+
+fn nft_set<T: ToString>(items: &[T]) -> String {
+    match items {
+        [single] => single.to_string(),
+        many => format!("{{ {} }}", many.iter().map(T::to_string).collect::<Vec<_>>().join(", ")),
+    }
+}
+
+/// Builds the match expressions of a rule, one list per address family variant
+fn nft_rule_matches(rule: &FirewallRule) -> Vec<Vec<String>> {
+    let (local_field, remote_field, local_port_field, remote_port_field) = match rule.direction {
+        RuleDirection::Inbound => ("daddr", "saddr", "dport", "sport"),
+        RuleDirection::Outbound => ("saddr", "daddr", "sport", "dport"),
+    };
+
+    let mut l4 = Vec::new();
+    let has_ports = !rule.local_ports.is_empty() || !rule.remote_ports.is_empty();
+    let port_proto = match rule.protocol {
+        Some(p @ (Protocol::Tcp | Protocol::Udp)) => Some(p.to_string()),
+        Some(p) => {
+            l4.push(format!("meta l4proto {}", p));
+            None
+        }
+        None if has_ports => {
+            l4.push("meta l4proto { tcp, udp }".to_string());
+            Some("th".to_string())
+        }
+        None => None,
+    };
+    if let Some(proto) = port_proto {
+        let ports: [(&str, &[PortRange]); 2] = [(local_port_field, &rule.local_ports), (remote_port_field, &rule.remote_ports)];
+        let port_matches: Vec<String> = ports
+            .iter()
+            .filter(|(_, p)| !p.is_empty())
+            .map(|(field, p)| format!("{} {} {}", proto, field, nft_set(p)))
+            .collect();
+        if port_matches.is_empty() {
+            l4.push(format!("meta l4proto {}", proto));
+        }
+        l4.extend(port_matches);
+    }
+
+    family_variants(rule)
+        .into_iter()
+        .map(|v| {
+            let mut matches = Vec::new();
+            let keyword = if v.family == AddressFamily::Ipv6 { "ip6" } else { "ip" };
+            if v.local.is_empty() && v.remote.is_empty() {
+                matches.extend(family_match(v.family).map(str::to_string));
+            }
+            if !v.local.is_empty() {
+                matches.push(format!("{} {} {}", keyword, local_field, nft_set(&v.local)));
+            }
+            if !v.remote.is_empty() {
+                matches.push(format!("{} {} {}", keyword, remote_field, nft_set(&v.remote)));
+            }
+            matches.extend(l4.iter().cloned());
+            matches
+        })
+        .collect()
+}
+
+fn render_nft_rule(rule: &FirewallRule, opts: NftOptions) -> Vec<String> {
+    let zone_match = match zone_set_for(rule.profile) {
+        Some(set) if opts.zone_hooks => Some(format!("{} @{}", iface_field(rule.direction), set)),
+        Some(_) => return vec![format!("# rule {} omitted: scoped to the {:?} profile", quote_string(&rule.name), rule.profile)],
+        None => None,
+    };
+    let mut stmt = String::from("counter");
+    if opts.track_last_hit {
+        stmt.push_str(" last");
+    }
+    if let (RuleAction::Block, Some(group)) = (rule.action, opts.nflog_group) {
+        let _ = write!(stmt, " log prefix {} group {}", quote_string(&rule.name), group);
+    }
+    nft_rule_matches(rule)
+        .into_iter()
+        .map(|mut parts| {
+            parts.splice(0..0, zone_match.clone());
+            parts.push(stmt.clone());
+            parts.push(nft_verdict(rule.action).to_string());
+            parts.push(format!("comment {}", quote_string(&rule.name)));
+            parts.join(" ")
+        })
+        .collect()
+}
+
+pub fn render_nft_nat_rule(rule: &NatRule) -> String {
+    let mut parts = Vec::new();
+    if let Some(iface) = &rule.in_interface {
+        parts.push(format!("iifname {}", quote_string(iface)));
+    }
+    if let Some(iface) = &rule.out_interface {
+        parts.push(format!("oifname {}", quote_string(iface)));
+    }
+    if let Some(src) = &rule.source {
+        parts.push(format!("{} saddr {}", ip_keyword(src), src));
+    }
+    if let Some(dst) = &rule.destination {
+        parts.push(format!("{} daddr {}", ip_keyword(dst), dst));
+    }
+    match (rule.protocol, rule.destination_port) {
+        (Some(proto), Some(port)) => parts.push(format!("{} dport {}", proto, port)),
+        (Some(proto), None) => parts.push(format!("meta l4proto {}", proto)),
+        _ => {}
+    }
+    parts.push("counter".to_string());
+
+    let target = |addr: IpAddr| match (addr, rule.to_port) {
+        (IpAddr::V4(a), Some(port)) => format!("ip to {}:{}", a, port),
+        (IpAddr::V6(a), Some(port)) => format!("ip6 to [{}]:{}", a, port),
+        (IpAddr::V4(a), None) => format!("ip to {}", a),
+        (IpAddr::V6(a), None) => format!("ip6 to {}", a),
+    };
+    parts.push(match rule.kind {
+        NatKind::Snat => format!("snat {}", rule.to_address.map(target).unwrap_or_default()),
+        NatKind::Dnat => format!("dnat {}", rule.to_address.map(target).unwrap_or_default()),
+        NatKind::Masquerade => match rule.to_port {
+            Some(port) => format!("masquerade to :{}", port),
+            None => "masquerade".to_string(),
+        },
+        NatKind::Redirect => format!("redirect to :{}", rule.to_port.unwrap_or_default()),
+    });
+    parts.push(format!("comment {}", quote_string(&rule.name)));
+    parts.join(" ")
+}
+
+/// Declares the nat chains so single rules can be added to a live table
+pub fn render_nft_nat_chains() -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "table {} {} {{", NFT_FAMILY, NFT_TABLE);
+    for (chain, priority) in NAT_CHAINS {
+        let _ = writeln!(out, "    chain {} {{", chain);
+        let _ = writeln!(out, "        type nat hook {} priority {}; policy accept;", chain, priority);
+        let _ = writeln!(out, "    }}");
+    }
+    let _ = writeln!(out, "}}");
+    out
+}
+
+/// Renders a policy as an atomic `nft -f` script replacing the cyberwall table
+pub fn render_nft(policy: &FirewallPolicy, opts: NftOptions) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "table {} {} {{}}", NFT_FAMILY, NFT_TABLE);
+    let _ = writeln!(out, "delete table {} {}", NFT_FAMILY, NFT_TABLE);
+    let _ = writeln!(out, "table {} {} {{", NFT_FAMILY, NFT_TABLE);
+    if opts.zone_hooks {
+        for (_, set) in ZONE_SETS {
+            let _ = writeln!(out, "    set {} {{", set);
+            let _ = writeln!(out, "        type ifname;");
+            let _ = writeln!(out, "    }}");
+        }
+        for chain in ZONE_CHAINS {
+            let _ = writeln!(out, "    chain {} {{", chain);
+            let _ = writeln!(out, "    }}");
+        }
+    }
+    for direction in [RuleDirection::Inbound, RuleDirection::Outbound] {
+        let chain = chain_for(direction);
+        let _ = writeln!(out, "    chain {} {{", chain);
+        let _ = writeln!(out, "        type filter hook {} priority filter; policy accept;", chain);
+        if opts.emergency_bypass {
+            let _ = writeln!(out, "        meta mark {:#x} counter accept comment {}", EMERGENCY_MARK, quote_string(EMERGENCY_COMMENT));
+        }
+        if opts.zone_hooks {
+            let _ = writeln!(out, "        jump {}", zone_chains_for(direction).0);
+        }
+        if policy.allow_neighbor_discovery {
+            let _ = writeln!(out, "        icmpv6 type {{ {} }} counter accept comment {}", NDP_TYPES, quote_string(NDP_COMMENT));
+        }
+        for rule in policy.rules.iter().filter(|r| r.enabled && r.direction == direction) {
+            for line in render_nft_rule(rule, opts) {
+                let _ = writeln!(out, "        {}", line);
+            }
+        }
+        if opts.zone_hooks {
+            let _ = writeln!(out, "        jump {}", zone_chains_for(direction).1);
+        }
+        let _ = writeln!(out, "    }}");
+    }
+    for (chain, priority) in NAT_CHAINS {
+        let _ = writeln!(out, "    chain {} {{", chain);
+        let _ = writeln!(out, "        type nat hook {} priority {}; policy accept;", chain, priority);
+        for rule in policy.nat_rules.iter().filter(|r| r.enabled && nat_chain_for(r.kind) == chain) {
+            let _ = writeln!(out, "        {}", render_nft_nat_rule(rule));
+        }
+        let _ = writeln!(out, "    }}");
+    }
+    let _ = writeln!(out, "}}");
+    out
+}
+
+// ---------------------------------------------------------------------------
+// iptables-restore / ip6tables-restore
+// ---------------------------------------------------------------------------
+
+fn iptables_ports(ports: &[PortRange]) -> String {
+    ports
+        .iter()
+        .map(|p| if p.start == p.end { p.start.to_string() } else { format!("{}:{}", p.start, p.end) })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn iptables_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn render_iptables_rule(rule: &FirewallRule, family: AddressFamily) -> Vec<String> {
+    let chain = match rule.direction {
+        RuleDirection::Inbound => "INPUT",
+        RuleDirection::Outbound => "OUTPUT",
+    };
+    let (local_flag, remote_flag, local_port_flag, remote_port_flag) = match rule.direction {
+        RuleDirection::Inbound => ("-d", "-s", "dport", "sport"),
+        RuleDirection::Outbound => ("-s", "-d", "sport", "dport"),
+    };
+    let target = match rule.action {
+        RuleAction::Allow => "ACCEPT",
+        RuleAction::Block => "DROP",
+    };
+    let protocols: Vec<&str> = match rule.protocol {
+        Some(Protocol::Tcp) => vec!["tcp"],
+        Some(Protocol::Udp) => vec!["udp"],
+        Some(Protocol::Icmp) if family == AddressFamily::Ipv4 => vec!["icmp"],
+        Some(Protocol::IcmpV6) if family == AddressFamily::Ipv6 => vec!["ipv6-icmp"],
+        Some(_) => return Vec::new(),
+        None if !rule.local_ports.is_empty() || !rule.remote_ports.is_empty() => vec!["tcp", "udp"],
+        None => vec![""],
+    };
+
+    let mut lines = Vec::new();
+    for variant in family_variants(rule).into_iter().filter(|v| v.family.covers(family)) {
+        for proto in &protocols {
+            let mut parts = vec![format!("-A {}", chain)];
+            if !variant.local.is_empty() {
+                parts.push(format!("{} {}", local_flag, variant.local.join(",")));
+            }
+            if !variant.remote.is_empty() {
+                parts.push(format!("{} {}", remote_flag, variant.remote.join(",")));
+            }
+            if !proto.is_empty() {
+                parts.push(format!("-p {}", proto));
+            }
+            let ports = [(local_port_flag, &rule.local_ports), (remote_port_flag, &rule.remote_ports)];
+            for (flag, list) in ports.iter().filter(|(_, l)| !l.is_empty()) {
+                if list.len() == 1 {
+                    parts.push(format!("-m {} --{} {}", proto, flag, iptables_ports(list)));
+                } else {
+                    parts.push(format!("-m multiport --{}s {}", flag, iptables_ports(list)));
+                }
+            }
+            parts.push(format!("-m comment --comment {}", iptables_quote(&rule.name)));
+            parts.push(format!("-j {}", target));
+            lines.push(parts.join(" "));
+        }
+    }
+    lines
+}
+
+fn render_iptables_nat_rule(rule: &NatRule, family: AddressFamily) -> Option<String> {
+    let rule_family = rule
+        .to_address
+        .map(|a| if a.is_ipv4() { AddressFamily::Ipv4 } else { AddressFamily::Ipv6 })
+        .or_else(|| rule.source.as_deref().or(rule.destination.as_deref()).and_then(|a| cidr_family(a).ok()))
+        .unwrap_or(AddressFamily::Any);
+    if !rule_family.covers(family) {
+        return None;
+    }
+    let mut parts = vec![format!("-A {}", nat_chain_for(rule.kind).to_uppercase())];
+    if let Some(iface) = &rule.in_interface {
+        parts.push(format!("-i {}", iface));
+    }
+    if let Some(iface) = &rule.out_interface {
+        parts.push(format!("-o {}", iface));
+    }
+    if let Some(src) = &rule.source {
+        parts.push(format!("-s {}", src));
+    }
+    if let Some(dst) = &rule.destination {
+        parts.push(format!("-d {}", dst));
+    }
+    if let Some(proto) = rule.protocol {
+        parts.push(format!("-p {}", proto));
+        if let Some(port) = rule.destination_port {
+            parts.push(format!("-m {} --dport {}", proto, port));
+        }
+    }
+    parts.push(format!("-m comment --comment {}", iptables_quote(&rule.name)));
+    let target = |addr: IpAddr| match (addr, rule.to_port) {
+        (IpAddr::V6(a), Some(port)) => format!("[{}]:{}", a, port),
+        (a, Some(port)) => format!("{}:{}", a, port),
+        (a, None) => a.to_string(),
+    };
+    parts.push(match rule.kind {
+        NatKind::Dnat => format!("-j DNAT --to-destination {}", rule.to_address.map(target).unwrap_or_default()),
+        NatKind::Snat => format!("-j SNAT --to-source {}", rule.to_address.map(target).unwrap_or_default()),
+        NatKind::Masquerade => match rule.to_port {
+            Some(port) => format!("-j MASQUERADE --to-ports {}", port),
+            None => "-j MASQUERADE".to_string(),
+        },
+        NatKind::Redirect => format!("-j REDIRECT --to-ports {}", rule.to_port.unwrap_or_default()),
+    });
+    Some(parts.join(" "))
+}
+
+/// Renders `iptables-restore` (IPv4) or `ip6tables-restore` (IPv6) input
+pub fn render_iptables(policy: &FirewallPolicy, family: AddressFamily) -> String {
+    // Names the tool like iptables-save does, so the import side can tell the families apart
+    let tool = if family == AddressFamily::Ipv6 { "ip6tables" } else { "iptables" };
+    let mut out = format!("# Generated by cyberwall {}-restore script from policy '{}' version {}\n", tool, policy.name, policy.version);
+    let _ = writeln!(out, "*filter");
+    let _ = writeln!(out, ":INPUT ACCEPT [0:0]");
+    let _ = writeln!(out, ":FORWARD ACCEPT [0:0]");
+    let _ = writeln!(out, ":OUTPUT ACCEPT [0:0]");
+    if family == AddressFamily::Ipv6 && policy.allow_neighbor_discovery {
+        for chain in ["INPUT", "OUTPUT"] {
+            for kind in IP6TABLES_NDP_TYPES {
+                let _ = writeln!(
+                    out,
+                    "-A {} -p ipv6-icmp -m icmp6 --icmpv6-type {} -m comment --comment {} -j ACCEPT",
+                    chain,
+                    kind,
+                    iptables_quote(NDP_COMMENT)
+                );
+            }
+        }
+    }
+    for rule in policy.rules.iter().filter(|r| r.enabled) {
+        if rule.application.is_some() {
+            let _ = writeln!(out, "# skipped '{}': application-scoped rules cannot be expressed", rule.name);
+            continue;
+        }
+        if rule.profile != ProfileType::All {
+            let _ = writeln!(out, "# skipped '{}': scoped to the {:?} profile", rule.name, rule.profile);
+            continue;
+        }
+        for line in render_iptables_rule(rule, family) {
+            let _ = writeln!(out, "{}", line);
+        }
+    }
+    let _ = writeln!(out, "COMMIT");
+
+    let nat: Vec<String> = policy
+        .nat_rules
+        .iter()
+        .filter(|r| r.enabled)
+        .filter_map(|r| render_iptables_nat_rule(r, family))
+        .collect();
+    if !nat.is_empty() {
+        let _ = writeln!(out, "*nat");
+        for chain in ["PREROUTING", "INPUT", "OUTPUT", "POSTROUTING"] {
+            let _ = writeln!(out, ":{} ACCEPT [0:0]", chain);
+        }
+        for line in nat {
+            let _ = writeln!(out, "{}", line);
+        }
+        let _ = writeln!(out, "COMMIT");
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Windows: netsh advfirewall and PowerShell NetSecurity
+// ---------------------------------------------------------------------------
+
+/// Flattened Windows Firewall rule settings shared by the netsh and PowerShell renderers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowsRuleSpec {
+    pub name: String,
+    pub enabled: bool,
+    pub inbound: bool,
+    pub allow: bool,
+    /// `any`, `domain`, `private` or `public`
+    pub profile: &'static str,
+    /// `any`, `TCP`, `UDP`, `ICMPv4` or `ICMPv6`
+    pub protocol: &'static str,
+    pub local_addresses: Vec<String>,
+    pub remote_addresses: Vec<String>,
+    pub local_ports: Vec<String>,
+    pub remote_ports: Vec<String>,
+    pub program: Option<String>,
+}
+
+fn profile_keyword(profile: ProfileType) -> &'static str {
+    match profile {
+        ProfileType::All => "any",
+        ProfileType::Domain => "domain",
+        ProfileType::Private => "private",
+        ProfileType::Public => "public",
+    }
+}
+
+/// Maps a cyberwall rule onto Windows Firewall rule settings. Windows rules are dual-stack, so a
+/// family restriction without explicit addresses is expressed as an all-addresses remote range
+pub fn windows_rule_specs(rule: &FirewallRule) -> Vec<WindowsRuleSpec> {
+    let protocols: Vec<&'static str> = match rule.protocol {
+        Some(Protocol::Tcp) => vec!["TCP"],
+        Some(Protocol::Udp) => vec!["UDP"],
+        Some(Protocol::Icmp) => vec!["ICMPv4"],
+        Some(Protocol::IcmpV6) => vec!["ICMPv6"],
+        None if !rule.local_ports.is_empty() || !rule.remote_ports.is_empty() => vec!["TCP", "UDP"],
+        None => vec!["any"],
+    };
+    let remote_addresses = match (rule.family, rule.local_addresses.is_empty() && rule.remote_addresses.is_empty()) {
+        (AddressFamily::Ipv4, true) => vec!["0.0.0.0/0".to_string()],
+        (AddressFamily::Ipv6, true) => vec!["::/0".to_string()],
+        _ => rule.remote_addresses.clone(),
+    };
+    protocols
+        .into_iter()
+        .map(|protocol| WindowsRuleSpec {
+            name: rule.name.clone(),
+            enabled: rule.enabled,
+            inbound: rule.direction == RuleDirection::Inbound,
+            allow: rule.action == RuleAction::Allow,
+            profile: profile_keyword(rule.profile),
+            protocol,
+            local_addresses: rule.local_addresses.clone(),
+            remote_addresses: remote_addresses.clone(),
+            local_ports: rule.local_ports.iter().map(PortRange::to_string).collect(),
+            remote_ports: rule.remote_ports.iter().map(PortRange::to_string).collect(),
+            program: rule.application.clone(),
+        })
+        .collect()
+}
+
+fn or_any(values: &[String]) -> String {
+    if values.is_empty() {
+        "any".to_string()
+    } else {
+        values.join(",")
+    }
+}
+
+/// Renders one `netsh advfirewall firewall add rule` command line
+pub fn netsh_add_command(spec: &WindowsRuleSpec) -> String {
+    let mut cmd = format!(
+        "netsh advfirewall firewall add rule name=\"{}\" dir={} action={} enable={} profile={} protocol={}",
+        spec.name.replace('"', "'"),
+        if spec.inbound { "in" } else { "out" },
+        if spec.allow { "allow" } else { "block" },
+        if spec.enabled { "yes" } else { "no" },
+        spec.profile,
+        spec.protocol
+    );
+    if spec.protocol == "TCP" || spec.protocol == "UDP" {
+        let _ = write!(cmd, " localport={} remoteport={}", or_any(&spec.local_ports), or_any(&spec.remote_ports));
+    }
+    let _ = write!(cmd, " localip={} remoteip={}", or_any(&spec.local_addresses), or_any(&spec.remote_addresses));
+    if let Some(program) = &spec.program {
+        let _ = write!(cmd, " program=\"{}\"", program);
+    }
+    cmd
+}
+
+pub fn render_netsh(policy: &FirewallPolicy) -> String {
+    let mut out = String::from("@echo off\r\n");
+    out.push_str(&header_comment("REM", policy).replace('\n', "\r\n"));
+    for rule in &policy.rules {
+        for spec in windows_rule_specs(rule) {
+            let _ = write!(out, "{}\r\n", netsh_add_command(&spec));
+        }
+    }
+    for rule in &policy.nat_rules {
+        let _ = write!(out, "REM skipped NAT rule '{}': Windows Firewall has no NAT support\r\n", rule.name);
+    }
+    out
+}
+
+fn ps_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn ps_list(values: &[String]) -> String {
+    values.iter().map(|v| ps_quote(v)).collect::<Vec<_>>().join(",")
+}
+
+/// Renders one `New-NetFirewallRule` invocation
+pub fn powershell_command(spec: &WindowsRuleSpec, group: &str) -> String {
+    let mut cmd = format!(
+        "New-NetFirewallRule -DisplayName {} -Group {} -Direction {} -Action {} -Enabled {} -Profile {} -Protocol {}",
+        ps_quote(&spec.name),
+        ps_quote(group),
+        if spec.inbound { "Inbound" } else { "Outbound" },
+        if spec.allow { "Allow" } else { "Block" },
+        if spec.enabled { "True" } else { "False" },
+        if spec.profile == "any" { "Any" } else { spec.profile },
+        if spec.protocol == "any" { "Any" } else { spec.protocol }
+    );
+    if !spec.local_ports.is_empty() {
+        let _ = write!(cmd, " -LocalPort {}", ps_list(&spec.local_ports));
+    }
+    if !spec.remote_ports.is_empty() {
+        let _ = write!(cmd, " -RemotePort {}", ps_list(&spec.remote_ports));
+    }
+    if !spec.local_addresses.is_empty() {
+        let _ = write!(cmd, " -LocalAddress {}", ps_list(&spec.local_addresses));
+    }
+    if !spec.remote_addresses.is_empty() {
+        let _ = write!(cmd, " -RemoteAddress {}", ps_list(&spec.remote_addresses));
+    }
+    if let Some(program) = &spec.program {
+        let _ = write!(cmd, " -Program {}", ps_quote(program));
+    }
+    cmd
+}
+
+pub fn render_powershell(policy: &FirewallPolicy) -> String {
+    let mut out = header_comment("#", policy);
+    out.push_str("#Requires -RunAsAdministrator\n");
+    let group = format!("cyberwall:{}", policy.name);
+    let _ = writeln!(out, "Remove-NetFirewallRule -Group {} -ErrorAction SilentlyContinue", ps_quote(&group));
+    for rule in &policy.rules {
+        for spec in windows_rule_specs(rule) {
+            let _ = writeln!(out, "{} | Out-Null", powershell_command(&spec, &group));
+        }
+    }
+    for rule in &policy.nat_rules {
+        let _ = writeln!(out, "# skipped NAT rule '{}': Windows Firewall has no NAT support", rule.name);
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Human-readable reports
+// ---------------------------------------------------------------------------
+
+const RULE_COLUMNS: [&str; 10] =
+    ["#", "Name", "Enabled", "Direction", "Action", "Profile", "Family", "Protocol", "Local", "Remote"];
+const NAT_COLUMNS: [&str; 6] = ["#", "Name", "Type", "Match", "Translate to", "Enabled"];
+
+fn endpoint(addrs: &[String], ports: &[PortRange]) -> String {
+    let addrs = if addrs.is_empty() { "any".to_string() } else { addrs.join(", ") };
+    if ports.is_empty() {
+        addrs
+    } else {
+        format!("{} port {}", addrs, ports.iter().map(PortRange::to_string).collect::<Vec<_>>().join(", "))
+    }
+}
+
+fn rule_row(idx: usize, rule: &FirewallRule) -> Vec<String> {
+    let mut local = endpoint(&rule.local_addresses, &rule.local_ports);
+    if let Some(app) = &rule.application {
+        local = format!("{} ({})", local, app);
+    }
+    vec![
+        (idx + 1).to_string(),
+        rule.name.clone(),
+        if rule.enabled { "yes" } else { "no" }.to_string(),
+        format!("{:?}", rule.direction),
+        format!("{:?}", rule.action),
+        format!("{:?}", rule.profile),
+        format!("{:?}", rule.family),
+        rule.protocol.map(|p| p.to_string()).unwrap_or_else(|| "any".to_string()),
+        local,
+        endpoint(&rule.remote_addresses, &rule.remote_ports),
+    ]
+}
+
+fn nat_row(idx: usize, rule: &NatRule) -> Vec<String> {
+    let matched = [
+        rule.in_interface.as_ref().map(|i| format!("in {}", i)),
+        rule.out_interface.as_ref().map(|i| format!("out {}", i)),
+        rule.source.as_ref().map(|a| format!("from {}", a)),
+        rule.destination.as_ref().map(|a| format!("to {}", a)),
+        rule.protocol.map(|p| p.to_string()),
+        rule.destination_port.map(|p| format!("port {}", p)),
+    ];
+    let matched: Vec<String> = matched.into_iter().flatten().collect();
+    let target = match (rule.to_address, rule.to_port) {
+        (Some(a), Some(p)) => format!("{} port {}", a, p),
+        (Some(a), None) => a.to_string(),
+        (None, Some(p)) => format!("port {}", p),
+        (None, None) => "outgoing interface".to_string(),
+    };
+    vec![
+        (idx + 1).to_string(),
+        rule.name.clone(),
+        format!("{:?}", rule.kind),
+        if matched.is_empty() { "any".to_string() } else { matched.join(", ") },
+        target,
+        if rule.enabled { "yes" } else { "no" }.to_string(),
+    ]
+}
+
+fn md_escape(value: &str) -> String {
+    value.replace('|', "\\|")
+}
+
+fn md_table(out: &mut String, columns: &[&str], rows: Vec<Vec<String>>) {
+    let _ = writeln!(out, "| {} |", columns.join(" | "));
+    let _ = writeln!(out, "|{}", "---|".repeat(columns.len()));
+    for row in rows {
+        let _ = writeln!(out, "| {} |", row.iter().map(|c| md_escape(c)).collect::<Vec<_>>().join(" | "));
+    }
+}
+
+pub fn render_markdown(policy: &FirewallPolicy) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Firewall policy: {}", policy.name);
+    let _ = writeln!(out);
+    let _ = writeln!(out, "- Version: {}", policy.version);
+    let _ = writeln!(out, "- Rules: {} ({} enabled)", policy.rules.len(), policy.rules.iter().filter(|r| r.enabled).count());
+    let _ = writeln!(out, "- NAT rules: {}", policy.nat_rules.len());
+    let _ = writeln!(
+        out,
+        "- ICMPv6 neighbor discovery: {}",
+        if policy.allow_neighbor_discovery { "always allowed" } else { "governed by rules" }
+    );
+    let _ = writeln!(out);
+    let _ = writeln!(out, "## Filter rules");
+    let _ = writeln!(out);
+    md_table(&mut out, &RULE_COLUMNS, policy.rules.iter().enumerate().map(|(i, r)| rule_row(i, r)).collect());
+    if !policy.nat_rules.is_empty() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "## NAT rules");
+        let _ = writeln!(out);
+        md_table(&mut out, &NAT_COLUMNS, policy.nat_rules.iter().enumerate().map(|(i, r)| nat_row(i, r)).collect());
+    }
+    out
+}
+
+fn html_escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn html_table(out: &mut String, columns: &[&str], rows: Vec<Vec<String>>) {
+    let _ = writeln!(out, "<table>");
+    let _ = writeln!(out, "<tr>{}</tr>", columns.iter().map(|c| format!("<th>{}</th>", c)).collect::<String>());
+    for row in rows {
+        let _ = writeln!(out, "<tr>{}</tr>", row.iter().map(|c| format!("<td>{}</td>", html_escape(c))).collect::<String>());
+    }
+    let _ = writeln!(out, "</table>");
+}
+
+pub fn render_html(policy: &FirewallPolicy) -> String {
+    let title = html_escape(&policy.name);
+    let mut out = String::new();
+    let _ = writeln!(out, "<!DOCTYPE html>");
+    let _ = writeln!(out, "<html>");
+    let _ = writeln!(out, "<head>");
+    let _ = writeln!(out, "<meta charset=\"utf-8\">");
+    let _ = writeln!(out, "<title>Firewall policy: {}</title>", title);
+    let _ = writeln!(
+        out,
+        "<style>body{{font-family:sans-serif}}table{{border-collapse:collapse}}th,td{{border:1px solid #999;padding:4px 8px;text-align:left}}th{{background:#eee}}</style>"
+    );
+    let _ = writeln!(out, "</head>");
+    let _ = writeln!(out, "<body>");
+    let _ = writeln!(out, "<h1>Firewall policy: {}</h1>", title);
+    let _ = writeln!(out, "<ul>");
+    let _ = writeln!(out, "<li>Version: {}</li>", html_escape(&policy.version));
+    let _ = writeln!(out, "<li>Rules: {} ({} enabled)</li>", policy.rules.len(), policy.rules.iter().filter(|r| r.enabled).count());
+    let _ = writeln!(out, "<li>NAT rules: {}</li>", policy.nat_rules.len());
+    let _ = writeln!(
+        out,
+        "<li>ICMPv6 neighbor discovery: {}</li>",
+        if policy.allow_neighbor_discovery { "always allowed" } else { "governed by rules" }
+    );
+    let _ = writeln!(out, "</ul>");
+    let _ = writeln!(out, "<h2>Filter rules</h2>");
+    html_table(&mut out, &RULE_COLUMNS, policy.rules.iter().enumerate().map(|(i, r)| rule_row(i, r)).collect());
+    if !policy.nat_rules.is_empty() {
+        let _ = writeln!(out, "<h2>NAT rules</h2>");
+        html_table(&mut out, &NAT_COLUMNS, policy.nat_rules.iter().enumerate().map(|(i, r)| nat_row(i, r)).collect());
+    }
+    let _ = writeln!(out, "</body>");
+    let _ = writeln!(out, "</html>");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn port(p: u16) -> PortRange {
+        PortRange { start: p, end: p }
+    }
+
+    /// Policy exercising every rule shape the exporters handle differently
+    fn golden_policy() -> FirewallPolicy {
+        FirewallPolicy {
+            name: "golden".to_string(),
+            version: "7".to_string(),
+            allow_neighbor_discovery: true,
+            rules: vec![
+                FirewallRule {
+                    protocol: Some(Protocol::Tcp),
+                    local_ports: vec![port(22)],
+                    remote_addresses: vec!["10.0.0.0/8".to_string(), "fd00::/8".to_string()],
+                    ..FirewallRule::new("ssh from management", RuleAction::Allow, RuleDirection::Inbound)
+                },
+                FirewallRule {
+                    protocol: Some(Protocol::Tcp),
+                    local_ports: vec![port(80), port(443), PortRange { start: 8000, end: 8100 }],
+                    ..FirewallRule::new("web", RuleAction::Allow, RuleDirection::Inbound)
+                },
+                FirewallRule {
+                    remote_ports: vec![port(53)],
+                    remote_addresses: vec!["192.0.2.53".to_string(), "2001:db8::53".to_string()],
+                    ..FirewallRule::new("dns any transport", RuleAction::Allow, RuleDirection::Outbound)
+                },
+                FirewallRule {
+                    family: AddressFamily::Ipv4,
+                    protocol: Some(Protocol::Icmp),
+                    ..FirewallRule::new("ping v4", RuleAction::Allow, RuleDirection::Inbound)
+                },
+                FirewallRule {
+                    protocol: Some(Protocol::IcmpV6),
+                    ..FirewallRule::new("ping v6", RuleAction::Allow, RuleDirection::Inbound)
+                },
+                FirewallRule {
+                    profile: ProfileType::Public,
+                    protocol: Some(Protocol::Tcp),
+                    local_ports: vec![port(445)],
+                    ..FirewallRule::new("no smb on public", RuleAction::Block, RuleDirection::Inbound)
+                },
+                FirewallRule {
+                    family: AddressFamily::Ipv6,
+                    local_addresses: vec!["2001:db8::10".to_string()],
+                    remote_addresses: vec!["2001:db8:bad::/48".to_string()],
+                    ..FirewallRule::new("block bad v6 net", RuleAction::Block, RuleDirection::Outbound)
+                },
+                FirewallRule {
+                    protocol: Some(Protocol::Udp),
+                    remote_ports: vec![port(123)],
+                    application: Some("C:\\Windows\\System32\\w32tm.exe".to_string()),
+                    ..FirewallRule::new("ntp <client> & \"sync\"", RuleAction::Allow, RuleDirection::Outbound)
+                },
+                FirewallRule { enabled: false, ..FirewallRule::new("disabled telnet", RuleAction::Block, RuleDirection::Inbound) },
+            ],
+            nat_rules: vec![
+                NatRule {
+                    name: "forward web".to_string(),
+                    enabled: true,
+                    kind: NatKind::Dnat,
+                    protocol: Some(Protocol::Tcp),
+                    in_interface: Some("eth0".to_string()),
+                    out_interface: None,
+                    source: None,
+                    destination: None,
+                    destination_port: Some(8080),
+                    to_address: Some("192.168.1.10".parse().unwrap()),
+                    to_port: Some(80),
+                },
+                NatRule {
+                    name: "masquerade lan".to_string(),
+                    enabled: true,
+                    kind: NatKind::Masquerade,
+                    protocol: None,
+                    in_interface: None,
+                    out_interface: Some("eth0".to_string()),
+                    source: Some("192.168.1.0/24".to_string()),
+                    destination: None,
+                    destination_port: None,
+                    to_address: None,
+                    to_port: None,
+                },
+            ],
+        }
+    }
+
+    /// Compares with `testdata/export/<name>`; set CYBERWALL_UPDATE_GOLDEN=1 to rewrite the files after an intended change
+    fn assert_golden(name: &str, actual: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("testdata/export").join(name);
+        if std::env::var_os("CYBERWALL_UPDATE_GOLDEN").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        assert_eq!(actual, expected, "{} differs from the rendered output", path.display());
+    }
+
+    #[test]
+    fn golden_exports() {
+        let policy = golden_policy();
+        for (format, file) in [
+            (ExportFormat::Nft, "golden.nft"),
+            (ExportFormat::Iptables, "golden.iptables"),
+            (ExportFormat::Ip6tables, "golden.ip6tables"),
+            (ExportFormat::Netsh, "golden.netsh.cmd"),
+            (ExportFormat::PowerShell, "golden.ps1"),
+            (ExportFormat::Markdown, "golden.md"),
+            (ExportFormat::Html, "golden.html"),
+        ] {
+            assert_golden(file, &export_policy(&policy, format));
+        }
+    }
+
+    #[test]
+    fn golden_live_nft_script() {
+        assert_golden("golden.live.nft", &render_nft(&golden_policy(), NftOptions::default()));
+    }
+
+    /// The same policy written for one family: every rule shape, with addresses taken from `addrs`
+    fn family_policy(family: AddressFamily, addrs: [&str; 3]) -> FirewallPolicy {
+        let rules = vec![
+            FirewallRule {
+                family,
+                protocol: Some(Protocol::Tcp),
+                local_ports: vec![port(22)],
+                remote_addresses: vec![addrs[0].to_string()],
+                ..FirewallRule::new("ssh", RuleAction::Allow, RuleDirection::Inbound)
+            },
+            FirewallRule {
+                family,
+                remote_ports: vec![port(53), PortRange { start: 5353, end: 5354 }],
+                remote_addresses: vec![addrs[1].to_string(), addrs[2].to_string()],
+                ..FirewallRule::new("dns", RuleAction::Allow, RuleDirection::Outbound)
+            },
+            FirewallRule {
+                family,
+                protocol: Some(Protocol::Udp),
+                local_addresses: vec![addrs[1].to_string()],
+                ..FirewallRule::new("udp from host", RuleAction::Block, RuleDirection::Outbound)
+            },
+            FirewallRule { family, ..FirewallRule::new("family only", RuleAction::Block, RuleDirection::Inbound) },
+        ];
+        FirewallPolicy { name: "parity".to_string(), version: "1".to_string(), rules, nat_rules: Vec::new(), allow_neighbor_discovery: false }
+    }
+
+    const V4: [&str; 3] = ["10.0.0.0/8", "192.0.2.1", "192.0.2.2"];
+    const V6: [&str; 3] = ["fd00::/8", "2001:db8::1", "2001:db8::2"];
+
+    /// Rewrites v4 output into what the v6 renderers must produce for the mirrored policy
+    fn as_v6(v4: &str) -> String {
+        let mut out = v4.replacen("cyberwall iptables", "cyberwall ip6tables", 1).replace("ip saddr", "ip6 saddr").replace("ip daddr", "ip6 daddr").replace("nfproto ipv4", "nfproto ipv6");
+        for (a, b) in V4.iter().zip(V6) {
+            out = out.replace(a, b);
+        }
+        out
+    }
+
+    #[test]
+    fn nft_output_has_v4_v6_parity() {
+        for opts in [NftOptions::default(), NftOptions::portable()] {
+            let v4 = render_nft(&family_policy(AddressFamily::Ipv4, V4), opts);
+            let v6 = render_nft(&family_policy(AddressFamily::Ipv6, V6), opts);
+            assert!(v4.contains("ip saddr 10.0.0.0/8") && v4.contains("meta nfproto ipv4"));
+            assert_eq!(v6, as_v6(&v4));
+        }
+    }
+
+    #[test]
+    fn iptables_output_has_v4_v6_parity() {
+        let v4 = render_iptables(&family_policy(AddressFamily::Ipv4, V4), AddressFamily::Ipv4);
+        let v6 = render_iptables(&family_policy(AddressFamily::Ipv6, V6), AddressFamily::Ipv6);
+        assert_eq!(v4.lines().filter(|l| l.starts_with("-A")).count(), 5);
+        assert_eq!(v6, as_v6(&v4));
+        // Each family's rules are left out of the other family's script
+        let other = render_iptables(&family_policy(AddressFamily::Ipv4, V4), AddressFamily::Ipv6);
+        assert!(!other.lines().any(|l| l.starts_with("-A")), "{}", other);
+    }
+
+    #[test]
+    fn dual_stack_rule_renders_once_per_family() {
+        let mut policy = family_policy(AddressFamily::Any, V4);
+        policy.rules[0].remote_addresses.push(V6[0].to_string());
+        let nft = render_nft(&policy, NftOptions::portable());
+        let ssh: Vec<&str> = nft.lines().filter(|l| l.contains("comment \"ssh\"")).collect();
+        assert_eq!(ssh, vec![
+            "        ip saddr 10.0.0.0/8 tcp dport 22 counter accept comment \"ssh\"",
+            "        ip6 saddr fd00::/8 tcp dport 22 counter accept comment \"ssh\"",
+        ]);
+        let v4 = render_iptables(&policy, AddressFamily::Ipv4);
+        let v6 = render_iptables(&policy, AddressFamily::Ipv6);
+        assert!(v4.contains("-A INPUT -s 10.0.0.0/8 -p tcp") && !v4.contains("fd00::/8"));
+        assert!(v6.contains("-A INPUT -s fd00::/8 -p tcp") && !v6.contains("10.0.0.0/8"));
+    }
+
+    #[test]
+    fn neighbor_discovery_is_only_added_to_v6() {
+        let mut policy = family_policy(AddressFamily::Ipv4, V4);
+        policy.allow_neighbor_discovery = true;
+        assert!(!render_iptables(&policy, AddressFamily::Ipv4).contains("icmp6"));
+        assert!(render_iptables(&policy, AddressFamily::Ipv6).contains("--icmpv6-type neighbour-solicitation"));
+    }
+}
@@ -0,0 +1,72 @@
+use crate::engine::{EngineError, EngineResult};
+use crate::models::FirewallPolicy;
+use crate::signing::{self, Signature, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+/// Shared secret agents send to the fleet server as a bearer token
+pub const FLEET_TOKEN_ENV: &str = "AEGIS_FLEET_TOKEN";
+pub const DEFAULT_FLEET_ADDR: &str = "127.0.0.1:5190";
+
+/// One published policy version for a host group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Release {
+    pub group: String,
+    /// Increases by one with every publish to the group
+    pub version: u64,
+    pub published_at: u64,
+    pub policy: FirewallPolicy,
+}
+
+impl Release {
+    fn signed_bytes(&self) -> EngineResult<Vec<u8>> {
+        serde_json::to_vec(self).map_err(|e| EngineError(format!("Failed to encode release: {}", e)))
+    }
+
+    pub fn sign(self, key: &SigningKey) -> EngineResult<SignedRelease> {
+        let signature = signing::sign(key, &self.signed_bytes()?);
+        Ok(SignedRelease { release: self, signature })
+    }
+}
+
+/// Release as served to agents; the signature covers group, version and policy together
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignedRelease {
+    pub release: Release,
+    pub signature: Signature,
+}
+
+impl SignedRelease {
+    /// Checks the signature and that the release is for `group`
+    pub fn verify(&self, key: &VerifyingKey, group: &str) -> EngineResult<&Release> {
+        signing::verify(key, &self.release.signed_bytes()?, &self.signature)?;
+        if self.release.group != group {
+            return Err(EngineError(format!("Release is for group '{}', not '{}'", self.release.group, group)));
+        }
+        Ok(&self.release)
+    }
+}
+
+/// What an agent sends back after each poll
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComplianceReport {
+    pub host: String,
+    pub group: String,
+    /// Release version currently applied, 0 before the first one
+    pub applied_version: u64,
+    /// True when the live rules match the applied policy
+    pub compliant: bool,
+    /// Human-readable differences between live rules and the policy
+    #[serde(default)]
+    pub drift: Vec<String>,
+    pub error: Option<String>,
+    pub reported_at: u64,
+}
+
+/// Latest report per host as kept by the fleet server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HostStatus {
+    #[serde(flatten)]
+    pub report: ComplianceReport,
+    /// Newest release published for the host's group
+    pub latest_version: u64,
+}
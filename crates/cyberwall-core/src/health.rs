@@ -0,0 +1,106 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HealthState {
+    Healthy,
+    /// Running, but not doing its job fully (e.g. firewall disabled)
+    Degraded,
+    Down,
+    /// The probe could not determine the state on this platform
+    Unknown,
+}
+
+/// Result of one subsystem health probe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleHealth {
+    pub module: String,
+    pub state: HealthState,
+    pub detail: String,
+    /// Most recent probe failure, kept after the module recovers
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f64>,
+    /// Unix timestamp (seconds) of the probe
+    pub checked_at: u64,
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+impl ModuleHealth {
+    pub fn new(module: &str, state: HealthState, detail: impl Into<String>) -> Self {
+        Self {
+            module: module.to_string(),
+            state,
+            detail: detail.into(),
+            last_error: None,
+            metrics: BTreeMap::new(),
+            checked_at: unix_now(),
+        }
+    }
+
+    pub fn metric(mut self, name: &str, value: f64) -> Self {
+        self.metrics.insert(name.to_string(), value);
+        self
+    }
+}
+
+/// Probe reporting the live state of one subsystem
+#[async_trait]
+pub trait HealthCheck: Send + Sync {
+    fn module(&self) -> &'static str;
+
+    async fn check(&self) -> ModuleHealth;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub generated_at: u64,
+    pub modules: Vec<ModuleHealth>,
+}
+
+impl HealthReport {
+    /// True only when every module is healthy
+    pub fn healthy(&self) -> bool {
+        self.modules.iter().all(|m| m.state == HealthState::Healthy)
+    }
+}
+
+/// Runs registered probes and remembers each module's last error across reports
+#[derive(Default)]
+pub struct HealthRegistry {
+    checks: Vec<Box<dyn HealthCheck>>,
+    last_errors: Mutex<HashMap<&'static str, String>>,
+}
+
+impl HealthRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(mut self, check: impl HealthCheck + 'static) -> Self {
+        self.checks.push(Box::new(check));
+        self
+    }
+
+    pub async fn report(&self) -> HealthReport {
+        let mut modules = Vec::with_capacity(self.checks.len());
+        for check in &self.checks {
+            let mut health = check.check().await;
+            let mut last_errors = self.last_errors.lock().unwrap_or_else(|e| e.into_inner());
+            match health.last_error.clone() {
+                Some(error) => {
+                    last_errors.insert(check.module(), error);
+                }
+                None => health.last_error = last_errors.get(check.module()).cloned(),
+            }
+            modules.push(health);
+        }
+        HealthReport { generated_at: unix_now(), modules }
+    }
+}
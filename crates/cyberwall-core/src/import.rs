@@ -0,0 +1,957 @@
+use crate::engine::{EngineError, EngineResult};
+use crate::export::ZONE_SETS;
+use crate::models::{
+    cidr_family, AddressFamily, FirewallPolicy, FirewallRule, NatKind, NatRule, PortRange, ProfileType, Protocol,
+    RuleAction, RuleDirection,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// `iptables-save` / `ip6tables-save` output
+    IptablesSave,
+    /// `nft -j list ruleset` output
+    NftJson,
+    /// `netsh advfirewall firewall show rule name=all` output
+    Netsh,
+}
+
+impl FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "iptables" | "iptables-save" | "ip6tables-save" => Ok(ImportFormat::IptablesSave),
+            "nft" | "nft-json" | "nftables" => Ok(ImportFormat::NftJson),
+            "netsh" => Ok(ImportFormat::Netsh),
+            other => Err(format!("Unknown import format '{}' (expected iptables-save, nft-json or netsh)", other)),
+        }
+    }
+}
+
+/// A source rule that has no faithful cyberwall representation
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedRule {
+    pub source: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportReport {
+    pub policy: FirewallPolicy,
+    pub skipped: Vec<SkippedRule>,
+}
+
+#[derive(Default)]
+struct Imported {
+    rules: Vec<FirewallRule>,
+    nat_rules: Vec<NatRule>,
+    skipped: Vec<SkippedRule>,
+}
+
+impl Imported {
+    fn skip(&mut self, source: impl Into<String>, reason: impl Into<String>) {
+        self.skipped.push(SkippedRule { source: source.into(), reason: reason.into() });
+    }
+
+    fn push_rule(&mut self, source: &str, rule: FirewallRule) {
+        match rule.validate() {
+            Ok(()) => self.rules.push(rule),
+            Err(e) => self.skip(source, e),
+        }
+    }
+
+    fn push_nat(&mut self, source: &str, rule: NatRule) {
+        match rule.validate() {
+            Ok(()) => self.nat_rules.push(rule),
+            Err(e) => self.skip(source, e),
+        }
+    }
+}
+
+/// Translates a foreign firewall dump into a policy, listing every rule that had to be left out
+pub fn import_policy(format: ImportFormat, input: &str, name: &str) -> EngineResult<ImportReport> {
+    let imported = match format {
+        ImportFormat::IptablesSave => import_iptables(input)?,
+        ImportFormat::NftJson => import_nft_json(input)?,
+        ImportFormat::Netsh => import_netsh(input),
+    };
+    Ok(ImportReport {
+        policy: FirewallPolicy {
+            name: name.to_string(),
+            version: "1".to_string(),
+            rules: imported.rules,
+            nat_rules: imported.nat_rules,
+            allow_neighbor_discovery: true,
+        },
+        skipped: imported.skipped,
+    })
+}
+
+fn family_of_all(addrs: &[String], default: AddressFamily) -> AddressFamily {
+    let mut families = addrs.iter().filter_map(|a| cidr_family(a).ok());
+    match families.next() {
+        Some(first) if families.all(|f| f == first) && default.covers(first) => first,
+        _ => default,
+    }
+}
+
+fn parse_nat_target(value: &str) -> Option<(IpAddr, Option<u16>)> {
+    if let Some(rest) = value.strip_prefix('[') {
+        let (addr, port) = rest.split_once(']')?;
+        let port = port.strip_prefix(':').map(str::parse).transpose().ok()?;
+        return Some((addr.parse().ok()?, port));
+    }
+    if let Ok(addr) = value.parse() {
+        return Some((addr, None));
+    }
+    let (addr, port) = value.rsplit_once(':')?;
+    Some((addr.parse().ok()?, Some(port.parse().ok()?)))
+}
+
+// ---------------------------------------------------------------------------
+// iptables-save
+// ---------------------------------------------------------------------------
+
+fn tokenize(line: &str) -> EngineResult<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut has_token = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_token = true;
+            }
+            '\\' if in_quotes => current.extend(chars.next()),
+            c if c.is_whitespace() && !in_quotes => {
+                if has_token {
+                    tokens.push(std::mem::take(&mut current));
+                    has_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                has_token = true;
+            }
+        }
+    }
+    if in_quotes {
+        return Err(EngineError(format!("Unterminated quote in '{}'", line)));
+    }
+    if has_token {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+#[derive(Default)]
+struct IptablesRule {
+    protocol: Option<String>,
+    sources: Vec<String>,
+    destinations: Vec<String>,
+    in_interface: Option<String>,
+    out_interface: Option<String>,
+    sports: Vec<PortRange>,
+    dports: Vec<PortRange>,
+    comment: Option<String>,
+    target: Option<String>,
+    to_destination: Option<String>,
+    to_source: Option<String>,
+    to_ports: Option<String>,
+    unsupported: Vec<String>,
+}
+
+const KNOWN_MATCH_MODULES: [&str; 6] = ["tcp", "udp", "multiport", "comment", "icmp", "icmp6"];
+
+fn parse_iptables_options(tokens: &[String]) -> IptablesRule {
+    let mut rule = IptablesRule::default();
+    let mut i = 0;
+    while i < tokens.len() {
+        let opt = tokens[i].as_str();
+        let value = tokens.get(i + 1).cloned();
+        let mut consumed = 2;
+        let ports = |v: &Option<String>| -> Result<Vec<PortRange>, String> {
+            v.as_deref().unwrap_or_default().split(',').map(str::parse).collect()
+        };
+        match opt {
+            "!" => {
+                rule.unsupported.push("negated match (!)".to_string());
+                consumed = 1;
+            }
+            "-p" | "--protocol" => rule.protocol = value,
+            "-s" | "--source" => rule.sources = value.unwrap_or_default().split(',').map(str::to_string).collect(),
+            "-d" | "--destination" => {
+                rule.destinations = value.unwrap_or_default().split(',').map(str::to_string).collect()
+            }
+            "-i" | "--in-interface" => rule.in_interface = value,
+            "-o" | "--out-interface" => rule.out_interface = value,
+            "--sport" | "--source-port" | "--sports" | "--source-ports" => match ports(&value) {
+                Ok(p) => rule.sports = p,
+                Err(e) => rule.unsupported.push(e),
+            },
+            "--dport" | "--destination-port" | "--dports" | "--destination-ports" => match ports(&value) {
+                Ok(p) => rule.dports = p,
+                Err(e) => rule.unsupported.push(e),
+            },
+            "-m" | "--match" => {
+                let module = value.unwrap_or_default();
+                if !KNOWN_MATCH_MODULES.contains(&module.as_str()) {
+                    rule.unsupported.push(format!("match module '{}'", module));
+                }
+            }
+            "--comment" => rule.comment = value,
+            "-j" | "--jump" | "-g" | "--goto" => rule.target = value,
+            "--to-destination" => rule.to_destination = value,
+            "--to-source" => rule.to_source = value,
+            "--to-ports" => rule.to_ports = value,
+            "--reject-with" => {}
+            other => {
+                rule.unsupported.push(format!("option '{}'", other));
+                if value.as_deref().is_none_or(|v| v.starts_with('-')) {
+                    consumed = 1;
+                }
+            }
+        }
+        i += consumed;
+    }
+    rule
+}
+
+fn import_iptables(input: &str) -> EngineResult<Imported> {
+    let mut out = Imported::default();
+    let mut family = AddressFamily::Ipv4;
+    let mut table = String::new();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+
+    for line in input.lines().map(str::trim) {
+        if line.starts_with('#') {
+            // iptables-save and ip6tables-save output is often concatenated; each dump opens with its own header
+            if line.starts_with("# Generated by") {
+                family = if line.contains("ip6tables") { AddressFamily::Ipv6 } else { AddressFamily::Ipv4 };
+            }
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('*') {
+            table = name.to_string();
+            continue;
+        }
+        if let Some(declaration) = line.strip_prefix(':') {
+            // `:INPUT DROP [0:0]` declares a chain and its policy; user chains carry `-` and built-in ones ACCEPT or DROP
+            if declaration.split_whitespace().nth(1) == Some("DROP") {
+                out.skip(line, "default drop policy is not represented; add explicit Block rules");
+            }
+            continue;
+        }
+        if line.is_empty() || line == "COMMIT" {
+            continue;
+        }
+        let Some(rest) = line.strip_prefix("-A ") else {
+            out.skip(line, "unsupported iptables-save statement");
+            continue;
+        };
+        let tokens = tokenize(rest)?;
+        let Some((chain, options)) = tokens.split_first() else {
+            continue;
+        };
+        let count = counts.entry(format!("{}/{}", table, chain)).or_default();
+        *count += 1;
+
+        let parsed = parse_iptables_options(options);
+        if !parsed.unsupported.is_empty() {
+            out.skip(line, format!("not representable: {}", parsed.unsupported.join(", ")));
+            continue;
+        }
+        let name = parsed.comment.clone().unwrap_or_else(|| format!("iptables {} {} #{}", table, chain, count));
+
+        match (table.as_str(), chain.as_str()) {
+            ("filter", "INPUT") | ("filter", "OUTPUT") => {
+                let direction = if chain == "INPUT" { RuleDirection::Inbound } else { RuleDirection::Outbound };
+                iptables_filter_rule(&mut out, line, name, direction, family, parsed)
+            }
+            ("nat", "PREROUTING") | ("nat", "POSTROUTING") => iptables_nat_rule(&mut out, line, name, parsed),
+            _ => out.skip(line, format!("chain {} in table {} has no cyberwall equivalent", chain, table)),
+        }
+    }
+    Ok(out)
+}
+
+fn iptables_filter_rule(
+    out: &mut Imported,
+    line: &str,
+    name: String,
+    direction: RuleDirection,
+    family: AddressFamily,
+    parsed: IptablesRule,
+) {
+    if parsed.in_interface.is_some() || parsed.out_interface.is_some() {
+        return out.skip(line, "interface-scoped filter rules are not representable");
+    }
+    let action = match parsed.target.as_deref() {
+        Some("ACCEPT") => RuleAction::Allow,
+        Some("DROP") | Some("REJECT") => RuleAction::Block,
+        other => return out.skip(line, format!("target {:?} is not an allow/block verdict", other.unwrap_or("none"))),
+    };
+    let protocol = match parsed.protocol.as_deref().map(str::parse::<Protocol>) {
+        None => None,
+        Some(Ok(p)) => Some(p),
+        Some(Err(e)) => return out.skip(line, e),
+    };
+    let (local, remote, local_ports, remote_ports) = match direction {
+        RuleDirection::Inbound => (parsed.destinations, parsed.sources, parsed.dports, parsed.sports),
+        RuleDirection::Outbound => (parsed.sources, parsed.destinations, parsed.sports, parsed.dports),
+    };
+    let rule = FirewallRule {
+        family,
+        protocol,
+        local_addresses: local,
+        remote_addresses: remote,
+        local_ports,
+        remote_ports,
+        ..FirewallRule::new(name, action, direction)
+    };
+    out.push_rule(line, rule);
+}
+
+fn iptables_nat_rule(out: &mut Imported, line: &str, name: String, parsed: IptablesRule) {
+    if parsed.sources.len() > 1 || parsed.destinations.len() > 1 || parsed.dports.len() > 1 || !parsed.sports.is_empty() {
+        return out.skip(line, "NAT rules support a single source, destination and destination port");
+    }
+    let (kind, target) = match parsed.target.as_deref() {
+        Some("DNAT") => (NatKind::Dnat, parsed.to_destination.as_deref()),
+        Some("SNAT") => (NatKind::Snat, parsed.to_source.as_deref()),
+        Some("MASQUERADE") => (NatKind::Masquerade, None),
+        Some("REDIRECT") => (NatKind::Redirect, None),
+        other => return out.skip(line, format!("NAT target {:?} is not supported", other.unwrap_or("none"))),
+    };
+    let (to_address, mut to_port) = match target.map(parse_nat_target) {
+        Some(Some((addr, port))) => (Some(addr), port),
+        Some(None) => return out.skip(line, "NAT target address ranges are not supported"),
+        None => (None, None),
+    };
+    if let Some(ports) = &parsed.to_ports {
+        match ports.parse::<u16>() {
+            Ok(p) => to_port = Some(p),
+            Err(_) => return out.skip(line, "NAT port ranges are not supported"),
+        }
+    }
+    let dport = parsed.dports.first().copied();
+    if dport.is_some_and(|p| p.start != p.end) {
+        return out.skip(line, "NAT port ranges are not supported");
+    }
+    let protocol = match parsed.protocol.as_deref().map(str::parse::<Protocol>) {
+        None => None,
+        Some(Ok(p)) => Some(p),
+        Some(Err(e)) => return out.skip(line, e),
+    };
+    let rule = NatRule {
+        name,
+        enabled: true,
+        kind,
+        protocol,
+        in_interface: parsed.in_interface,
+        out_interface: parsed.out_interface,
+        source: parsed.sources.into_iter().next(),
+        destination: parsed.destinations.into_iter().next(),
+        destination_port: dport.map(|p| p.start),
+        to_address,
+        to_port,
+    };
+    out.push_nat(line, rule);
+}
+
+// ---------------------------------------------------------------------------
+// nft -j list ruleset
+// ---------------------------------------------------------------------------
+
+fn nft_values(right: &Value) -> Option<Vec<String>> {
+    match right {
+        Value::String(s) => Some(vec![s.clone()]),
+        Value::Number(n) => Some(vec![n.to_string()]),
+        Value::Array(items) => items.iter().map(nft_values).collect::<Option<Vec<_>>>().map(|v| v.concat()),
+        Value::Object(o) => {
+            if let Some(set) = o.get("set") {
+                return nft_values(set);
+            }
+            if let Some(prefix) = o.get("prefix") {
+                return Some(vec![format!(
+                    "{}/{}",
+                    prefix.get("addr")?.as_str()?,
+                    prefix.get("len")?.as_u64()?
+                )]);
+            }
+            let range = o.get("range")?.as_array()?;
+            Some(vec![format!("{}-{}", nft_values(range.first()?)?.first()?, nft_values(range.get(1)?)?.first()?)])
+        }
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct NftMatches {
+    family: Option<AddressFamily>,
+    protocol: Option<Protocol>,
+    saddr: Vec<String>,
+    daddr: Vec<String>,
+    sport: Vec<PortRange>,
+    dport: Vec<PortRange>,
+    iifname: Option<String>,
+    oifname: Option<String>,
+    action: Option<RuleAction>,
+    nat: Option<(NatKind, Option<IpAddr>, Option<u16>)>,
+    /// `meta l4proto { tcp, udp }`, which export emits for port rules without a protocol
+    tcp_or_udp: bool,
+    unsupported: Vec<String>,
+}
+
+fn parse_nft_exprs(exprs: &[Value]) -> NftMatches {
+    let mut m = NftMatches::default();
+    for expr in exprs {
+        let Some((key, body)) = expr.as_object().and_then(|o| o.iter().next()) else {
+            continue;
+        };
+        match key.as_str() {
+            "match" => {
+                let op = body.get("op").and_then(Value::as_str).unwrap_or("==");
+                if op != "==" && op != "in" {
+                    m.unsupported.push(format!("match operator '{}'", op));
+                    continue;
+                }
+                let Some(values) = body.get("right").and_then(nft_values) else {
+                    m.unsupported.push("complex match value".to_string());
+                    continue;
+                };
+                let left = body.get("left").unwrap_or(&Value::Null);
+                if let Some(meta) = left.get("meta").and_then(|v| v.get("key")).and_then(Value::as_str) {
+                    match meta {
+                        "l4proto" if values.len() == 1 => match values[0].parse() {
+                            Ok(p) => m.protocol = Some(p),
+                            Err(e) => m.unsupported.push(e),
+                        },
+                        "l4proto" if values.len() == 2 && values.contains(&"tcp".to_string()) && values.contains(&"udp".to_string()) => {
+                            m.tcp_or_udp = true
+                        }
+                        "nfproto" => {
+                            m.family = match values.first().map(String::as_str) {
+                                Some("ipv4") => Some(AddressFamily::Ipv4),
+                                Some("ipv6") => Some(AddressFamily::Ipv6),
+                                _ => None,
+                            }
+                        }
+                        "iifname" if values.len() == 1 => m.iifname = values.into_iter().next(),
+                        "oifname" if values.len() == 1 => m.oifname = values.into_iter().next(),
+                        other => m.unsupported.push(format!("meta {} match", other)),
+                    }
+                } else if let Some(payload) = left.get("payload") {
+                    let proto = payload.get("protocol").and_then(Value::as_str).unwrap_or_default();
+                    let field = payload.get("field").and_then(Value::as_str).unwrap_or_default();
+                    match (proto, field) {
+                        ("ip", "saddr") | ("ip6", "saddr") => m.saddr = values,
+                        ("ip", "daddr") | ("ip6", "daddr") => m.daddr = values,
+                        (_, "sport") | (_, "dport") => {
+                            if proto != "th" {
+                                match proto.parse() {
+                                    Ok(p) => m.protocol = Some(p),
+                                    Err(e) => m.unsupported.push(e),
+                                }
+                            }
+                            match values.iter().map(|v| v.parse()).collect::<Result<Vec<PortRange>, _>>() {
+                                Ok(ports) if field == "sport" => m.sport = ports,
+                                Ok(ports) => m.dport = ports,
+                                Err(e) => m.unsupported.push(e),
+                            }
+                        }
+                        _ => m.unsupported.push(format!("{} {} match", proto, field)),
+                    }
+                } else if left.get("ct").is_some() {
+                    m.unsupported.push("connection tracking state match".to_string());
+                } else {
+                    m.unsupported.push("unrecognized match".to_string());
+                }
+            }
+            "counter" | "last" | "log" | "comment" => {}
+            "accept" => m.action = Some(RuleAction::Allow),
+            "drop" | "reject" => m.action = Some(RuleAction::Block),
+            "snat" | "dnat" | "masquerade" | "redirect" => {
+                let kind = match key.as_str() {
+                    "snat" => NatKind::Snat,
+                    "dnat" => NatKind::Dnat,
+                    "masquerade" => NatKind::Masquerade,
+                    _ => NatKind::Redirect,
+                };
+                let addr = body.get("addr").and_then(Value::as_str).and_then(|a| a.parse().ok());
+                let port = body.get("port").and_then(Value::as_u64).map(|p| p as u16);
+                m.nat = Some((kind, addr, port));
+            }
+            other => m.unsupported.push(format!("'{}' statement", other)),
+        }
+    }
+    // Without ports the set would widen to every protocol once imported as `protocol: None`
+    if m.tcp_or_udp && m.sport.is_empty() && m.dport.is_empty() {
+        m.unsupported.push("meta l4proto { tcp, udp } without ports".to_string());
+    }
+    m
+}
+
+/// Imports filter and NAT rules from `nft -j list ruleset` (or `list table`) output
+fn import_nft_json(input: &str) -> EngineResult<Imported> {
+    let doc: Value = serde_json::from_str(input).map_err(|e| EngineError(format!("Invalid nft JSON: {}", e)))?;
+    let items = doc
+        .get("nftables")
+        .and_then(Value::as_array)
+        .ok_or_else(|| EngineError("nft JSON has no 'nftables' array".to_string()))?;
+
+    let mut out = Imported::default();
+    let mut chains: HashMap<(String, String, String), (String, String)> = HashMap::new();
+    for chain in items.iter().filter_map(|i| i.get("chain")) {
+        let key = |k: &str| chain.get(k).and_then(Value::as_str).unwrap_or_default().to_string();
+        let id = (key("family"), key("table"), key("name"));
+        if let Some("drop") = chain.get("policy").and_then(Value::as_str) {
+            out.skip(
+                format!("{} {} chain {}", id.0, id.1, id.2),
+                "default drop policy is not represented; add explicit Block rules",
+            );
+        }
+        chains.insert(id, (key("type"), key("hook")));
+    }
+
+    for rule in items.iter().filter_map(|i| i.get("rule")) {
+        let key = |k: &str| rule.get(k).and_then(Value::as_str).unwrap_or_default().to_string();
+        let (family, table, chain) = (key("family"), key("table"), key("chain"));
+        let handle = rule.get("handle").and_then(Value::as_u64).unwrap_or_default();
+        let source = format!("{} {} {} handle {}", family, table, chain, handle);
+        let name = rule
+            .get("comment")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("nft {} {} #{}", table, chain, handle));
+
+        let table_family = match family.as_str() {
+            "ip" => AddressFamily::Ipv4,
+            "ip6" => AddressFamily::Ipv6,
+            "inet" => AddressFamily::Any,
+            other => {
+                out.skip(source, format!("{} family tables are not representable", other));
+                continue;
+            }
+        };
+        let Some((chain_type, hook)) = chains.get(&(family.clone(), table.clone(), chain.clone())).cloned() else {
+            out.skip(source, "chain definition missing from input");
+            continue;
+        };
+        let exprs = rule.get("expr").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+        let m = parse_nft_exprs(exprs);
+        if !m.unsupported.is_empty() {
+            out.skip(source, format!("not representable: {}", m.unsupported.join(", ")));
+            continue;
+        }
+        let family = family_of_all(&[m.saddr.clone(), m.daddr.clone()].concat(), m.family.unwrap_or(table_family));
+
+        match (chain_type.as_str(), hook.as_str()) {
+            ("filter", "input") | ("filter", "output") => {
+                // Profile-scoped rules match the interface set of the profile's zone
+                let zone = m.iifname.as_deref().or(m.oifname.as_deref()).and_then(|i| i.strip_prefix('@'));
+                let profile = zone.and_then(|set| ZONE_SETS.iter().find(|(_, s)| *s == set)).map(|(p, _)| *p);
+                if (m.iifname.is_some() || m.oifname.is_some()) && profile.is_none() {
+                    out.skip(source, "interface-scoped filter rules are not representable");
+                    continue;
+                }
+                let Some(action) = m.action else {
+                    out.skip(source, "rule has no accept/drop verdict");
+                    continue;
+                };
+                let direction = if hook == "input" { RuleDirection::Inbound } else { RuleDirection::Outbound };
+                let (local, remote, local_ports, remote_ports) = match direction {
+                    RuleDirection::Inbound => (m.daddr, m.saddr, m.dport, m.sport),
+                    RuleDirection::Outbound => (m.saddr, m.daddr, m.sport, m.dport),
+                };
+                let rule = FirewallRule {
+                    family,
+                    protocol: m.protocol,
+                    local_addresses: local,
+                    remote_addresses: remote,
+                    local_ports,
+                    remote_ports,
+                    profile: profile.unwrap_or(ProfileType::All),
+                    ..FirewallRule::new(name, action, direction)
+                };
+                out.push_rule(&source, rule);
+            }
+            ("nat", "prerouting") | ("nat", "postrouting") => {
+                let Some((kind, to_address, to_port)) = m.nat else {
+                    out.skip(source, "nat chain rule without a NAT statement");
+                    continue;
+                };
+                if m.saddr.len() > 1 || m.daddr.len() > 1 || m.dport.len() > 1 || !m.sport.is_empty() {
+                    out.skip(source, "NAT rules support a single source, destination and destination port");
+                    continue;
+                }
+                let dport = m.dport.first().copied();
+                if dport.is_some_and(|p| p.start != p.end) {
+                    out.skip(source, "NAT port ranges are not supported");
+                    continue;
+                }
+                let rule = NatRule {
+                    name,
+                    enabled: true,
+                    kind,
+                    protocol: m.protocol,
+                    in_interface: m.iifname,
+                    out_interface: m.oifname,
+                    source: m.saddr.into_iter().next(),
+                    destination: m.daddr.into_iter().next(),
+                    destination_port: dport.map(|p| p.start),
+                    to_address,
+                    to_port,
+                };
+                out.push_nat(&source, rule);
+            }
+            _ if hook.is_empty() => out.skip(source, format!("rule lives in regular chain '{}' reached by jump", chain)),
+            _ => out.skip(source, format!("{} chain on hook '{}' is not representable", chain_type, hook)),
+        }
+    }
+    Ok(out)
+}
+
+/// Parses the live cyberwall table back into policy rules (used by the Linux backend)
+pub fn policy_from_nft_json(input: &str) -> EngineResult<(Vec<FirewallRule>, Vec<NatRule>)> {
+    let imported = import_nft_json(input)?;
+    Ok((imported.rules, imported.nat_rules))
+}
+
+// ---------------------------------------------------------------------------
+// netsh advfirewall firewall show rule name=all
+// ---------------------------------------------------------------------------
+
+fn netsh_addresses(value: &str) -> Result<Vec<String>, String> {
+    if value.eq_ignore_ascii_case("any") || value == "*" || value.is_empty() {
+        return Ok(Vec::new());
+    }
+    value
+        .split(',')
+        .map(|item| {
+            let item = item.trim();
+            let converted = match item.split_once('/') {
+                Some((addr, mask)) if mask.contains('.') => {
+                    let mask: std::net::Ipv4Addr = mask.parse().map_err(|_| format!("Invalid netmask '{}'", item))?;
+                    format!("{}/{}", addr, u32::from(mask).count_ones())
+                }
+                _ => item.to_string(),
+            };
+            cidr_family(&converted).map_err(|_| format!("address '{}' is not representable", item))?;
+            Ok(converted)
+        })
+        .collect()
+}
+
+fn netsh_ports(value: &str) -> Result<Vec<PortRange>, String> {
+    if value.eq_ignore_ascii_case("any") || value == "*" || value.is_empty() {
+        return Ok(Vec::new());
+    }
+    value
+        .split(',')
+        .map(|p| p.trim().parse().map_err(|_| format!("port '{}' is not representable", p.trim())))
+        .collect()
+}
+
+fn netsh_profiles(value: &str) -> Result<Vec<ProfileType>, String> {
+    let mut profiles = Vec::new();
+    for p in value.split(',').map(str::trim) {
+        profiles.push(match p.to_ascii_lowercase().as_str() {
+            "domain" => ProfileType::Domain,
+            "private" => ProfileType::Private,
+            "public" => ProfileType::Public,
+            "any" => ProfileType::All,
+            other => return Err(format!("profile '{}' is not representable", other)),
+        });
+    }
+    if profiles.len() >= 3 || profiles.contains(&ProfileType::All) {
+        return Ok(vec![ProfileType::All]);
+    }
+    Ok(profiles)
+}
+
+/// Converts one rule block of `netsh ... show rule` output, keyed by lowercased field name
+pub fn netsh_rule(fields: &HashMap<String, String>) -> Result<Vec<FirewallRule>, String> {
+    let get = |k: &str| fields.get(k).map(String::as_str).unwrap_or("Any");
+    let name = fields.get("rule name").cloned().unwrap_or_default();
+    let direction = match get("direction").to_ascii_lowercase().as_str() {
+        "in" => RuleDirection::Inbound,
+        "out" => RuleDirection::Outbound,
+        other => return Err(format!("direction '{}' is not representable", other)),
+    };
+    let action = match get("action").to_ascii_lowercase().as_str() {
+        "allow" => RuleAction::Allow,
+        "block" => RuleAction::Block,
+        other => return Err(format!("action '{}' is not representable", other)),
+    };
+    let protocol = match get("protocol").to_ascii_lowercase().as_str() {
+        "any" => None,
+        "icmpv4" => Some(Protocol::Icmp),
+        other => Some(other.parse::<Protocol>()?),
+    };
+    let local_addresses = netsh_addresses(get("localip"))?;
+    let remote_addresses = netsh_addresses(get("remoteip"))?;
+    let family = family_of_all(&[local_addresses.clone(), remote_addresses.clone()].concat(), AddressFamily::Any);
+    let family = match protocol {
+        Some(Protocol::Icmp) => AddressFamily::Ipv4,
+        Some(Protocol::IcmpV6) => AddressFamily::Ipv6,
+        _ => family,
+    };
+    let base = FirewallRule {
+        enabled: get("enabled").eq_ignore_ascii_case("yes"),
+        application: fields.get("program").filter(|p| !p.eq_ignore_ascii_case("any")).cloned(),
+        family,
+        protocol,
+        local_addresses,
+        remote_addresses,
+        local_ports: netsh_ports(get("localport"))?,
+        remote_ports: netsh_ports(get("remoteport"))?,
+        ..FirewallRule::new(name, action, direction)
+    };
+    // A rule bound to two profiles becomes one rule per profile
+    Ok(netsh_profiles(get("profiles"))?
+        .into_iter()
+        .map(|profile| FirewallRule { profile, ..base.clone() })
+        .collect())
+}
+
+/// Splits `netsh ... show rule` output into one field map per rule
+pub fn netsh_blocks(input: &str) -> Vec<HashMap<String, String>> {
+    let mut blocks: Vec<HashMap<String, String>> = Vec::new();
+    for line in input.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        if key == "rule name" {
+            blocks.push(HashMap::new());
+        }
+        if let Some(block) = blocks.last_mut() {
+            block.insert(key, value.trim().to_string());
+        }
+    }
+    blocks
+}
+
+fn import_netsh(input: &str) -> Imported {
+    let mut out = Imported::default();
+    for block in netsh_blocks(input) {
+        let source = format!("netsh rule '{}'", block.get("rule name").cloned().unwrap_or_default());
+        match netsh_rule(&block) {
+            Ok(rules) => rules.into_iter().for_each(|r| out.push_rule(&source, r)),
+            Err(e) => out.skip(source, e),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::render_iptables;
+
+    const SAVES: &str = "\
+# Generated by iptables-save v1.8.7 on Mon Oct 19 10:00:00 2026
+*filter
+:INPUT ACCEPT [0:0]
+-A INPUT -p tcp -m tcp --dport 22 -m comment --comment \"ssh v4\" -j ACCEPT
+COMMIT
+# Completed on Mon Oct 19 10:00:00 2026
+# Generated by ip6tables-save v1.8.7 on Mon Oct 19 10:00:00 2026
+*filter
+:INPUT ACCEPT [0:0]
+-A INPUT -p tcp -m tcp --dport 22 -m comment --comment \"ssh v6\" -j ACCEPT
+COMMIT
+# Completed on Mon Oct 19 10:00:00 2026
+# Generated by iptables-save v1.8.7 on Mon Oct 19 10:00:01 2026
+*filter
+:OUTPUT ACCEPT [0:0]
+-A OUTPUT -d 192.0.2.1/32 -m comment --comment \"block v4 host\" -j DROP
+COMMIT
+";
+
+    fn family_of(rules: &[FirewallRule], name: &str) -> Vec<AddressFamily> {
+        rules.iter().filter(|r| r.name == name).map(|r| r.family).collect()
+    }
+
+    #[test]
+    fn every_save_header_sets_the_family() {
+        let imported = import_iptables(SAVES).unwrap();
+        assert!(imported.skipped.is_empty(), "{:?}", imported.skipped);
+        assert_eq!(family_of(&imported.rules, "ssh v4"), vec![AddressFamily::Ipv4]);
+        assert_eq!(family_of(&imported.rules, "ssh v6"), vec![AddressFamily::Ipv6]);
+        assert_eq!(family_of(&imported.rules, "block v4 host"), vec![AddressFamily::Ipv4]);
+    }
+
+    #[test]
+    fn concatenated_iptables_exports_import_per_family() {
+        let policy = FirewallPolicy {
+            name: "families".to_string(),
+            version: "1".to_string(),
+            rules: vec![
+                FirewallRule {
+                    protocol: Some(Protocol::Tcp),
+                    local_ports: vec![PortRange { start: 80, end: 80 }, PortRange { start: 8000, end: 8100 }],
+                    ..FirewallRule::new("web", RuleAction::Allow, RuleDirection::Inbound)
+                },
+                FirewallRule {
+                    family: AddressFamily::Ipv4,
+                    protocol: Some(Protocol::Icmp),
+                    ..FirewallRule::new("ping v4", RuleAction::Allow, RuleDirection::Inbound)
+                },
+                FirewallRule { protocol: Some(Protocol::IcmpV6), ..FirewallRule::new("ping v6", RuleAction::Allow, RuleDirection::Inbound) },
+            ],
+            nat_rules: Vec::new(),
+            allow_neighbor_discovery: true,
+        };
+        let input = render_iptables(&policy, AddressFamily::Ipv6) + &render_iptables(&policy, AddressFamily::Ipv4);
+        let imported = import_iptables(&input).unwrap();
+        // Neighbor discovery comes back through `allow_neighbor_discovery`, not as rules
+        assert!(imported.skipped.iter().all(|s| s.source.contains(crate::export::NDP_COMMENT)), "{:?}", imported.skipped);
+        assert_eq!(family_of(&imported.rules, "web"), vec![AddressFamily::Ipv6, AddressFamily::Ipv4]);
+        assert_eq!(family_of(&imported.rules, "ping v6"), vec![AddressFamily::Ipv6]);
+        assert_eq!(family_of(&imported.rules, "ping v4"), vec![AddressFamily::Ipv4]);
+    }
+
+    #[test]
+    fn drop_chain_policies_are_reported_as_skipped() {
+        let input = "*filter\n:INPUT DROP [12:840]\n:FORWARD DROP [0:0]\n:OUTPUT ACCEPT [0:0]\n:cyberwall-user - [0:0]\nCOMMIT\n";
+        let imported = import_iptables(input).unwrap();
+        let sources: Vec<&str> = imported.skipped.iter().map(|s| s.source.as_str()).collect();
+        assert_eq!(sources, vec![":INPUT DROP [12:840]", ":FORWARD DROP [0:0]"]);
+        assert!(imported.skipped.iter().all(|s| s.reason.contains("default drop policy")));
+    }
+
+    /// `netsh advfirewall firewall show rule name=all verbose` in the layout an English-locale Windows 11 host prints
+    const NETSH_SHOW_RULE: &str = include_str!("../testdata/import/netsh_show_rule.txt");
+
+    fn netsh_by_name<'a>(rules: &'a [FirewallRule], name: &str) -> Vec<&'a FirewallRule> {
+        rules.iter().filter(|r| r.name == name).collect()
+    }
+
+    #[test]
+    fn netsh_blocks_split_real_output_per_rule() {
+        let blocks = netsh_blocks(NETSH_SHOW_RULE);
+        assert_eq!(blocks.len(), 8);
+        let rdp = &blocks[0];
+        assert_eq!(rdp["rule name"], "Remote Desktop - User Mode (TCP-In)");
+        assert_eq!(rdp["localport"], "3389");
+        // Drive letters keep everything after the first colon
+        assert_eq!(rdp["program"], "C:\\Windows\\system32\\svchost.exe");
+        assert_eq!(rdp["action"], "Allow");
+        // ICMP type/code rows have no colon and do not leak into the block
+        assert_eq!(blocks[1]["protocol"], "ICMPv6");
+        assert_eq!(blocks[1].len(), blocks[2].len());
+        assert_eq!(blocks[6]["remoteip"], "2001:db8::53/128");
+        // CRLF output from a Windows console splits the same way
+        assert_eq!(netsh_blocks(&NETSH_SHOW_RULE.replace('\n', "\r\n")), blocks);
+    }
+
+    #[test]
+    fn netsh_rules_from_real_output() {
+        let blocks = netsh_blocks(NETSH_SHOW_RULE);
+
+        let rdp = netsh_rule(&blocks[0]).unwrap();
+        assert_eq!(rdp.iter().map(|r| r.profile).collect::<Vec<_>>(), vec![ProfileType::Domain, ProfileType::Private]);
+        assert_eq!((rdp[0].direction, rdp[0].action, rdp[0].protocol), (RuleDirection::Inbound, RuleAction::Allow, Some(Protocol::Tcp)));
+        assert_eq!(rdp[0].local_ports, vec![PortRange { start: 3389, end: 3389 }]);
+        assert_eq!(rdp[0].application.as_deref(), Some("C:\\Windows\\system32\\svchost.exe"));
+
+        let unreachable = netsh_rule(&blocks[1]).unwrap();
+        assert_eq!(unreachable.len(), 1);
+        assert_eq!((unreachable[0].profile, unreachable[0].family), (ProfileType::All, AddressFamily::Ipv6));
+        assert_eq!(unreachable[0].protocol, Some(Protocol::IcmpV6));
+
+        let ping = netsh_rule(&blocks[2]).unwrap();
+        assert_eq!(ping.len(), 2);
+        assert!(ping.iter().all(|r| !r.enabled && r.protocol == Some(Protocol::Icmp) && r.family == AddressFamily::Ipv4));
+        assert_eq!(ping[0].application, None);
+
+        let telemetry = netsh_rule(&blocks[5]).unwrap();
+        assert_eq!((telemetry[0].direction, telemetry[0].action), (RuleDirection::Outbound, RuleAction::Block));
+        assert_eq!(telemetry[0].remote_addresses, vec!["192.0.2.0/24", "198.51.100.7/32"]);
+        assert_eq!(telemetry[0].remote_ports, vec![PortRange { start: 443, end: 443 }, PortRange { start: 8080, end: 8081 }]);
+        assert_eq!(telemetry[0].family, AddressFamily::Ipv4);
+
+        let dns = netsh_rule(&blocks[6]).unwrap();
+        assert_eq!((dns[0].family, dns[0].protocol), (AddressFamily::Ipv6, Some(Protocol::Udp)));
+        assert_eq!(dns[0].remote_addresses, vec!["2001:db8::53/128"]);
+    }
+
+    #[test]
+    fn netsh_import_skips_what_it_cannot_represent() {
+        let imported = import_netsh(NETSH_SHOW_RULE);
+        let skipped: Vec<(&str, &str)> = imported.skipped.iter().map(|s| (s.source.as_str(), s.reason.as_str())).collect();
+        assert_eq!(
+            skipped,
+            vec![
+                ("netsh rule 'File and Printer Sharing (SMB-In)'", "address 'LocalSubnet' is not representable"),
+                ("netsh rule 'Remote Event Log Management (RPC)'", "port 'RPC' is not representable"),
+                ("netsh rule 'Backup Agent Range'", "address '10.0.0.10-10.0.0.20' is not representable"),
+            ]
+        );
+        assert_eq!(netsh_by_name(&imported.rules, "Remote Desktop - User Mode (TCP-In)").len(), 2);
+        assert_eq!(netsh_by_name(&imported.rules, "Lab DNS (UDP-Out)").len(), 1);
+        assert_eq!(imported.rules.len(), 7);
+    }
+
+    fn nft_rule(exprs: serde_json::Value) -> String {
+        serde_json::json!({ "nftables": [
+            { "chain": { "family": "inet", "table": "t", "name": "output", "type": "filter", "hook": "output", "policy": "accept" } },
+            { "rule": { "family": "inet", "table": "t", "chain": "output", "handle": 4, "comment": "dns", "expr": exprs } },
+        ]})
+        .to_string()
+    }
+
+    #[test]
+    fn tcp_udp_set_with_ports_imports_without_protocol() {
+        let l4 = serde_json::json!({ "match": { "op": "==", "left": { "meta": { "key": "l4proto" } }, "right": { "set": ["udp", "tcp"] } } });
+        let dport = serde_json::json!({ "match": { "op": "==", "left": { "payload": { "protocol": "th", "field": "dport" } }, "right": 53 } });
+        let accept = serde_json::json!({ "accept": null });
+
+        let imported = import_nft_json(&nft_rule(serde_json::json!([l4, dport, accept]))).unwrap();
+        assert_eq!(imported.rules.len(), 1, "{:?}", imported.skipped);
+        assert_eq!(imported.rules[0].protocol, None);
+        assert_eq!(imported.rules[0].remote_ports, vec![PortRange { start: 53, end: 53 }]);
+
+        // Without ports the set cannot be expressed as a single protocol
+        let imported = import_nft_json(&nft_rule(serde_json::json!([l4, accept]))).unwrap();
+        assert!(imported.rules.is_empty());
+        assert!(imported.skipped[0].reason.contains("without ports"), "{:?}", imported.skipped);
+
+        let l4 = serde_json::json!({ "match": { "op": "==", "left": { "meta": { "key": "l4proto" } }, "right": { "set": ["tcp", "icmp"] } } });
+        assert!(import_nft_json(&nft_rule(serde_json::json!([l4, dport, accept]))).unwrap().rules.is_empty());
+    }
+
+    /// `nft -j list table inet cyberwall` with the zone hooks, as the drift tests read it
+    const LIST_TABLE: &str = include_str!("../testdata/nft/list-table.json");
+
+    #[test]
+    fn live_listing_imports_port_rule_without_protocol() {
+        let imported = import_nft_json(LIST_TABLE).unwrap();
+        let dns: Vec<&FirewallRule> = imported.rules.iter().filter(|r| r.name == "dns any transport").collect();
+        assert_eq!(dns.len(), 1, "{:?}", imported.rules);
+        assert_eq!((dns[0].protocol, dns[0].family, dns[0].direction), (None, AddressFamily::Ipv4, RuleDirection::Outbound));
+        assert_eq!(dns[0].remote_ports, vec![PortRange { start: 53, end: 53 }]);
+        assert_eq!(dns[0].remote_addresses, vec!["192.0.2.53"]);
+
+        // Counters, `last` and log statements carry no policy; the ranged port set survives nft's reordering
+        let web = imported.rules.iter().find(|r| r.name == "web v6 only").unwrap();
+        assert_eq!(web.family, AddressFamily::Ipv6);
+        assert_eq!(web.local_ports, vec![PortRange { start: 80, end: 80 }, PortRange { start: 443, end: 443 }, PortRange { start: 8000, end: 8100 }]);
+        let smb = imported.rules.iter().find(|r| r.name == "no smb on public").unwrap();
+        assert_eq!((smb.profile, smb.action), (ProfileType::Public, RuleAction::Block));
+
+        // Only the emergency mark, neighbor discovery and the zone jumps are left behind, twice each
+        let reasons: Vec<&str> = imported.skipped.iter().map(|s| s.reason.as_str()).collect();
+        assert_eq!(reasons.len(), 8, "{:?}", imported.skipped);
+        assert!(reasons.iter().all(|r| ["meta mark match", "icmpv6 type match", "'jump' statement"].iter().any(|k| r.contains(k))), "{:?}", reasons);
+    }
+}
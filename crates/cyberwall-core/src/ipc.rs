@@ -0,0 +1,363 @@
+use crate::engine::{EngineError, EngineResult, FirewallEngine};
+use crate::metrics;
+use crate::models::{EmergencyMode, FirewallPolicy, FirewallRule, FirewallStatus, NatRule, ProfileType, RuleStats};
+use crate::signing::{SignedDocument, SigningConfig};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+/// Default path of the aegisd control socket
+pub const DEFAULT_SOCKET_PATH: &str = "/run/aegisd/aegisd.sock";
+
+/// Default address of the optional TCP control listener
+pub const DEFAULT_TCP_ADDR: &str = "127.0.0.1:5180";
+
+/// Environment variable holding the shared token for TCP control connections
+pub const TOKEN_ENV: &str = "AEGISD_TOKEN";
+
+/// Longest a client waits for aegisd to connect and answer one request; applying a large policy is the slowest call
+pub const CALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Control API call; all but the daemon-level calls map onto a `FirewallEngine` method
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum IpcRequest {
+    Status,
+    SetEnabled { enabled: bool },
+    SetProfileEnabled { profile: ProfileType, enabled: bool },
+    SetOutboundBlock { blocked: bool },
+    /// `mode: null` is the break-glass release and is refused without a `reason`, which is kept in the audit log
+    SetEmergency {
+        mode: Option<EmergencyMode>,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    ListRules,
+    RuleStats,
+    ListNatRules,
+    AddNatRule { rule: NatRule },
+    RemoveNatRule { name: String },
+    ApplyPolicy { policy: FirewallPolicy },
+    /// Policy file contents with their detached signature, checked against the trusted keys
+    ApplySignedPolicy { signed: SignedDocument },
+    /// Subsystem health report, answered by aegisd itself
+    Health,
+    /// Re-reads the aegisd manifest and applies it to every subsystem
+    Reload,
+    /// States of the subsystem workers supervised by aegisd
+    Workers,
+}
+
+impl IpcRequest {
+    /// Wire name of the call, as in the `method` field
+    pub fn method(&self) -> &'static str {
+        match self {
+            IpcRequest::Status => "status",
+            IpcRequest::SetEnabled { .. } => "set_enabled",
+            IpcRequest::SetProfileEnabled { .. } => "set_profile_enabled",
+            IpcRequest::SetOutboundBlock { .. } => "set_outbound_block",
+            IpcRequest::SetEmergency { .. } => "set_emergency",
+            IpcRequest::ListRules => "list_rules",
+            IpcRequest::RuleStats => "rule_stats",
+            IpcRequest::ListNatRules => "list_nat_rules",
+            IpcRequest::AddNatRule { .. } => "add_nat_rule",
+            IpcRequest::RemoveNatRule { .. } => "remove_nat_rule",
+            IpcRequest::ApplyPolicy { .. } => "apply_policy",
+            IpcRequest::ApplySignedPolicy { .. } => "apply_signed_policy",
+            IpcRequest::Health => "health",
+            IpcRequest::Reload => "reload",
+            IpcRequest::Workers => "workers",
+        }
+    }
+
+    /// Calls that change firewall or daemon state, and so are written to the audit log
+    pub fn is_mutating(&self) -> bool {
+        !matches!(
+            self,
+            IpcRequest::Status | IpcRequest::ListRules | IpcRequest::RuleStats | IpcRequest::ListNatRules | IpcRequest::Health | IpcRequest::Workers
+        )
+    }
+}
+
+/// Who sent a control request, as far as the listener can tell
+#[derive(Debug, Clone, Default)]
+pub struct Caller {
+    /// Listener the request came through, e.g. `unix` or `tcp`
+    pub source: String,
+    pub actor: String,
+    pub uid: Option<u32>,
+    pub peer: String,
+    /// Role granted to a remote API client, kept in the audit log
+    pub role: Option<String>,
+}
+
+/// One request line on the wire; `token` is required on TCP listeners
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpcEnvelope {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    #[serde(flatten)]
+    pub request: IpcRequest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum IpcResponse {
+    Ok { result: serde_json::Value },
+    Error { message: String },
+}
+
+impl IpcResponse {
+    pub fn from_result<T: Serialize>(result: EngineResult<T>) -> Self {
+        match result.and_then(|v| serde_json::to_value(v).map_err(|e| EngineError(e.to_string()))) {
+            Ok(result) => IpcResponse::Ok { result },
+            Err(e) => IpcResponse::Error { message: e.0 },
+        }
+    }
+}
+
+/// Runs one control API call against an engine
+pub async fn dispatch(engine: &dyn FirewallEngine, request: IpcRequest) -> IpcResponse {
+    match request {
+        IpcRequest::Status => IpcResponse::from_result(engine.get_status().await),
+        IpcRequest::SetEnabled { enabled } => IpcResponse::from_result(engine.set_enabled(enabled).await),
+        IpcRequest::SetProfileEnabled { profile, enabled } => {
+            IpcResponse::from_result(engine.set_profile_enabled(profile, enabled).await)
+        }
+        IpcRequest::SetOutboundBlock { blocked } => IpcResponse::from_result(engine.set_outbound_block(blocked).await),
+        IpcRequest::SetEmergency { mode: None, reason } if reason.as_deref().is_none_or(|r| r.trim().is_empty()) => {
+            IpcResponse::Error { message: "Releasing an emergency mode is a break-glass action and needs a reason".to_string() }
+        }
+        IpcRequest::SetEmergency { mode, .. } => IpcResponse::from_result(engine.set_emergency(mode.as_ref()).await),
+        IpcRequest::ListRules => IpcResponse::from_result(engine.list_rules().await),
+        IpcRequest::RuleStats => IpcResponse::from_result(engine.rule_stats().await),
+        IpcRequest::ListNatRules => IpcResponse::from_result(engine.list_nat_rules().await),
+        IpcRequest::AddNatRule { rule } => IpcResponse::from_result(engine.add_nat_rule(&rule).await),
+        IpcRequest::RemoveNatRule { name } => IpcResponse::from_result(engine.remove_nat_rule(&name).await),
+        IpcRequest::ApplyPolicy { policy } => {
+            let allowed = SigningConfig::load_default().and_then(|config| config.allow_unsigned("Policy"));
+            match allowed {
+                Ok(_) => IpcResponse::from_result(engine.apply_policy(&policy).await),
+                Err(e) => IpcResponse::Error { message: format!("{}; send it as a signed document instead", e) },
+            }
+        }
+        IpcRequest::ApplySignedPolicy { signed } => {
+            let policy = SigningConfig::load_default().and_then(|config| signed.verified_policy(&config));
+            match policy {
+                Ok(policy) => IpcResponse::from_result(engine.apply_policy(&policy).await),
+                Err(e) => IpcResponse::Error { message: e.0 },
+            }
+        }
+        IpcRequest::Health | IpcRequest::Reload | IpcRequest::Workers => {
+            IpcResponse::Error { message: "This call is only served by aegisd".to_string() }
+        }
+    }
+}
+
+/// Answers control API calls; implementors forward engine calls to `dispatch`
+#[async_trait]
+pub trait ControlHandler: Send + Sync {
+    async fn handle(&self, request: IpcRequest) -> IpcResponse;
+
+    /// Like `handle`, for handlers that record who made the call
+    async fn handle_from(&self, caller: &Caller, request: IpcRequest) -> IpcResponse {
+        let _ = caller;
+        self.handle(request).await
+    }
+}
+
+/// Compares tokens without stopping at the first mismatching byte
+pub fn token_matches(expected: &str, given: &str) -> bool {
+    expected.len() == given.len() && expected.bytes().zip(given.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Serves newline-delimited JSON requests on one connection until the peer hangs up
+pub async fn serve_connection<S>(stream: S, handler: &dyn ControlHandler, token: Option<&str>, caller: &Caller) -> EngineResult<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (read, mut write) = tokio::io::split(stream);
+    let mut lines = BufReader::new(read).lines();
+    while let Some(line) = lines.next_line().await.map_err(|e| EngineError(format!("Control connection failed: {}", e)))? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<IpcEnvelope>(&line) {
+            Err(e) => IpcResponse::Error { message: format!("Invalid request: {}", e) },
+            Ok(envelope) => match token {
+                Some(expected) if !envelope.token.as_deref().is_some_and(|t| token_matches(expected, t)) => {
+                    IpcResponse::Error { message: "Unauthorized: missing or invalid token".to_string() }
+                }
+                _ => {
+                    let method = envelope.request.method();
+                    let started = Instant::now();
+                    let response = handler.handle_from(caller, envelope.request).await;
+                    let result = if matches!(response, IpcResponse::Ok { .. }) { "ok" } else { "error" };
+                    metrics::global().histogram_observe(
+                        "aegis_ipc_request_duration_seconds",
+                        "Control API call latency",
+                        metrics::labels(&[("method", method), ("result", result)]),
+                        started.elapsed().as_secs_f64(),
+                    );
+                    response
+                }
+            },
+        };
+        let mut out = serde_json::to_string(&response).map_err(|e| EngineError(e.to_string()))?;
+        out.push('\n');
+        write
+            .write_all(out.as_bytes())
+            .await
+            .map_err(|e| EngineError(format!("Control connection failed: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Where the aegisd control API listens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpcEndpoint {
+    Unix(PathBuf),
+    Tcp(String),
+}
+
+impl FromStr for IpcEndpoint {
+    type Err = String;
+
+    /// `tcp://host:port` selects TCP, anything else is a Unix socket path
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("tcp://") {
+            Some("") => Err("tcp:// endpoint needs a host:port".to_string()),
+            Some(addr) => Ok(IpcEndpoint::Tcp(addr.to_string())),
+            None if s.is_empty() => Err("Empty control endpoint".to_string()),
+            None => Ok(IpcEndpoint::Unix(PathBuf::from(s.strip_prefix("unix://").unwrap_or(s)))),
+        }
+    }
+}
+
+impl std::fmt::Display for IpcEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IpcEndpoint::Unix(path) => write!(f, "unix://{}", path.display()),
+            IpcEndpoint::Tcp(addr) => write!(f, "tcp://{}", addr),
+        }
+    }
+}
+
+/// `FirewallEngine` that forwards every call to a running aegisd
+pub struct IpcClient {
+    endpoint: IpcEndpoint,
+    token: Option<String>,
+}
+
+impl IpcClient {
+    pub fn new(endpoint: IpcEndpoint, token: Option<String>) -> Self {
+        Self { endpoint, token }
+    }
+
+    async fn exchange<S>(&self, stream: S, line: &str) -> EngineResult<String>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let (read, mut write) = tokio::io::split(stream);
+        write.write_all(line.as_bytes()).await.map_err(|e| EngineError(format!("Failed to send to aegisd: {}", e)))?;
+        let mut reply = String::new();
+        BufReader::new(read)
+            .read_line(&mut reply)
+            .await
+            .map_err(|e| EngineError(format!("Failed to read from aegisd: {}", e)))?;
+        if reply.is_empty() {
+            return Err(EngineError("aegisd closed the connection without replying".to_string()));
+        }
+        Ok(reply)
+    }
+
+    async fn round_trip(&self, line: &str) -> EngineResult<String> {
+        let connect_err = |e: std::io::Error| EngineError(format!("Cannot reach aegisd at {}: {}", self.endpoint, e));
+        match &self.endpoint {
+            IpcEndpoint::Tcp(addr) => self.exchange(tokio::net::TcpStream::connect(addr).await.map_err(connect_err)?, line).await,
+            #[cfg(unix)]
+            IpcEndpoint::Unix(path) => self.exchange(tokio::net::UnixStream::connect(path).await.map_err(connect_err)?, line).await,
+            #[cfg(not(unix))]
+            IpcEndpoint::Unix(_) => Err(EngineError("Unix socket endpoints are not supported on this platform".to_string())),
+        }
+    }
+
+    /// Sends one request and decodes its result, giving up after `CALL_TIMEOUT`
+    pub async fn call<T: DeserializeOwned>(&self, request: IpcRequest) -> EngineResult<T> {
+        let envelope = IpcEnvelope { token: self.token.clone(), request };
+        let mut line = serde_json::to_string(&envelope).map_err(|e| EngineError(e.to_string()))?;
+        line.push('\n');
+
+        let reply = tokio::time::timeout(CALL_TIMEOUT, self.round_trip(&line))
+            .await
+            .map_err(|_| EngineError(format!("aegisd at {} did not answer within {}s", self.endpoint, CALL_TIMEOUT.as_secs())))??;
+
+        match serde_json::from_str(&reply).map_err(|e| EngineError(format!("Invalid reply from aegisd: {}", e)))? {
+            IpcResponse::Ok { result } => {
+                serde_json::from_value(result).map_err(|e| EngineError(format!("Unexpected reply from aegisd: {}", e)))
+            }
+            IpcResponse::Error { message } => Err(EngineError(message)),
+        }
+    }
+
+    /// Engages or releases an emergency mode, passing the break-glass reason on to the daemon's audit log
+    pub async fn set_emergency_with_reason(&self, mode: Option<&EmergencyMode>, reason: Option<&str>) -> EngineResult<()> {
+        self.call(IpcRequest::SetEmergency { mode: mode.cloned(), reason: reason.map(str::to_string) }).await
+    }
+
+    /// Applies a policy file's contents, letting the daemon check the signature itself
+    pub async fn apply_signed_policy(&self, signed: SignedDocument) -> EngineResult<()> {
+        self.call(IpcRequest::ApplySignedPolicy { signed }).await
+    }
+}
+
+#[async_trait]
+impl FirewallEngine for IpcClient {
+    async fn get_status(&self) -> EngineResult<FirewallStatus> {
+        self.call(IpcRequest::Status).await
+    }
+
+    async fn set_enabled(&self, enabled: bool) -> EngineResult<()> {
+        self.call(IpcRequest::SetEnabled { enabled }).await
+    }
+
+    async fn set_profile_enabled(&self, profile: ProfileType, enabled: bool) -> EngineResult<()> {
+        self.call(IpcRequest::SetProfileEnabled { profile, enabled }).await
+    }
+
+    async fn set_outbound_block(&self, blocked: bool) -> EngineResult<()> {
+        self.call(IpcRequest::SetOutboundBlock { blocked }).await
+    }
+
+    async fn set_emergency(&self, mode: Option<&EmergencyMode>) -> EngineResult<()> {
+        self.call(IpcRequest::SetEmergency { mode: mode.cloned(), reason: None }).await
+    }
+
+    async fn list_rules(&self) -> EngineResult<Vec<FirewallRule>> {
+        self.call(IpcRequest::ListRules).await
+    }
+
+    async fn rule_stats(&self) -> EngineResult<Vec<RuleStats>> {
+        self.call(IpcRequest::RuleStats).await
+    }
+
+    async fn list_nat_rules(&self) -> EngineResult<Vec<NatRule>> {
+        self.call(IpcRequest::ListNatRules).await
+    }
+
+    async fn add_nat_rule(&self, rule: &NatRule) -> EngineResult<()> {
+        self.call(IpcRequest::AddNatRule { rule: rule.clone() }).await
+    }
+
+    async fn remove_nat_rule(&self, name: &str) -> EngineResult<()> {
+        self.call(IpcRequest::RemoveNatRule { name: name.to_string() }).await
+    }
+
+    async fn apply_policy(&self, policy: &FirewallPolicy) -> EngineResult<()> {
+        self.call(IpcRequest::ApplyPolicy { policy: policy.clone() }).await
+    }
+}
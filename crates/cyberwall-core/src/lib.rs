@@ -1,5 +1,15 @@
+pub mod audit;
+pub mod drift;
 pub mod engine;
+pub mod export;
+pub mod fleet;
+pub mod health;
+pub mod import;
+pub mod ipc;
+pub mod metrics;
 pub mod models;
+pub mod signing;
+pub mod state;
 
 pub use engine::{EngineError, EngineResult, FirewallEngine};
 pub use models::*;
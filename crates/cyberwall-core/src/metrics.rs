@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Gauge holding the unix time of each long-running subsystem's last heartbeat
+pub const HEARTBEAT_METRIC: &str = "cyberwall_subsystem_heartbeat_timestamp_seconds";
+
+/// How often serving subsystems write their heartbeat; aegisd reports them down after three missed beats
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Upper bounds, in seconds, of the request latency histogram buckets
+pub const LATENCY_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl MetricKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Histogram => "histogram",
+        }
+    }
+}
+
+/// Label pairs, kept sorted so equal sets name the same series
+pub type Labels = Vec<(String, String)>;
+
+pub fn labels(pairs: &[(&str, &str)]) -> Labels {
+    let mut labels: Labels = pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    labels.sort();
+    labels
+}
+
+#[derive(Debug, Clone)]
+enum Series {
+    Value(f64),
+    Histogram { counts: Vec<u64>, sum: f64, count: u64 },
+}
+
+#[derive(Debug, Clone)]
+struct Family {
+    help: String,
+    kind: MetricKind,
+    series: BTreeMap<Labels, Series>,
+}
+
+/// Metric families of one process, rendered in the Prometheus text format
+#[derive(Default)]
+pub struct MetricsRegistry {
+    families: Mutex<BTreeMap<String, Family>>,
+}
+
+/// Registry shared by everything in this process, e.g. the control API latency
+pub fn global() -> &'static MetricsRegistry {
+    static GLOBAL: OnceLock<MetricsRegistry> = OnceLock::new();
+    GLOBAL.get_or_init(MetricsRegistry::default)
+}
+
+impl MetricsRegistry {
+    fn with_series(&self, name: &str, help: &str, kind: MetricKind, labels: Labels, f: impl FnOnce(&mut Series)) {
+        let mut families = self.families.lock().unwrap_or_else(|p| p.into_inner());
+        let family = families.entry(name.to_string()).or_insert_with(|| Family { help: help.to_string(), kind, series: BTreeMap::new() });
+        let series = family.series.entry(labels).or_insert_with(|| match kind {
+            MetricKind::Histogram => Series::Histogram { counts: vec![0; LATENCY_BUCKETS.len()], sum: 0.0, count: 0 },
+            _ => Series::Value(0.0),
+        });
+        f(series);
+    }
+
+    pub fn counter_add(&self, name: &str, help: &str, labels: Labels, by: f64) {
+        self.with_series(name, help, MetricKind::Counter, labels, |s| {
+            if let Series::Value(v) = s {
+                *v += by;
+            }
+        });
+    }
+
+    pub fn gauge_set(&self, name: &str, help: &str, labels: Labels, value: f64) {
+        self.with_series(name, help, MetricKind::Gauge, labels, |s| *s = Series::Value(value));
+    }
+
+    /// Records one observation in a histogram with `LATENCY_BUCKETS`
+    pub fn histogram_observe(&self, name: &str, help: &str, labels: Labels, value: f64) {
+        self.with_series(name, help, MetricKind::Histogram, labels, |s| {
+            if let Series::Histogram { counts, sum, count } = s {
+                for (bucket, bound) in counts.iter_mut().zip(LATENCY_BUCKETS) {
+                    if value <= *bound {
+                        *bucket += 1;
+                    }
+                }
+                *sum += value;
+                *count += 1;
+            }
+        });
+    }
+
+    /// Adds samples persisted by other processes
+    pub fn absorb(&self, samples: &[StoredMetric]) {
+        for sample in samples {
+            match sample.kind {
+                MetricKind::Counter => self.counter_add(&sample.name, &sample.help, sample.labels.clone(), sample.value),
+                _ => self.gauge_set(&sample.name, &sample.help, sample.labels.clone(), sample.value),
+            }
+        }
+    }
+
+    /// Prometheus text exposition format, version 0.0.4
+    pub fn render(&self) -> String {
+        let families = self.families.lock().unwrap_or_else(|p| p.into_inner());
+        let mut out = String::new();
+        for (name, family) in families.iter() {
+            let _ = writeln!(out, "# HELP {} {}", name, family.help.replace('\\', "\\\\").replace('\n', "\\n"));
+            let _ = writeln!(out, "# TYPE {} {}", name, family.kind.as_str());
+            for (labels, series) in &family.series {
+                match series {
+                    Series::Value(v) => {
+                        let _ = writeln!(out, "{}{} {}", name, render_labels(labels, None), v);
+                    }
+                    Series::Histogram { counts, sum, count } => {
+                        for (bucket, bound) in counts.iter().zip(LATENCY_BUCKETS) {
+                            let _ = writeln!(out, "{}_bucket{} {}", name, render_labels(labels, Some(&bound.to_string())), bucket);
+                        }
+                        let _ = writeln!(out, "{}_bucket{} {}", name, render_labels(labels, Some("+Inf")), count);
+                        let _ = writeln!(out, "{}_sum{} {}", name, render_labels(labels, None), sum);
+                        let _ = writeln!(out, "{}_count{} {}", name, render_labels(labels, None), count);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+fn render_labels(labels: &Labels, le: Option<&str>) -> String {
+    let escape = |v: &str| v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    let mut pairs: Vec<String> = labels.iter().map(|(k, v)| format!("{}=\"{}\"", k, escape(v))).collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+    match pairs.is_empty() {
+        true => String::new(),
+        false => format!("{{{}}}", pairs.join(",")),
+    }
+}
+
+/// Counter or gauge a subsystem process keeps in the state store for aegisd to export
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredMetric {
+    pub name: String,
+    pub help: String,
+    pub kind: MetricKind,
+    pub labels: Labels,
+    pub value: f64,
+    pub updated_at: u64,
+}
@@ -1,4 +1,7 @@
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProfileType {
@@ -8,6 +11,30 @@ pub enum ProfileType {
     All,
 }
 
+impl ProfileType {
+    /// The concrete profiles, in the order they are reported
+    pub const CONCRETE: [ProfileType; 3] = [ProfileType::Domain, ProfileType::Private, ProfileType::Public];
+
+    /// Whether a rule or setting scoped to `self` applies to `other`
+    pub fn covers(self, other: ProfileType) -> bool {
+        self == ProfileType::All || self == other
+    }
+}
+
+impl FromStr for ProfileType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "private" => Ok(ProfileType::Private),
+            "public" => Ok(ProfileType::Public),
+            "domain" => Ok(ProfileType::Domain),
+            "all" | "any" => Ok(ProfileType::All),
+            other => Err(format!("Unknown profile '{}' (expected private, public, domain or all)", other)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum RuleAction {
     Allow,
@@ -20,19 +47,299 @@ pub enum RuleDirection {
     Outbound,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AddressFamily {
+    /// Rule applies to both IPv4 and IPv6 traffic
+    #[default]
+    Any,
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    pub fn covers(self, other: AddressFamily) -> bool {
+        self == AddressFamily::Any || self == other
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+    Icmp,
+    IcmpV6,
+}
+
+impl FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "tcp" | "6" => Ok(Protocol::Tcp),
+            "udp" | "17" => Ok(Protocol::Udp),
+            "icmp" | "1" => Ok(Protocol::Icmp),
+            "icmpv6" | "ipv6-icmp" | "58" => Ok(Protocol::IcmpV6),
+            other => Err(format!("Unknown protocol '{}'", other)),
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+            Protocol::Icmp => "icmp",
+            Protocol::IcmpV6 => "icmpv6",
+        })
+    }
+}
+
+/// Inclusive port range; a single port has `start == end`. Serialized as "80" or "1000-2000"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PortRange {
+    pub fn single(port: u16) -> Self {
+        Self { start: port, end: port }
+    }
+}
+
+impl FromStr for PortRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (start, end) = s.split_once(['-', ':']).unwrap_or((s, s));
+        let parse = |p: &str| p.trim().parse::<u16>().map_err(|_| format!("Invalid port '{}'", s));
+        let range = PortRange { start: parse(start)?, end: parse(end)? };
+        if range.start > range.end {
+            return Err(format!("Invalid port range '{}'", s));
+        }
+        Ok(range)
+    }
+}
+
+impl TryFrom<String> for PortRange {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<PortRange> for String {
+    fn from(range: PortRange) -> Self {
+        range.to_string()
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NatKind {
+    /// Rewrite the source address to a fixed address
+    Snat,
+    /// Rewrite the destination address/port (port forwarding)
+    Dnat,
+    /// Rewrite the source address to the outgoing interface address
+    Masquerade,
+    /// Rewrite the destination to a local port on this host
+    Redirect,
+}
+
+impl FromStr for NatKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "snat" => Ok(NatKind::Snat),
+            "dnat" | "forward" | "port-forward" => Ok(NatKind::Dnat),
+            "masquerade" | "masq" => Ok(NatKind::Masquerade),
+            "redirect" => Ok(NatKind::Redirect),
+            other => Err(format!("Unknown NAT type '{}' (expected snat, dnat, masquerade or redirect)", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NatRule {
+    pub name: String,
+    pub enabled: bool,
+    pub kind: NatKind,
+    pub protocol: Option<Protocol>,
+    pub in_interface: Option<String>,
+    pub out_interface: Option<String>,
+    /// Source address or CIDR to match
+    pub source: Option<String>,
+    /// Destination address or CIDR to match
+    pub destination: Option<String>,
+    pub destination_port: Option<u16>,
+    pub to_address: Option<IpAddr>,
+    pub to_port: Option<u16>,
+}
+
+/// Validates an address or CIDR and returns its family
+pub fn cidr_family(value: &str) -> Result<AddressFamily, String> {
+    let (addr, prefix) = value.split_once('/').unwrap_or((value, ""));
+    let addr: IpAddr = addr.parse().map_err(|_| format!("Invalid address '{}'", value))?;
+    let max = if addr.is_ipv4() { 32 } else { 128 };
+    if !prefix.is_empty() && !prefix.parse::<u8>().is_ok_and(|p| p <= max) {
+        return Err(format!("Invalid prefix length in '{}'", value));
+    }
+    Ok(if addr.is_ipv4() { AddressFamily::Ipv4 } else { AddressFamily::Ipv6 })
+}
+
+impl NatRule {
+    /// Checks that the rule carries the fields its NAT type needs
+    pub fn validate(&self) -> Result<(), String> {
+        match self.kind {
+            NatKind::Snat | NatKind::Dnat if self.to_address.is_none() => {
+                return Err(format!("NAT rule '{}': {:?} requires a target address", self.name, self.kind));
+            }
+            NatKind::Redirect if self.to_port.is_none() => {
+                return Err(format!("NAT rule '{}': Redirect requires a target port", self.name));
+            }
+            NatKind::Masquerade if self.to_address.is_some() => {
+                return Err(format!("NAT rule '{}': Masquerade cannot take a target address", self.name));
+            }
+            _ => {}
+        }
+        if (self.destination_port.is_some() || self.to_port.is_some())
+            && !matches!(self.protocol, Some(Protocol::Tcp) | Some(Protocol::Udp))
+        {
+            return Err(format!("NAT rule '{}': ports require protocol tcp or udp", self.name));
+        }
+        for cidr in self.source.iter().chain(self.destination.iter()) {
+            cidr_family(cidr).map_err(|e| format!("NAT rule '{}': {}", self.name, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// State of one firewall profile (a Windows network profile, or a Linux interface zone)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileStatus {
+    pub profile: ProfileType,
+    pub enabled: bool,
+    /// Action for inbound traffic no rule matched
+    pub default_inbound: RuleAction,
+    /// Action for outbound traffic no rule matched
+    pub default_outbound: RuleAction,
+    pub log_allowed: bool,
+    pub log_dropped: bool,
+    /// Where the backend writes connection logs for this profile, if anywhere
+    pub log_target: Option<String>,
+    /// Interfaces bound to the profile; empty where the OS assigns profiles itself
+    #[serde(default)]
+    pub interfaces: Vec<String>,
+}
+
+/// Peer kept reachable in an emergency mode: an address or CIDR, optionally limited to one service port
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmergencyPeer {
+    pub address: String,
+    /// Port of the service, on whichever side of the connection it runs
+    pub port: Option<u16>,
+}
+
+impl FromStr for EmergencyPeer {
+    type Err = String;
+
+    /// `10.0.0.0/24`, `10.0.0.5:22`, `fd00::/64` or `[fd00::5]:22`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, port) = match s.strip_prefix('[') {
+            Some(rest) => match rest.split_once("]:") {
+                Some((address, port)) => (address, Some(port)),
+                None => (rest.strip_suffix(']').ok_or_else(|| format!("Unclosed '[' in '{}'", s))?, None),
+            },
+            None if s.matches(':').count() == 1 => s.split_once(':').map(|(a, p)| (a, Some(p))).unwrap_or((s, None)),
+            None => (s, None),
+        };
+        cidr_family(address)?;
+        let port = match port {
+            Some(p) => Some(p.parse::<u16>().ok().filter(|p| *p != 0).ok_or_else(|| format!("Invalid port in '{}'", s))?),
+            None => None,
+        };
+        Ok(EmergencyPeer { address: address.to_string(), port })
+    }
+}
+
+impl fmt::Display for EmergencyPeer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.port, self.address.contains(':')) {
+            (Some(port), true) => write!(f, "[{}]:{}", self.address, port),
+            (Some(port), false) => write!(f, "{}:{}", self.address, port),
+            (None, _) => write!(f, "{}", self.address),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmergencyKind {
+    Isolate,
+    Quarantine,
+}
+
+/// Lockdown that overrides the active policy until it is released
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum EmergencyMode {
+    /// All traffic dropped in both directions except loopback and the management channel
+    Isolate { management: Vec<EmergencyPeer> },
+    /// Only outbound connections to the remediation servers; nothing may connect in
+    Quarantine { remediation: Vec<EmergencyPeer> },
+}
+
+impl EmergencyMode {
+    pub fn kind(&self) -> EmergencyKind {
+        match self {
+            EmergencyMode::Isolate { .. } => EmergencyKind::Isolate,
+            EmergencyMode::Quarantine { .. } => EmergencyKind::Quarantine,
+        }
+    }
+
+    pub fn peers(&self) -> &[EmergencyPeer] {
+        match self {
+            EmergencyMode::Isolate { management } => management,
+            EmergencyMode::Quarantine { remediation } => remediation,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FirewallStatus {
     pub enabled: bool,
     pub outbound_blocked: bool,
     pub defender_active: bool,
-    pub profile_private: bool,
-    pub profile_public: bool,
-    pub profile_domain: bool,
+    pub profiles: Vec<ProfileStatus>,
     pub platform: String,
     pub backend_driver: String,
+    /// Emergency mode in force, if any
+    #[serde(default)]
+    pub emergency: Option<EmergencyKind>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl FirewallStatus {
+    pub fn profile(&self, profile: ProfileType) -> Option<&ProfileStatus> {
+        self.profiles.iter().find(|p| p.profile == profile)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FirewallRule {
     pub name: String,
     pub enabled: bool,
@@ -40,11 +347,86 @@ pub struct FirewallRule {
     pub direction: RuleDirection,
     pub profile: ProfileType,
     pub application: Option<String>,
+    #[serde(default)]
+    pub family: AddressFamily,
+    #[serde(default)]
+    pub protocol: Option<Protocol>,
+    /// Addresses or CIDRs on this host's side of the connection; empty means any
+    #[serde(default)]
+    pub local_addresses: Vec<String>,
+    /// Addresses or CIDRs of the peer; empty means any
+    #[serde(default)]
+    pub remote_addresses: Vec<String>,
+    #[serde(default)]
+    pub local_ports: Vec<PortRange>,
+    #[serde(default)]
+    pub remote_ports: Vec<PortRange>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl FirewallRule {
+    /// Creates an enabled rule matching all traffic in one direction on every profile
+    pub fn new(name: impl Into<String>, action: RuleAction, direction: RuleDirection) -> Self {
+        Self {
+            name: name.into(),
+            enabled: true,
+            action,
+            direction,
+            profile: ProfileType::All,
+            application: None,
+            family: AddressFamily::Any,
+            protocol: None,
+            local_addresses: Vec::new(),
+            remote_addresses: Vec::new(),
+            local_ports: Vec::new(),
+            remote_ports: Vec::new(),
+        }
+    }
+
+    /// Checks address/family consistency and that ports come with a port-bearing protocol
+    pub fn validate(&self) -> Result<(), String> {
+        for addr in self.local_addresses.iter().chain(self.remote_addresses.iter()) {
+            let family = cidr_family(addr).map_err(|e| format!("Rule '{}': {}", self.name, e))?;
+            if !self.family.covers(family) {
+                return Err(format!("Rule '{}': address {} does not match family {:?}", self.name, addr, self.family));
+            }
+        }
+        let has_ports = !self.local_ports.is_empty() || !self.remote_ports.is_empty();
+        if has_ports && !matches!(self.protocol, None | Some(Protocol::Tcp) | Some(Protocol::Udp)) {
+            return Err(format!("Rule '{}': ports require protocol tcp or udp", self.name));
+        }
+        match self.protocol {
+            Some(Protocol::Icmp) if self.family == AddressFamily::Ipv6 => {
+                Err(format!("Rule '{}': ICMP (v4) cannot match an IPv6-only rule", self.name))
+            }
+            Some(Protocol::IcmpV6) if self.family == AddressFamily::Ipv4 => {
+                Err(format!("Rule '{}': ICMPv6 cannot match an IPv4-only rule", self.name))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FirewallPolicy {
     pub name: String,
     pub version: String,
     pub rules: Vec<FirewallRule>,
+    #[serde(default)]
+    pub nat_rules: Vec<NatRule>,
+    /// Keeps ICMPv6 neighbor discovery open ahead of all rules; IPv6 breaks without it
+    #[serde(default = "default_true")]
+    pub allow_neighbor_discovery: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleStats {
+    pub name: String,
+    pub packets: u64,
+    pub bytes: u64,
+    /// Unix timestamp (seconds) of the most recent hit, if the backend tracks it
+    pub last_hit: Option<u64>,
 }
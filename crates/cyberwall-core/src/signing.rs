@@ -0,0 +1,413 @@
+use crate::engine::{EngineError, EngineResult};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine as _;
+use ed25519_dalek::{Signer, Verifier};
+pub use ed25519_dalek::{SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Detached Ed25519 signature and the id of the key that made it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Signature {
+    pub key_id: String,
+    /// Base64 signature bytes
+    pub value: String,
+}
+
+/// Short, stable name for a public key: the first 16 hex digits of its SHA-256
+pub fn key_id(key: &VerifyingKey) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))[..16].to_string()
+}
+
+pub fn generate_key() -> SigningKey {
+    SigningKey::generate(&mut rand::rngs::OsRng)
+}
+
+pub fn encode_secret(key: &SigningKey) -> String {
+    BASE64.encode(key.to_bytes())
+}
+
+pub fn encode_public(key: &VerifyingKey) -> String {
+    BASE64.encode(key.as_bytes())
+}
+
+fn decode_32(raw: &str, what: &str) -> EngineResult<[u8; 32]> {
+    let bytes = BASE64.decode(raw.trim()).map_err(|e| EngineError(format!("Invalid {}: {}", what, e)))?;
+    bytes.try_into().map_err(|_| EngineError(format!("Invalid {}: expected 32 bytes", what)))
+}
+
+pub fn decode_secret(raw: &str) -> EngineResult<SigningKey> {
+    Ok(SigningKey::from_bytes(&decode_32(raw, "signing key")?))
+}
+
+pub fn decode_public(raw: &str) -> EngineResult<VerifyingKey> {
+    VerifyingKey::from_bytes(&decode_32(raw, "public key")?).map_err(|e| EngineError(format!("Invalid public key: {}", e)))
+}
+
+pub fn load_secret(path: &Path) -> EngineResult<SigningKey> {
+    let raw = std::fs::read_to_string(path).map_err(|e| EngineError(format!("Failed to read {}: {}", path.display(), e)))?;
+    decode_secret(&raw).map_err(|e| EngineError(format!("{}: {}", path.display(), e)))
+}
+
+pub fn load_public(path: &Path) -> EngineResult<VerifyingKey> {
+    let raw = std::fs::read_to_string(path).map_err(|e| EngineError(format!("Failed to read {}: {}", path.display(), e)))?;
+    decode_public(&raw).map_err(|e| EngineError(format!("{}: {}", path.display(), e)))
+}
+
+/// Writes a new signing key (owner-only) and its public half next to it as `<path>.pub`
+pub fn write_keypair(path: &Path, key: &SigningKey) -> EngineResult<()> {
+    let write = |path: &Path, content: String, private: bool| -> std::io::Result<()> {
+        use std::io::Write;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, if private { 0o600 } else { 0o644 });
+        #[cfg(not(unix))]
+        let _ = private;
+        options.open(path)?.write_all(content.as_bytes())
+    };
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| EngineError(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    let public = path.with_extension("pub");
+    write(path, encode_secret(key) + "\n", true).map_err(|e| EngineError(format!("Failed to write {}: {}", path.display(), e)))?;
+    write(&public, encode_public(&key.verifying_key()) + "\n", false).map_err(|e| EngineError(format!("Failed to write {}: {}", public.display(), e)))
+}
+
+pub fn sign(key: &SigningKey, message: &[u8]) -> Signature {
+    Signature { key_id: key_id(&key.verifying_key()), value: BASE64.encode(key.sign(message).to_bytes()) }
+}
+
+pub fn verify(key: &VerifyingKey, message: &[u8], signature: &Signature) -> EngineResult<()> {
+    if signature.key_id != key_id(key) {
+        return Err(EngineError(format!("Signed by unknown key {} (expected {})", signature.key_id, key_id(key))));
+    }
+    let bytes: [u8; 64] = BASE64
+        .decode(&signature.value)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| EngineError("Malformed signature".to_string()))?;
+    key.verify(message, &ed25519_dalek::Signature::from_bytes(&bytes))
+        .map_err(|_| EngineError(format!("Signature by key {} does not match the signed content", signature.key_id)))
+}
+
+/// Directory of public keys whose signatures are accepted, one `<name>.pub` per key
+pub const TRUSTED_KEYS_DIR: &str = "/etc/cyberwall/trusted-keys";
+pub const SIGNING_CONFIG_PATH: &str = "/etc/cyberwall/signing.json";
+/// Overrides the location of the signing config
+pub const SIGNING_CONFIG_ENV: &str = "CYBERWALL_SIGNING_CONFIG";
+
+/// Detached signature of a document, kept next to it as `<file>.sig`
+pub fn signature_path(file: &Path) -> std::path::PathBuf {
+    let mut name = file.as_os_str().to_owned();
+    name.push(".sig");
+    name.into()
+}
+
+/// Signs the file's exact bytes and writes the signature to `<file>.sig`
+pub fn sign_file(key: &SigningKey, file: &Path) -> EngineResult<Signature> {
+    let bytes = std::fs::read(file).map_err(|e| EngineError(format!("Failed to read {}: {}", file.display(), e)))?;
+    let signature = sign(key, &bytes);
+    let path = signature_path(file);
+    let raw = serde_json::to_string_pretty(&signature).map_err(|e| EngineError(format!("Failed to encode signature: {}", e)))?;
+    std::fs::write(&path, raw + "\n").map_err(|e| EngineError(format!("Failed to write {}: {}", path.display(), e)))?;
+    Ok(signature)
+}
+
+/// Reads `<file>.sig`, or `None` when the file was never signed
+pub fn read_signature(file: &Path) -> EngineResult<Option<Signature>> {
+    let path = signature_path(file);
+    match std::fs::read_to_string(&path) {
+        Ok(raw) => serde_json::from_str(&raw).map(Some).map_err(|e| EngineError(format!("{} is not a signature: {}", path.display(), e))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(EngineError(format!("Failed to read {}: {}", path.display(), e))),
+    }
+}
+
+/// Document sent over the control API together with its detached signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedDocument {
+    /// Exact bytes that were signed, as UTF-8
+    pub document: String,
+    pub signature: Signature,
+}
+
+impl SignedDocument {
+    /// Checks the signature against the trusted keys and parses the document as a policy
+    pub fn verified_policy(&self, config: &SigningConfig) -> EngineResult<crate::models::FirewallPolicy> {
+        config.check("Policy", self.document.as_bytes(), Some(&self.signature))?;
+        serde_json::from_str(&self.document).map_err(|e| EngineError(format!("Signed document is not a firewall policy: {}", e)))
+    }
+}
+
+fn parse_time(raw: &str) -> Option<u64> {
+    raw.parse().ok().or_else(|| chrono::DateTime::parse_from_rfc3339(raw).ok().and_then(|t| u64::try_from(t.timestamp()).ok()))
+}
+
+/// Public key accepted for a window of time; windows of successive keys overlap during rotation
+#[derive(Debug, Clone)]
+pub struct TrustedKey {
+    /// File stem in the trusted-keys directory
+    pub name: String,
+    pub key: VerifyingKey,
+    pub not_before: Option<u64>,
+    pub not_after: Option<u64>,
+}
+
+impl TrustedKey {
+    /// Parses a key file: `#` comments, optional `not_before = ` / `not_after = ` lines
+    /// (unix seconds or RFC 3339) and the base64 public key
+    pub fn parse(name: &str, raw: &str) -> EngineResult<Self> {
+        let (mut key, mut not_before, mut not_after) = (None, None, None);
+        for line in raw.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            match line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                Some(("not_before", v)) => not_before = Some(parse_time(v).ok_or_else(|| EngineError(format!("Invalid not_before '{}'", v)))?),
+                Some(("not_after", v)) => not_after = Some(parse_time(v).ok_or_else(|| EngineError(format!("Invalid not_after '{}'", v)))?),
+                // Base64 keys end in '=' padding, so anything else is the key itself
+                _ if key.is_none() => key = Some(decode_public(line)?),
+                _ => return Err(EngineError(format!("Unexpected line '{}'", line))),
+            }
+        }
+        let key = key.ok_or_else(|| EngineError("No public key in file".to_string()))?;
+        Ok(Self { name: name.to_string(), key, not_before, not_after })
+    }
+
+    pub fn key_id(&self) -> String {
+        key_id(&self.key)
+    }
+
+    /// Why the key may not be used at `now`, if it may not
+    pub fn outside_window(&self, now: u64) -> Option<String> {
+        match (self.not_before, self.not_after) {
+            (Some(from), _) if now < from => Some(format!("key '{}' is not valid before {}", self.name, from)),
+            (_, Some(until)) if now > until => Some(format!("key '{}' was retired at {}", self.name, until)),
+            _ => None,
+        }
+    }
+}
+
+/// Keys loaded from the trusted-keys directory
+#[derive(Debug, Clone, Default)]
+pub struct TrustStore {
+    pub keys: Vec<TrustedKey>,
+}
+
+impl TrustStore {
+    /// Loads every `*.pub` file in `dir`; a missing directory trusts nothing
+    pub fn load(dir: &Path) -> EngineResult<Self> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(EngineError(format!("Failed to read {}: {}", dir.display(), e))),
+        };
+        let mut keys = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("pub") {
+                continue;
+            }
+            let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let raw = std::fs::read_to_string(&path).map_err(|e| EngineError(format!("Failed to read {}: {}", path.display(), e)))?;
+            keys.push(TrustedKey::parse(&name, &raw).map_err(|e| EngineError(format!("{}: {}", path.display(), e)))?);
+        }
+        keys.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Self { keys })
+    }
+
+    /// Checks `signature` against the trusted key it names, which must be inside its window at `now`
+    pub fn verify(&self, message: &[u8], signature: &Signature, now: u64) -> EngineResult<&TrustedKey> {
+        let trusted = self
+            .keys
+            .iter()
+            .find(|k| k.key_id() == signature.key_id)
+            .ok_or_else(|| EngineError(format!("Signed by key {}, which is not in the trusted keys", signature.key_id)))?;
+        if let Some(reason) = trusted.outside_window(now) {
+            return Err(EngineError(format!("Signature rejected: {}", reason)));
+        }
+        verify(&trusted.key, message, signature)?;
+        Ok(trusted)
+    }
+}
+
+/// Whether unsigned documents are refused, and where trusted keys live
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigningConfig {
+    #[serde(default)]
+    pub enforce: bool,
+    #[serde(default = "default_trusted_keys")]
+    pub trusted_keys: std::path::PathBuf,
+}
+
+fn default_trusted_keys() -> std::path::PathBuf {
+    TRUSTED_KEYS_DIR.into()
+}
+
+impl Default for SigningConfig {
+    fn default() -> Self {
+        Self { enforce: false, trusted_keys: default_trusted_keys() }
+    }
+}
+
+impl SigningConfig {
+    /// Reads `$CYBERWALL_SIGNING_CONFIG` or the system config; without one, enforcement is off
+    pub fn load_default() -> EngineResult<Self> {
+        let path = std::env::var(SIGNING_CONFIG_ENV).unwrap_or_else(|_| SIGNING_CONFIG_PATH.to_string());
+        Self::load(Path::new(&path))
+    }
+
+    pub fn load(path: &Path) -> EngineResult<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(raw) => serde_json::from_str(&raw).map_err(|e| EngineError(format!("{}: invalid signing config: {}", path.display(), e))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(EngineError(format!("Failed to read {}: {}", path.display(), e))),
+        }
+    }
+
+    /// Trusted keys as they are on disk right now, so rotations apply without a restart
+    pub fn trust_store(&self) -> EngineResult<TrustStore> {
+        TrustStore::load(&self.trusted_keys)
+    }
+
+    /// Checks a document before it is applied. A signature that is present must always verify;
+    /// a missing one is refused only in enforcement mode. Returns the key that signed it, if any.
+    pub fn check(&self, what: &str, document: &[u8], signature: Option<&Signature>) -> EngineResult<Option<TrustedKey>> {
+        match signature {
+            Some(signature) => {
+                let store = self.trust_store()?;
+                let key = store.verify(document, signature, crate::health::unix_now()).map_err(|e| EngineError(format!("{}: {}", what, e)))?;
+                Ok(Some(key.clone()))
+            }
+            None => self.allow_unsigned(what).map(|_| None),
+        }
+    }
+
+    /// Refuses an unsigned document in enforcement mode
+    pub fn allow_unsigned(&self, what: &str) -> EngineResult<()> {
+        match self.enforce {
+            true => Err(EngineError(format!("{} is not signed and policy signing is enforced", what))),
+            false => Ok(()),
+        }
+    }
+
+    /// Reads a file and checks it against `<file>.sig`
+    pub fn read_verified(&self, file: &Path) -> EngineResult<(Vec<u8>, Option<TrustedKey>)> {
+        let bytes = std::fs::read(file).map_err(|e| EngineError(format!("Failed to read {}: {}", file.display(), e)))?;
+        let key = self.check(&file.display().to_string(), &bytes, read_signature(file)?.as_ref())?;
+        Ok((bytes, key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_790_000_000;
+    const DAY: u64 = 86_400;
+
+    struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        fn new(tag: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("cyberwall-signing-{}-{}", tag, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn trusted(name: &str, seed: u8, not_before: Option<u64>, not_after: Option<u64>) -> TrustedKey {
+        TrustedKey { name: name.to_string(), key: key(seed).verifying_key(), not_before, not_after }
+    }
+
+    fn rejection(store: &TrustStore, signer: u8, now: u64) -> String {
+        store.verify(b"policy", &sign(&key(signer), b"policy"), now).unwrap_err().0
+    }
+
+    #[test]
+    fn overlapping_rotation_windows_accept_both_keys() {
+        let store = TrustStore {
+            keys: vec![trusted("2026-q3", 1, Some(NOW - 90 * DAY), Some(NOW + DAY)), trusted("2026-q4", 2, Some(NOW - DAY), None)],
+        };
+        assert_eq!(store.verify(b"policy", &sign(&key(1), b"policy"), NOW).unwrap().name, "2026-q3");
+        assert_eq!(store.verify(b"policy", &sign(&key(2), b"policy"), NOW).unwrap().name, "2026-q4");
+        // Once the overlap ends only the new key is accepted
+        assert!(rejection(&store, 1, NOW + 2 * DAY).contains("key '2026-q3' was retired"));
+        assert!(store.verify(b"policy", &sign(&key(2), b"policy"), NOW + 2 * DAY).is_ok());
+    }
+
+    #[test]
+    fn retired_and_future_keys_are_refused() {
+        let store = TrustStore { keys: vec![trusted("old", 1, None, Some(NOW - 1)), trusted("next", 2, Some(NOW + 1), None)] };
+        assert_eq!(rejection(&store, 1, NOW), format!("Signature rejected: key 'old' was retired at {}", NOW - 1));
+        assert_eq!(rejection(&store, 2, NOW), format!("Signature rejected: key 'next' is not valid before {}", NOW + 1));
+        // Window bounds are inclusive
+        assert!(store.verify(b"policy", &sign(&key(1), b"policy"), NOW - 1).is_ok());
+        assert!(store.verify(b"policy", &sign(&key(2), b"policy"), NOW + 1).is_ok());
+    }
+
+    #[test]
+    fn wrong_key_and_tampered_content_are_refused() {
+        let store = TrustStore { keys: vec![trusted("ops", 1, None, None)] };
+        assert!(rejection(&store, 3, NOW).contains("which is not in the trusted keys"));
+
+        // A signature made by another key but labelled with the trusted key's id
+        let forged = Signature { key_id: key_id(&key(1).verifying_key()), ..sign(&key(3), b"policy") };
+        assert!(store.verify(b"policy", &forged, NOW).unwrap_err().0.contains("does not match the signed content"));
+
+        let signature = sign(&key(1), b"policy");
+        assert!(store.verify(b"policy!", &signature, NOW).is_err());
+        let garbled = Signature { value: "not base64".to_string(), ..signature };
+        assert_eq!(store.verify(b"policy", &garbled, NOW).unwrap_err().0, "Malformed signature");
+    }
+
+    #[test]
+    fn key_files_parse_windows_in_either_format() {
+        let raw = format!("# rotated in Q4\nnot_before = 2026-10-01T00:00:00Z\nnot_after = {}\n{}\n", NOW, encode_public(&key(1).verifying_key()));
+        let parsed = TrustedKey::parse("q4", &raw).unwrap();
+        assert_eq!((parsed.not_before, parsed.not_after), (Some(1_790_812_800), Some(NOW)));
+        assert_eq!(parsed.key_id(), key_id(&key(1).verifying_key()));
+        assert!(TrustedKey::parse("bad", "not_after = someday\n").is_err());
+        assert!(TrustedKey::parse("empty", "# nothing\n").is_err());
+    }
+
+    #[test]
+    fn enforcement_refuses_unsigned_documents() {
+        let dir = TempDir::new("enforce");
+        let keys = dir.0.join("trusted-keys");
+        std::fs::create_dir_all(&keys).unwrap();
+        std::fs::write(keys.join("ops.pub"), encode_public(&key(1).verifying_key()) + "\n").unwrap();
+        let enforcing = SigningConfig { enforce: true, trusted_keys: keys.clone() };
+        let lenient = SigningConfig { enforce: false, trusted_keys: keys };
+
+        assert_eq!(enforcing.allow_unsigned("Manifest").unwrap_err().0, "Manifest is not signed and policy signing is enforced");
+        assert!(lenient.allow_unsigned("Manifest").is_ok());
+
+        let manifest = dir.0.join("policy.json");
+        std::fs::write(&manifest, b"{\"revision\":\"1\"}").unwrap();
+        assert!(enforcing.read_verified(&manifest).unwrap_err().0.contains("is not signed"));
+        let (bytes, signer) = lenient.read_verified(&manifest).unwrap();
+        assert_eq!((bytes.as_slice(), signer.is_none()), (b"{\"revision\":\"1\"}".as_slice(), true));
+
+        sign_file(&key(1), &manifest).unwrap();
+        let (bytes, signer) = enforcing.read_verified(&manifest).unwrap();
+        assert_eq!((bytes.as_slice(), signer.map(|k| k.name)), (b"{\"revision\":\"1\"}".as_slice(), Some("ops".to_string())));
+
+        // A present signature must verify even when enforcement is off
+        std::fs::write(&manifest, b"{\"revision\":\"2\"}").unwrap();
+        assert!(enforcing.read_verified(&manifest).is_err());
+        assert!(lenient.read_verified(&manifest).unwrap_err().0.contains("does not match the signed content"));
+        sign_file(&key(3), &manifest).unwrap();
+        assert!(lenient.read_verified(&manifest).unwrap_err().0.contains("not in the trusted keys"));
+    }
+}
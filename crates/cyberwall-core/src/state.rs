@@ -0,0 +1,845 @@
+use crate::audit::{self, ChainReport};
+use crate::engine::{EngineError, EngineResult};
+use crate::health::unix_now;
+use crate::metrics::{self, Labels, MetricKind, StoredMetric, HEARTBEAT_METRIC};
+use crate::models::{FirewallPolicy, FirewallRule};
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Default location of the shared state database
+pub const DEFAULT_STATE_PATH: &str = "/var/lib/aegisd/state.db";
+
+/// Overrides the database location for every subsystem
+pub const STATE_ENV: &str = "AEGIS_STATE_DB";
+
+/// Schema migrations; entry `n` moves the database from version `n` to `n + 1`
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE dns_blocklist (
+        domain TEXT PRIMARY KEY,
+        source TEXT NOT NULL,
+        added_at INTEGER NOT NULL
+    );
+    CREATE TABLE bans (
+        address TEXT PRIMARY KEY,
+        reason TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        expires_at INTEGER
+    );
+    CREATE TABLE scheduled_rules (
+        rule_name TEXT PRIMARY KEY,
+        rule TEXT NOT NULL,
+        not_before INTEGER,
+        not_after INTEGER
+    );
+    CREATE TABLE scan_results (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        path TEXT NOT NULL,
+        sha256 TEXT NOT NULL,
+        verdict TEXT NOT NULL,
+        scanned_at INTEGER NOT NULL
+    );
+    CREATE INDEX scan_results_path ON scan_results (path);
+    CREATE TABLE sessions (
+        id TEXT PRIMARY KEY,
+        principal TEXT NOT NULL,
+        device TEXT NOT NULL,
+        issued_at INTEGER NOT NULL,
+        expires_at INTEGER NOT NULL
+    );",
+    "CREATE TABLE audit_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at INTEGER NOT NULL,
+        source TEXT NOT NULL,
+        actor TEXT NOT NULL,
+        role TEXT,
+        peer TEXT NOT NULL,
+        action TEXT NOT NULL,
+        ok INTEGER NOT NULL,
+        detail TEXT NOT NULL
+    );",
+    // Runtime counters rather than state, so they are left out of snapshots
+    "CREATE TABLE metrics (
+        name TEXT NOT NULL,
+        labels TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        help TEXT NOT NULL,
+        value REAL NOT NULL,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (name, labels)
+    );",
+    // Entries written before this migration keep an empty hash and are reported as unchained
+    "ALTER TABLE audit_log ADD COLUMN uid INTEGER;
+    ALTER TABLE audit_log ADD COLUMN args TEXT NOT NULL DEFAULT '[]';
+    ALTER TABLE audit_log ADD COLUMN before_status TEXT;
+    ALTER TABLE audit_log ADD COLUMN after_status TEXT;
+    ALTER TABLE audit_log ADD COLUMN prev_hash TEXT NOT NULL DEFAULT '';
+    ALTER TABLE audit_log ADD COLUMN hash TEXT NOT NULL DEFAULT '';
+    CREATE TRIGGER audit_log_no_update BEFORE UPDATE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;
+    CREATE TRIGGER audit_log_no_delete BEFORE DELETE ON audit_log
+        BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END;",
+    // The fleet agent's rollback floor; left out of snapshots so an import can never lower it
+    "CREATE TABLE fleet_applied (
+        group_name TEXT PRIMARY KEY,
+        version INTEGER NOT NULL,
+        policy TEXT NOT NULL,
+        applied_at INTEGER NOT NULL
+    );",
+    // Manifest sections read by cyberdns, cyberdefender and cyberztna; aegisd rewrites them on every reload
+    "CREATE TABLE subsystem_config (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    );",
+];
+
+/// Schema version this build reads and writes
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+pub fn db_err(e: rusqlite::Error) -> EngineError {
+    EngineError(format!("State store error: {}", e))
+}
+
+/// Domain refused by cyberdns
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockedDomain {
+    pub domain: String,
+    /// Who added it, e.g. `cli` or `manifest`
+    pub source: String,
+    pub added_at: u64,
+}
+
+/// Address banned from reaching this host
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ban {
+    pub address: String,
+    pub reason: String,
+    pub created_at: u64,
+    /// None means the ban never lapses
+    pub expires_at: Option<u64>,
+}
+
+/// Firewall rule that is only in force during a time window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledRule {
+    pub rule: FirewallRule,
+    pub not_before: Option<u64>,
+    pub not_after: Option<u64>,
+}
+
+impl ScheduledRule {
+    pub fn active_at(&self, now: u64) -> bool {
+        self.not_before.is_none_or(|t| now >= t) && self.not_after.is_none_or(|t| now < t)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanResult {
+    pub path: String,
+    pub sha256: String,
+    pub verdict: String,
+    pub scanned_at: u64,
+}
+
+/// Recurring cyberdefender scan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanSchedule {
+    pub name: String,
+    pub paths: Vec<String>,
+    pub interval_minutes: u32,
+}
+
+/// Application published through the cyberztna gateway
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZtnaRoute {
+    pub name: String,
+    pub fqdn: String,
+    /// Private `host:port` the route forwards to
+    pub target: String,
+    #[serde(default)]
+    pub require_mfa: bool,
+}
+
+/// Authenticated identity session issued by cyberid
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub principal: String,
+    pub device: String,
+    pub issued_at: u64,
+    pub expires_at: u64,
+}
+
+/// One action taken on this host through a management interface
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the chain, assigned when the entry is recorded
+    #[serde(default)]
+    pub id: u64,
+    pub at: u64,
+    /// Interface the action came through, e.g. `remote`, `cli` or `unix`
+    pub source: String,
+    /// Authenticated identity, e.g. the client certificate CN or the local user
+    pub actor: String,
+    #[serde(default)]
+    pub uid: Option<u32>,
+    pub role: Option<String>,
+    pub peer: String,
+    pub action: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Firewall state before and after the action, when it changes firewall state
+    #[serde(default)]
+    pub before: Option<String>,
+    #[serde(default)]
+    pub after: Option<String>,
+    pub ok: bool,
+    pub detail: String,
+    /// Hash of the previous entry; empty for the first chained entry
+    #[serde(default)]
+    pub prev_hash: String,
+    /// Empty for entries written before the log was chained
+    #[serde(default)]
+    pub hash: String,
+}
+
+/// Full copy of the store, used by `aegisd state export|import`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub schema_version: u32,
+    pub exported_at: u64,
+    #[serde(default)]
+    pub dns_blocklist: Vec<BlockedDomain>,
+    #[serde(default)]
+    pub bans: Vec<Ban>,
+    #[serde(default)]
+    pub scheduled_rules: Vec<ScheduledRule>,
+    #[serde(default)]
+    pub scan_results: Vec<ScanResult>,
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+    #[serde(default)]
+    pub audit_log: Vec<AuditEntry>,
+}
+
+/// Embedded SQLite store shared by aegisd and the subsystem CLIs
+pub struct StateStore {
+    path: PathBuf,
+    conn: Mutex<Connection>,
+}
+
+impl StateStore {
+    /// Opens the database at `AEGIS_STATE_DB`, or the default path
+    pub fn open_default() -> EngineResult<Self> {
+        let path = std::env::var_os(STATE_ENV).map(PathBuf::from).unwrap_or_else(|| PathBuf::from(DEFAULT_STATE_PATH));
+        Self::open(&path)
+    }
+
+    /// Opens or creates the database and brings its schema up to date
+    pub fn open(path: &Path) -> EngineResult<Self> {
+        let conn = open_database(path, MIGRATIONS)?;
+        Ok(Self { path: path.to_path_buf(), conn: Mutex::new(conn) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn schema_version(&self) -> EngineResult<u32> {
+        self.conn().pragma_query_value(None, "user_version", |row| row.get(0)).map_err(db_err)
+    }
+
+    pub fn dns(&self) -> DnsRepo<'_> {
+        DnsRepo(self)
+    }
+
+    pub fn bans(&self) -> BanRepo<'_> {
+        BanRepo(self)
+    }
+
+    pub fn schedules(&self) -> ScheduleRepo<'_> {
+        ScheduleRepo(self)
+    }
+
+    pub fn scans(&self) -> ScanRepo<'_> {
+        ScanRepo(self)
+    }
+
+    pub fn sessions(&self) -> SessionRepo<'_> {
+        SessionRepo(self)
+    }
+
+    pub fn audit(&self) -> AuditRepo<'_> {
+        AuditRepo(self)
+    }
+
+    pub fn metrics(&self) -> MetricsRepo<'_> {
+        MetricsRepo(self)
+    }
+
+    pub fn fleet(&self) -> FleetRepo<'_> {
+        FleetRepo(self)
+    }
+
+    pub fn config(&self) -> ConfigRepo<'_> {
+        ConfigRepo(self)
+    }
+
+    pub fn export(&self) -> EngineResult<StateSnapshot> {
+        Ok(StateSnapshot {
+            schema_version: self.schema_version()?,
+            exported_at: unix_now(),
+            dns_blocklist: self.dns().list()?,
+            bans: self.bans().list()?,
+            scheduled_rules: self.schedules().list()?,
+            scan_results: self.scans().recent(u32::MAX)?,
+            sessions: self.sessions().list()?,
+            audit_log: self.audit().recent(u32::MAX)?,
+        })
+    }
+
+    /// Replaces the whole store with `snapshot` in one transaction; the append-only audit log is kept as it is
+    pub fn import(&self, snapshot: &StateSnapshot) -> EngineResult<()> {
+        if snapshot.schema_version > SCHEMA_VERSION {
+            return Err(EngineError(format!(
+                "Snapshot uses schema v{} but this build only knows up to v{}",
+                snapshot.schema_version, SCHEMA_VERSION
+            )));
+        }
+        let mut conn = self.conn();
+        let tx = conn.transaction().map_err(db_err)?;
+        for table in ["dns_blocklist", "bans", "scheduled_rules", "scan_results", "sessions"] {
+            tx.execute(&format!("DELETE FROM {}", table), []).map_err(db_err)?;
+        }
+        for d in &snapshot.dns_blocklist {
+            insert_domain(&tx, d)?;
+        }
+        for b in &snapshot.bans {
+            insert_ban(&tx, b)?;
+        }
+        for s in &snapshot.scheduled_rules {
+            insert_schedule(&tx, s)?;
+        }
+        // Oldest first, so row ids keep the original order
+        for r in snapshot.scan_results.iter().rev() {
+            insert_scan(&tx, r)?;
+        }
+        for s in &snapshot.sessions {
+            insert_session(&tx, s)?;
+        }
+        tx.commit().map_err(db_err)
+    }
+}
+
+/// Opens a SQLite database shared between processes and applies `migrations` it has not seen yet
+pub fn open_database(path: &Path, migrations: &[&str]) -> EngineResult<Connection> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(|e| EngineError(format!("Failed to create {}: {}", dir.display(), e)))?;
+    }
+    let mut conn = Connection::open(path).map_err(|e| EngineError(format!("Failed to open database {}: {}", path.display(), e)))?;
+    // Subsystems write from separate processes; wait briefly instead of failing on a busy database
+    conn.busy_timeout(Duration::from_secs(5)).map_err(db_err)?;
+    conn.pragma_update(None, "journal_mode", "WAL").map_err(db_err)?;
+    migrate(&mut conn, migrations).map_err(|e| EngineError(format!("{}: {}", path.display(), e)))?;
+    Ok(conn)
+}
+
+fn migrate(conn: &mut Connection, migrations: &[&str]) -> EngineResult<()> {
+    let known = migrations.len() as u32;
+    let current: u32 = conn.pragma_query_value(None, "user_version", |row| row.get(0)).map_err(db_err)?;
+    if current > known {
+        return Err(EngineError(format!("Database is at schema v{}, newer than this build (v{}); refusing to touch it", current, known)));
+    }
+    for (idx, sql) in migrations.iter().enumerate().skip(current as usize) {
+        let tx = conn.transaction().map_err(db_err)?;
+        tx.execute_batch(sql).map_err(|e| EngineError(format!("Migration to schema v{} failed: {}", idx + 1, e)))?;
+        tx.pragma_update(None, "user_version", idx as u32 + 1).map_err(db_err)?;
+        tx.commit().map_err(db_err)?;
+    }
+    Ok(())
+}
+
+fn insert_domain(tx: &Connection, d: &BlockedDomain) -> EngineResult<()> {
+    tx.execute(
+        "INSERT INTO dns_blocklist (domain, source, added_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT (domain) DO UPDATE SET source = excluded.source",
+        params![d.domain, d.source, d.added_at],
+    )
+    .map(|_| ())
+    .map_err(db_err)
+}
+
+fn insert_ban(tx: &Connection, b: &Ban) -> EngineResult<()> {
+    tx.execute(
+        "INSERT OR REPLACE INTO bans (address, reason, created_at, expires_at) VALUES (?1, ?2, ?3, ?4)",
+        params![b.address, b.reason, b.created_at, b.expires_at],
+    )
+    .map(|_| ())
+    .map_err(db_err)
+}
+
+fn insert_schedule(tx: &Connection, s: &ScheduledRule) -> EngineResult<()> {
+    let rule = serde_json::to_string(&s.rule).map_err(|e| EngineError(format!("Failed to encode rule: {}", e)))?;
+    tx.execute(
+        "INSERT OR REPLACE INTO scheduled_rules (rule_name, rule, not_before, not_after) VALUES (?1, ?2, ?3, ?4)",
+        params![s.rule.name, rule, s.not_before, s.not_after],
+    )
+    .map(|_| ())
+    .map_err(db_err)
+}
+
+fn insert_scan(tx: &Connection, r: &ScanResult) -> EngineResult<()> {
+    tx.execute(
+        "INSERT INTO scan_results (path, sha256, verdict, scanned_at) VALUES (?1, ?2, ?3, ?4)",
+        params![r.path, r.sha256, r.verdict, r.scanned_at],
+    )
+    .map(|_| ())
+    .map_err(db_err)
+}
+
+fn insert_session(tx: &Connection, s: &Session) -> EngineResult<()> {
+    tx.execute(
+        "INSERT OR REPLACE INTO sessions (id, principal, device, issued_at, expires_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![s.id, s.principal, s.device, s.issued_at, s.expires_at],
+    )
+    .map(|_| ())
+    .map_err(db_err)
+}
+
+pub struct DnsRepo<'a>(&'a StateStore);
+
+impl DnsRepo<'_> {
+    pub fn block(&self, domain: &str, source: &str) -> EngineResult<()> {
+        let entry = BlockedDomain { domain: domain.to_ascii_lowercase(), source: source.to_string(), added_at: unix_now() };
+        insert_domain(&self.0.conn(), &entry)
+    }
+
+    /// Returns false when the domain was not blocked
+    pub fn unblock(&self, domain: &str) -> EngineResult<bool> {
+        let removed = self.0.conn().execute("DELETE FROM dns_blocklist WHERE domain = ?1", [domain.to_ascii_lowercase()]).map_err(db_err)?;
+        Ok(removed > 0)
+    }
+
+    /// Makes the entries owned by `source` exactly `domains`, leaving other sources alone
+    pub fn replace_source(&self, source: &str, domains: &[String]) -> EngineResult<()> {
+        let mut conn = self.0.conn();
+        let tx: Transaction<'_> = conn.transaction().map_err(db_err)?;
+        tx.execute("DELETE FROM dns_blocklist WHERE source = ?1", [source]).map_err(db_err)?;
+        let now = unix_now();
+        for domain in domains {
+            insert_domain(&tx, &BlockedDomain { domain: domain.to_ascii_lowercase(), source: source.to_string(), added_at: now })?;
+        }
+        tx.commit().map_err(db_err)
+    }
+
+    /// Whether `domain` or one of its parent domains is blocked
+    pub fn is_blocked(&self, domain: &str) -> EngineResult<bool> {
+        let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+        let conn = self.0.conn();
+        let mut stmt = conn.prepare_cached("SELECT 1 FROM dns_blocklist WHERE domain = ?1").map_err(db_err)?;
+        let mut candidate = domain.as_str();
+        loop {
+            if stmt.exists([candidate]).map_err(db_err)? {
+                return Ok(true);
+            }
+            match candidate.split_once('.') {
+                Some((_, parent)) if parent.contains('.') => candidate = parent,
+                _ => return Ok(false),
+            }
+        }
+    }
+
+    pub fn count(&self) -> EngineResult<u64> {
+        self.0.conn().query_row("SELECT COUNT(*) FROM dns_blocklist", [], |row| row.get(0)).map_err(db_err)
+    }
+
+    pub fn list(&self) -> EngineResult<Vec<BlockedDomain>> {
+        let conn = self.0.conn();
+        let mut stmt = conn.prepare("SELECT domain, source, added_at FROM dns_blocklist ORDER BY domain").map_err(db_err)?;
+        let rows = stmt
+            .query_map([], |row| Ok(BlockedDomain { domain: row.get(0)?, source: row.get(1)?, added_at: row.get(2)? }))
+            .map_err(db_err)?;
+        rows.collect::<Result<_, _>>().map_err(db_err)
+    }
+}
+
+pub struct BanRepo<'a>(&'a StateStore);
+
+impl BanRepo<'_> {
+    pub fn ban(&self, address: &str, reason: &str, duration: Option<Duration>) -> EngineResult<Ban> {
+        let now = unix_now();
+        let ban = Ban {
+            address: address.to_string(),
+            reason: reason.to_string(),
+            created_at: now,
+            expires_at: duration.map(|d| now + d.as_secs()),
+        };
+        insert_ban(&self.0.conn(), &ban)?;
+        Ok(ban)
+    }
+
+    pub fn lift(&self, address: &str) -> EngineResult<bool> {
+        let removed = self.0.conn().execute("DELETE FROM bans WHERE address = ?1", [address]).map_err(db_err)?;
+        Ok(removed > 0)
+    }
+
+    /// Bans still in force at `now`
+    pub fn active(&self, now: u64) -> EngineResult<Vec<Ban>> {
+        Ok(self.list()?.into_iter().filter(|b| b.expires_at.is_none_or(|t| t > now)).collect())
+    }
+
+    /// Drops lapsed bans and returns how many were removed
+    pub fn purge_expired(&self, now: u64) -> EngineResult<usize> {
+        self.0.conn().execute("DELETE FROM bans WHERE expires_at IS NOT NULL AND expires_at <= ?1", [now]).map_err(db_err)
+    }
+
+    pub fn list(&self) -> EngineResult<Vec<Ban>> {
+        let conn = self.0.conn();
+        let mut stmt = conn.prepare("SELECT address, reason, created_at, expires_at FROM bans ORDER BY created_at").map_err(db_err)?;
+        let rows = stmt
+            .query_map([], |row| Ok(Ban { address: row.get(0)?, reason: row.get(1)?, created_at: row.get(2)?, expires_at: row.get(3)? }))
+            .map_err(db_err)?;
+        rows.collect::<Result<_, _>>().map_err(db_err)
+    }
+}
+
+pub struct ScheduleRepo<'a>(&'a StateStore);
+
+impl ScheduleRepo<'_> {
+    pub fn save(&self, schedule: &ScheduledRule) -> EngineResult<()> {
+        insert_schedule(&self.0.conn(), schedule)
+    }
+
+    pub fn remove(&self, rule_name: &str) -> EngineResult<bool> {
+        let removed = self.0.conn().execute("DELETE FROM scheduled_rules WHERE rule_name = ?1", [rule_name]).map_err(db_err)?;
+        Ok(removed > 0)
+    }
+
+    pub fn list(&self) -> EngineResult<Vec<ScheduledRule>> {
+        let conn = self.0.conn();
+        let mut stmt = conn.prepare("SELECT rule, not_before, not_after FROM scheduled_rules ORDER BY rule_name").map_err(db_err)?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?, row.get(2)?)))
+            .map_err(db_err)?;
+        let mut schedules = Vec::new();
+        for row in rows {
+            let (raw, not_before, not_after) = row.map_err(db_err)?;
+            let rule = serde_json::from_str(&raw).map_err(|e| EngineError(format!("Corrupt scheduled rule in state store: {}", e)))?;
+            schedules.push(ScheduledRule { rule, not_before, not_after });
+        }
+        Ok(schedules)
+    }
+}
+
+pub struct ScanRepo<'a>(&'a StateStore);
+
+impl ScanRepo<'_> {
+    pub fn record(&self, result: &ScanResult) -> EngineResult<()> {
+        insert_scan(&self.0.conn(), result)
+    }
+
+    /// Most recent results first
+    pub fn recent(&self, limit: u32) -> EngineResult<Vec<ScanResult>> {
+        let conn = self.0.conn();
+        let mut stmt = conn
+            .prepare("SELECT path, sha256, verdict, scanned_at FROM scan_results ORDER BY id DESC LIMIT ?1")
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map([limit], |row| Ok(ScanResult { path: row.get(0)?, sha256: row.get(1)?, verdict: row.get(2)?, scanned_at: row.get(3)? }))
+            .map_err(db_err)?;
+        rows.collect::<Result<_, _>>().map_err(db_err)
+    }
+
+    /// Last result for `path`, if it was ever scanned
+    pub fn last_for(&self, path: &str) -> EngineResult<Option<ScanResult>> {
+        self.0
+            .conn()
+            .query_row(
+                "SELECT path, sha256, verdict, scanned_at FROM scan_results WHERE path = ?1 ORDER BY id DESC LIMIT 1",
+                [path],
+                |row| Ok(ScanResult { path: row.get(0)?, sha256: row.get(1)?, verdict: row.get(2)?, scanned_at: row.get(3)? }),
+            )
+            .optional()
+            .map_err(db_err)
+    }
+}
+
+pub struct SessionRepo<'a>(&'a StateStore);
+
+impl SessionRepo<'_> {
+    pub fn open(&self, session: &Session) -> EngineResult<()> {
+        insert_session(&self.0.conn(), session)
+    }
+
+    pub fn close(&self, id: &str) -> EngineResult<bool> {
+        let removed = self.0.conn().execute("DELETE FROM sessions WHERE id = ?1", [id]).map_err(db_err)?;
+        Ok(removed > 0)
+    }
+
+    /// Sessions that have not expired at `now`
+    pub fn active(&self, now: u64) -> EngineResult<Vec<Session>> {
+        Ok(self.list()?.into_iter().filter(|s| s.expires_at > now).collect())
+    }
+
+    pub fn list(&self) -> EngineResult<Vec<Session>> {
+        let conn = self.0.conn();
+        let mut stmt = conn
+            .prepare("SELECT id, principal, device, issued_at, expires_at FROM sessions ORDER BY issued_at")
+            .map_err(db_err)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(Session { id: row.get(0)?, principal: row.get(1)?, device: row.get(2)?, issued_at: row.get(3)?, expires_at: row.get(4)? })
+            })
+            .map_err(db_err)?;
+        rows.collect::<Result<_, _>>().map_err(db_err)
+    }
+}
+
+pub struct AuditRepo<'a>(&'a StateStore);
+
+const AUDIT_COLUMNS: &str = "id, at, source, actor, uid, role, peer, action, args, before_status, after_status, ok, detail, prev_hash, hash";
+
+fn audit_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<(AuditEntry, String)> {
+    let entry = AuditEntry {
+        id: row.get(0)?,
+        at: row.get(1)?,
+        source: row.get(2)?,
+        actor: row.get(3)?,
+        uid: row.get(4)?,
+        role: row.get(5)?,
+        peer: row.get(6)?,
+        action: row.get(7)?,
+        args: Vec::new(),
+        before: row.get(9)?,
+        after: row.get(10)?,
+        ok: row.get(11)?,
+        detail: row.get(12)?,
+        prev_hash: row.get(13)?,
+        hash: row.get(14)?,
+    };
+    Ok((entry, row.get(8)?))
+}
+
+impl AuditRepo<'_> {
+    /// Appends `entry` to the hash chain and returns it as stored
+    pub fn record(&self, entry: &AuditEntry) -> EngineResult<AuditEntry> {
+        let mut conn = self.0.conn();
+        // Take the write lock up front so concurrent writers in other processes cannot fork the chain
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate).map_err(db_err)?;
+        let prev_hash: String =
+            tx.query_row("SELECT hash FROM audit_log ORDER BY id DESC LIMIT 1", [], |row| row.get(0)).optional().map_err(db_err)?.unwrap_or_default();
+        let mut sealed = AuditEntry { id: last_audit_id(&tx)? + 1, prev_hash, hash: String::new(), ..entry.clone() };
+        sealed.hash = audit::entry_hash(&sealed);
+        let args = serde_json::to_string(&sealed.args).map_err(|e| EngineError(format!("Failed to encode arguments: {}", e)))?;
+        tx.execute(
+            &format!("INSERT INTO audit_log ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)", AUDIT_COLUMNS),
+            params![
+                sealed.id,
+                sealed.at,
+                sealed.source,
+                sealed.actor,
+                sealed.uid,
+                sealed.role,
+                sealed.peer,
+                sealed.action,
+                args,
+                sealed.before,
+                sealed.after,
+                sealed.ok,
+                sealed.detail,
+                sealed.prev_hash,
+                sealed.hash
+            ],
+        )
+        .map_err(db_err)?;
+        tx.commit().map_err(db_err)?;
+        Ok(sealed)
+    }
+
+    fn query(&self, sql: &str, limit: u32) -> EngineResult<Vec<AuditEntry>> {
+        let conn = self.0.conn();
+        let mut stmt = conn.prepare(sql).map_err(db_err)?;
+        let rows = stmt.query_map([limit], audit_row).map_err(db_err)?;
+        rows.map(|row| {
+            let (mut entry, args) = row.map_err(db_err)?;
+            entry.args = serde_json::from_str(&args).map_err(|e| EngineError(format!("Corrupt arguments in audit entry {}: {}", entry.id, e)))?;
+            Ok(entry)
+        })
+        .collect()
+    }
+
+    /// Most recent entries first
+    pub fn recent(&self, limit: u32) -> EngineResult<Vec<AuditEntry>> {
+        self.query(&format!("SELECT {} FROM audit_log ORDER BY id DESC LIMIT ?1", AUDIT_COLUMNS), limit)
+    }
+
+    /// Walks the whole chain, oldest entry first
+    pub fn verify(&self) -> EngineResult<ChainReport> {
+        let entries = self.query(&format!("SELECT {} FROM audit_log ORDER BY id ASC LIMIT ?1", AUDIT_COLUMNS), u32::MAX)?;
+        let last_id = last_audit_id(&self.0.conn())?;
+        Ok(audit::verify_chain(&entries, last_id))
+    }
+}
+
+/// Highest id ever handed out; AUTOINCREMENT keeps it in sqlite_sequence even after rows are removed
+fn last_audit_id(conn: &Connection) -> EngineResult<u64> {
+    let sequence: Option<u64> =
+        conn.query_row("SELECT seq FROM sqlite_sequence WHERE name = 'audit_log'", [], |row| row.get(0)).optional().map_err(db_err)?;
+    let max: Option<u64> = conn.query_row("SELECT MAX(id) FROM audit_log", [], |row| row.get(0)).map_err(db_err)?;
+    Ok(sequence.unwrap_or(0).max(max.unwrap_or(0)))
+}
+
+pub struct MetricsRepo<'a>(&'a StateStore);
+
+impl MetricsRepo<'_> {
+    fn upsert(&self, name: &str, help: &str, kind: MetricKind, labels: &Labels, value: f64, accumulate: bool) -> EngineResult<()> {
+        let labels = serde_json::to_string(labels).map_err(|e| EngineError(format!("Failed to encode labels: {}", e)))?;
+        let sql = match accumulate {
+            true => "INSERT INTO metrics (name, labels, kind, help, value, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                     ON CONFLICT (name, labels) DO UPDATE SET value = value + excluded.value, updated_at = excluded.updated_at",
+            false => "INSERT INTO metrics (name, labels, kind, help, value, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                      ON CONFLICT (name, labels) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+        };
+        self.0.conn().execute(sql, params![name, labels, kind.as_str(), help, value, unix_now()]).map_err(db_err)?;
+        Ok(())
+    }
+
+    /// Adds `by` to a counter shared by every process using the store
+    pub fn add(&self, name: &str, help: &str, labels: &Labels, by: f64) -> EngineResult<()> {
+        self.upsert(name, help, MetricKind::Counter, labels, by, true)
+    }
+
+    pub fn set(&self, name: &str, help: &str, labels: &Labels, value: f64) -> EngineResult<()> {
+        self.upsert(name, help, MetricKind::Gauge, labels, value, false)
+    }
+
+    /// Records that `subsystem` is serving; `stopping` marks a clean shutdown so it is not reported as missing
+    pub fn heartbeat(&self, subsystem: &str, stopping: bool) -> EngineResult<()> {
+        let at = if stopping { 0.0 } else { unix_now() as f64 };
+        self.set(HEARTBEAT_METRIC, "Unix time of the subsystem's last heartbeat, 0 after a clean stop", &metrics::labels(&[("subsystem", subsystem)]), at)
+    }
+
+    /// Unix time of the last heartbeat, `Some(0)` after a clean stop, None if the subsystem never ran
+    pub fn last_heartbeat(&self, subsystem: &str) -> EngineResult<Option<u64>> {
+        Ok(self.get(HEARTBEAT_METRIC, &metrics::labels(&[("subsystem", subsystem)]))?.map(|at| at as u64))
+    }
+
+    pub fn get(&self, name: &str, labels: &Labels) -> EngineResult<Option<f64>> {
+        let labels = serde_json::to_string(labels).map_err(|e| EngineError(format!("Failed to encode labels: {}", e)))?;
+        self.0
+            .conn()
+            .query_row("SELECT value FROM metrics WHERE name = ?1 AND labels = ?2", params![name, labels], |row| row.get(0))
+            .optional()
+            .map_err(db_err)
+    }
+
+    pub fn list(&self) -> EngineResult<Vec<StoredMetric>> {
+        let conn = self.0.conn();
+        let mut stmt = conn.prepare("SELECT name, help, kind, labels, value, updated_at FROM metrics ORDER BY name, labels").map_err(db_err)?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get(4)?, row.get(5)?))
+            })
+            .map_err(db_err)?;
+        rows.map(|row| {
+            let (name, help, kind, labels, value, updated_at) = row.map_err(db_err)?;
+            let kind = serde_json::from_value(serde_json::Value::String(kind)).map_err(|e| EngineError(format!("Corrupt metric kind: {}", e)))?;
+            let labels = serde_json::from_str(&labels).map_err(|e| EngineError(format!("Corrupt metric labels: {}", e)))?;
+            Ok(StoredMetric { name, help, kind, labels, value, updated_at })
+        })
+        .collect()
+    }
+}
+
+pub struct FleetRepo<'a>(&'a StateStore);
+
+impl FleetRepo<'_> {
+    /// Release version and policy last applied for `group`
+    pub fn applied(&self, group: &str) -> EngineResult<Option<(u64, FirewallPolicy)>> {
+        let row: Option<(u64, String)> = self
+            .0
+            .conn()
+            .query_row("SELECT version, policy FROM fleet_applied WHERE group_name = ?1", params![group], |row| Ok((row.get(0)?, row.get(1)?)))
+            .optional()
+            .map_err(db_err)?;
+        row.map(|(version, policy)| {
+            let policy = serde_json::from_str(&policy).map_err(|e| EngineError(format!("Corrupt applied fleet policy: {}", e)))?;
+            Ok((version, policy))
+        })
+        .transpose()
+    }
+
+    /// Records an applied release; a version at or below the recorded one is left alone
+    pub fn record_applied(&self, group: &str, version: u64, policy: &FirewallPolicy) -> EngineResult<()> {
+        let policy = serde_json::to_string(policy).map_err(|e| EngineError(format!("Failed to encode policy: {}", e)))?;
+        self.0
+            .conn()
+            .execute(
+                "INSERT INTO fleet_applied (group_name, version, policy, applied_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (group_name) DO UPDATE SET version = excluded.version, policy = excluded.policy, applied_at = excluded.applied_at
+                 WHERE excluded.version > fleet_applied.version",
+                params![group, version, policy, unix_now()],
+            )
+            .map_err(db_err)?;
+        Ok(())
+    }
+}
+
+pub struct ConfigRepo<'a>(&'a StateStore);
+
+impl ConfigRepo<'_> {
+    fn get<T: DeserializeOwned + Default>(&self, key: &str) -> EngineResult<T> {
+        let raw: Option<String> = self
+            .0
+            .conn()
+            .query_row("SELECT value FROM subsystem_config WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()
+            .map_err(db_err)?;
+        match raw {
+            Some(raw) => serde_json::from_str(&raw).map_err(|e| EngineError(format!("Corrupt '{}' configuration: {}", key, e))),
+            None => Ok(T::default()),
+        }
+    }
+
+    fn set<T: Serialize + ?Sized>(&self, key: &str, value: &T) -> EngineResult<()> {
+        let raw = serde_json::to_string(value).map_err(|e| EngineError(format!("Failed to encode '{}' configuration: {}", key, e)))?;
+        self.0
+            .conn()
+            .execute("INSERT OR REPLACE INTO subsystem_config (key, value, updated_at) VALUES (?1, ?2, ?3)", params![key, raw, unix_now()])
+            .map_err(db_err)?;
+        Ok(())
+    }
+
+    /// DoH resolver URLs for cyberdns, tried in order; empty means the built-in resolver
+    pub fn dns_resolvers(&self) -> EngineResult<Vec<String>> {
+        self.get("dns_resolvers")
+    }
+
+    pub fn set_dns_resolvers(&self, resolvers: &[String]) -> EngineResult<()> {
+        self.set("dns_resolvers", resolvers)
+    }
+
+    pub fn scan_schedules(&self) -> EngineResult<Vec<ScanSchedule>> {
+        self.get("scan_schedules")
+    }
+
+    pub fn set_scan_schedules(&self, schedules: &[ScanSchedule]) -> EngineResult<()> {
+        self.set("scan_schedules", schedules)
+    }
+
+    pub fn ztna_routes(&self) -> EngineResult<Vec<ZtnaRoute>> {
+        self.get("ztna_routes")
+    }
+
+    pub fn set_ztna_routes(&self, routes: &[ZtnaRoute]) -> EngineResult<()> {
+        self.set("ztna_routes", routes)
+    }
+}
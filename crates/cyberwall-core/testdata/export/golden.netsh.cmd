@@ -0,0 +1,14 @@
+@echo off
+REM Generated by cyberwall from policy 'golden' version 7
+netsh advfirewall firewall add rule name="ssh from management" dir=in action=allow enable=yes profile=any protocol=TCP localport=22 remoteport=any localip=any remoteip=10.0.0.0/8,fd00::/8
+netsh advfirewall firewall add rule name="web" dir=in action=allow enable=yes profile=any protocol=TCP localport=80,443,8000-8100 remoteport=any localip=any remoteip=any
+netsh advfirewall firewall add rule name="dns any transport" dir=out action=allow enable=yes profile=any protocol=TCP localport=any remoteport=53 localip=any remoteip=192.0.2.53,2001:db8::53
+netsh advfirewall firewall add rule name="dns any transport" dir=out action=allow enable=yes profile=any protocol=UDP localport=any remoteport=53 localip=any remoteip=192.0.2.53,2001:db8::53
+netsh advfirewall firewall add rule name="ping v4" dir=in action=allow enable=yes profile=any protocol=ICMPv4 localip=any remoteip=0.0.0.0/0
+netsh advfirewall firewall add rule name="ping v6" dir=in action=allow enable=yes profile=any protocol=ICMPv6 localip=any remoteip=any
+netsh advfirewall firewall add rule name="no smb on public" dir=in action=block enable=yes profile=public protocol=TCP localport=445 remoteport=any localip=any remoteip=any
+netsh advfirewall firewall add rule name="block bad v6 net" dir=out action=block enable=yes profile=any protocol=any localip=2001:db8::10 remoteip=2001:db8:bad::/48
+netsh advfirewall firewall add rule name="ntp <client> & 'sync'" dir=out action=allow enable=yes profile=any protocol=UDP localport=any remoteport=123 localip=any remoteip=any program="C:\Windows\System32\w32tm.exe"
+netsh advfirewall firewall add rule name="disabled telnet" dir=in action=block enable=no profile=any protocol=any localip=any remoteip=any
+REM skipped NAT rule 'forward web': Windows Firewall has no NAT support
+REM skipped NAT rule 'masquerade lan': Windows Firewall has no NAT support
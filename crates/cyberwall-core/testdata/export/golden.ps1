@@ -0,0 +1,15 @@
+# Generated by cyberwall from policy 'golden' version 7
+#Requires -RunAsAdministrator
+Remove-NetFirewallRule -Group 'cyberwall:golden' -ErrorAction SilentlyContinue
+New-NetFirewallRule -DisplayName 'ssh from management' -Group 'cyberwall:golden' -Direction Inbound -Action Allow -Enabled True -Profile Any -Protocol TCP -LocalPort '22' -RemoteAddress '10.0.0.0/8','fd00::/8' | Out-Null
+New-NetFirewallRule -DisplayName 'web' -Group 'cyberwall:golden' -Direction Inbound -Action Allow -Enabled True -Profile Any -Protocol TCP -LocalPort '80','443','8000-8100' | Out-Null
+New-NetFirewallRule -DisplayName 'dns any transport' -Group 'cyberwall:golden' -Direction Outbound -Action Allow -Enabled True -Profile Any -Protocol TCP -RemotePort '53' -RemoteAddress '192.0.2.53','2001:db8::53' | Out-Null
+New-NetFirewallRule -DisplayName 'dns any transport' -Group 'cyberwall:golden' -Direction Outbound -Action Allow -Enabled True -Profile Any -Protocol UDP -RemotePort '53' -RemoteAddress '192.0.2.53','2001:db8::53' | Out-Null
+New-NetFirewallRule -DisplayName 'ping v4' -Group 'cyberwall:golden' -Direction Inbound -Action Allow -Enabled True -Profile Any -Protocol ICMPv4 -RemoteAddress '0.0.0.0/0' | Out-Null
+New-NetFirewallRule -DisplayName 'ping v6' -Group 'cyberwall:golden' -Direction Inbound -Action Allow -Enabled True -Profile Any -Protocol ICMPv6 | Out-Null
+New-NetFirewallRule -DisplayName 'no smb on public' -Group 'cyberwall:golden' -Direction Inbound -Action Block -Enabled True -Profile public -Protocol TCP -LocalPort '445' | Out-Null
+New-NetFirewallRule -DisplayName 'block bad v6 net' -Group 'cyberwall:golden' -Direction Outbound -Action Block -Enabled True -Profile Any -Protocol Any -LocalAddress '2001:db8::10' -RemoteAddress '2001:db8:bad::/48' | Out-Null
+New-NetFirewallRule -DisplayName 'ntp <client> & "sync"' -Group 'cyberwall:golden' -Direction Outbound -Action Allow -Enabled True -Profile Any -Protocol UDP -RemotePort '123' -Program 'C:\Windows\System32\w32tm.exe' | Out-Null
+New-NetFirewallRule -DisplayName 'disabled telnet' -Group 'cyberwall:golden' -Direction Inbound -Action Block -Enabled False -Profile Any -Protocol Any | Out-Null
+# skipped NAT rule 'forward web': Windows Firewall has no NAT support
+# skipped NAT rule 'masquerade lan': Windows Firewall has no NAT support
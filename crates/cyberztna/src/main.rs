@@ -1,5 +1,6 @@
 use clap::{Parser, Subcommand};
 use colored::*;
+use cyberwall_core::state::StateStore;
 
 #[derive(Parser)]
 #[command(name = "cyberztna")]
@@ -45,17 +46,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("{}", "=========================================================".cyan());
             println!("{}", "        ZEROTRUST SASE MICRO-SEGMENTATION ROUTES         ".bold().green());
             println!("{}", "=========================================================".cyan());
-            let routes = vec![
-                ("S2O Internal Jira", "jira.internal.split2ops.com", "10.220.0.10:8080", "mTLS Required"),
-                ("S2O Production DB", "db.prod.split2ops.com", "10.220.0.1:5432", "mTLS + PAM Approval"),
-                ("S2O SecOps Vault", "vault.split2ops.com", "10.220.0.3:8200", "mTLS + FIDO2 MFA"),
-            ];
-
-            for (idx, (name, fqdn, target, policy)) in routes.iter().enumerate() {
-                println!("{}. App Target : {}", idx + 1, name.bold());
-                println!("   Public FQDN: {}", fqdn.cyan());
-                println!("   Private IP : {}", target.yellow());
-                println!("   Access Policy: {}", policy.green().bold());
+            // Routes come from the enterprise manifest, delivered by aegisd through the state store
+            let routes = StateStore::open_default()?.config().ztna_routes()?;
+            if routes.is_empty() {
+                println!("{}", "No routes published; add ztna_routes to the aegisd manifest.".yellow());
+            }
+            for (idx, route) in routes.iter().enumerate() {
+                println!("{}. App Target : {}", idx + 1, route.name.bold());
+                println!("   Public FQDN: {}", route.fqdn.cyan());
+                println!("   Private IP : {}", route.target.yellow());
+                println!("   Access Policy: {}", if route.require_mfa { "mTLS + MFA".green().bold() } else { "mTLS Required".green().bold() });
                 println!("{}", "---------------------------------------------------------".cyan());
             }
         }